@@ -5,24 +5,27 @@ use std::time::Duration;
 use aide::axum::ApiRouter;
 use aide::axum::routing::get_with;
 use aide::transform::TransformOperation;
-use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::{Extension, Router};
 use proof_api_util::api::{
-    ApiRouterExt, JSON_HEADERS_CACHE_1W, JSON_HEADERS_DONT_CACHE, OpenApiConfig, get_version,
-    prepare_open_api,
+    ApiRouterExt, JSON_HEADERS_CACHE_1W, JSON_HEADERS_DONT_CACHE, OpenApiConfig, ProofChainQuery,
+    ProofChainResponse, TxProofChainQuery, get_version, prepare_open_api,
 };
-use proof_api_util::serde_helpers::TonAddr;
+use proof_api_util::block::{ProofChainFormat, ProofChainStats, ProofKind, TxContent};
+use proof_api_util::serde_helpers::{MsgHash, TonAddr, TxHash};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tower_http::timeout::TimeoutLayer;
 use tycho_types::boc::Boc;
+use tycho_types::cell::{Cell, HashBytes};
+use tycho_types::models::StdAddr;
 use tycho_util::sync::rayon_run;
 
-use crate::storage::ProofStorage;
+use crate::storage::{AccountTx, ProofStorage, TxTrace};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiConfig {
@@ -55,6 +58,53 @@ pub fn build_api(config: &ApiConfig, proofs: ProofStorage) -> Router {
             "/v1/proof_chain/{address}/{lt}",
             get_with(get_proof_chain_v1, get_proof_chain_v1_docs),
         )
+        .api_route(
+            "/v1/proof_chains/{address}",
+            get_with(get_proof_chains_v1, get_proof_chains_v1_docs),
+        )
+        .api_route(
+            "/v1/proof_chain_by_hash/{hash}",
+            get_with(get_proof_chain_by_hash_v1, get_proof_chain_by_hash_v1_docs),
+        )
+        .api_route(
+            "/v1/proof_chain_by_in_msg/{msg_hash}",
+            get_with(
+                get_proof_chain_by_in_msg_v1,
+                get_proof_chain_by_in_msg_v1_docs,
+            ),
+        )
+        .api_route(
+            "/v1/transactions/{address}",
+            get_with(get_transactions_v1, get_transactions_v1_docs),
+        )
+        .api_route(
+            "/v1/out_msg_proof_chain/{address}/{lt}/{msg_hash}",
+            get_with(get_out_msg_proof_chain_v1, get_out_msg_proof_chain_v1_docs),
+        )
+        .api_route(
+            "/v1/state_proof_chain/{address}",
+            get_with(get_state_proof_chain_v1, get_state_proof_chain_v1_docs),
+        )
+        .api_route(
+            "/v1/trace/{address}/{lt}",
+            get_with(get_trace_v1, get_trace_v1_docs),
+        )
+        .api_route(
+            "/v1/config_proof/{param}",
+            get_with(get_config_proof_v1, get_config_proof_v1_docs),
+        )
+        .api_route(
+            "/v2/proof_chain/{address}/{lt}",
+            get_with(get_proof_chain_v2, get_proof_chain_v2_docs),
+        )
+        .api_route(
+            "/v2/out_msg_proof_chain/{address}/{lt}/{msg_hash}",
+            get_with(get_out_msg_proof_chain_v2, get_out_msg_proof_chain_v2_docs),
+        )
+        .api_route(
+            "/v2/state_proof_chain/{address}",
+            get_with(get_state_proof_chain_v2, get_state_proof_chain_v2_docs),
+        )
         .with_docs()
         .layer(
             ServiceBuilder::new()
@@ -71,23 +121,290 @@ pub fn build_api(config: &ApiConfig, proofs: ProofStorage) -> Router {
 
 // === V1 Routes ===
 
-/// Block proof chain for an existing transaction.
+async fn get_proof_chain_v1(
+    State(state): State<ProofStorage>,
+    Path((TonAddr(address), lt)): Path<(TonAddr, u64)>,
+    Query(query): Query<TxProofChainQuery>,
+) -> Response {
+    tx_proof_chain_response(&state, &address, lt, query, ProofChainFormat::V1).await
+}
+
+fn get_proof_chain_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chain")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Batch proof chains request params.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofChainsQuery {
+    /// Comma-separated logical times of account transactions (max 64).
+    ///
+    /// NOTE: All transactions belong to the account from the path,
+    /// transactions of other accounts must be requested separately.
+    pub lts: String,
+}
+
+/// Proof chains for a group of transactions of a single account.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct ProofChainResponse {
+pub struct ProofChainsResponse {
+    /// Proof chains of the requested account transactions grouped by blocks
+    /// (unknown transactions are skipped).
+    pub proof_chains: Vec<BatchProofChainResponse>,
+}
+
+/// Proof chain shared by transactions from the same block.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProofChainResponse {
+    /// Logical times of transactions in the proof chain.
+    pub lts: Vec<u64>,
     /// Base64 encoded BOC with the proof chain.
     pub proof_chain: String,
 }
 
-async fn get_proof_chain_v1(
+async fn get_proof_chains_v1(
     State(state): State<ProofStorage>,
-    Path((TonAddr(address), lt)): Path<(TonAddr, u64)>,
+    Path(TonAddr(address)): Path<TonAddr>,
+    Query(query): Query<ProofChainsQuery>,
+) -> Response {
+    const MAX_TXS: usize = 64;
+
+    let lts = query
+        .lts
+        .split(',')
+        .map(|lt| lt.trim().parse::<u64>())
+        .collect::<Result<Vec<_>, _>>();
+    let transactions = match lts {
+        Ok(lts) if lts.len() <= MAX_TXS => lts
+            .into_iter()
+            .map(|lt| (address.clone(), lt))
+            .collect::<Vec<_>>(),
+        _ => {
+            return res_error(ErrorResponse::BadRequest {
+                message: "invalid lts list",
+            });
+        }
+    };
+
+    match state.build_proofs(&transactions).await {
+        Ok(batch) if batch.is_empty() => res_error(ErrorResponse::NotFound {
+            message: "tx not found",
+        }),
+        Ok(batch) => {
+            rayon_run(move || {
+                let data = serde_json::to_vec(&ProofChainsResponse {
+                    proof_chains: batch
+                        .into_iter()
+                        .map(|item| BatchProofChainResponse {
+                            lts: item.transactions.into_iter().map(|(_, lt)| lt).collect(),
+                            proof_chain: Boc::encode_base64(item.proof_chain),
+                        })
+                        .collect(),
+                })
+                .unwrap();
+
+                // NOTE: Unknown transactions are skipped and can appear later.
+                (JSON_HEADERS_DONT_CACHE, axum::body::Bytes::from(data)).into_response()
+            })
+            .await
+        }
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn get_proof_chains_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chains for multiple transactions of a single account")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<ProofChainsResponse>>()
+        .response::<400, axum::Json<ErrorResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Block proof chain for a transaction found by its hash
+/// or by its inbound message hash.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofChainByHashResponse {
+    /// Transaction account address.
+    pub address: String,
+    /// Transaction logical time.
+    pub lt: u64,
+    /// Base64 encoded BOC with the proof chain.
+    pub proof_chain: String,
+    /// Proof chain size info.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ProofChainStats>,
+}
+
+async fn get_proof_chain_by_hash_v1(
+    State(state): State<ProofStorage>,
+    Path(TxHash(tx_hash)): Path<TxHash>,
+    Query(query): Query<TxProofChainQuery>,
 ) -> Response {
-    match state.build_proof(&address, lt).await {
+    let found = state.find_tx_by_hash(&tx_hash);
+    found_tx_proof_chain_response(&state, found, query).await
+}
+
+fn get_proof_chain_by_hash_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chain for a transaction by its hash")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<ProofChainByHashResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+async fn get_proof_chain_by_in_msg_v1(
+    State(state): State<ProofStorage>,
+    Path(MsgHash(msg_hash)): Path<MsgHash>,
+    Query(query): Query<TxProofChainQuery>,
+) -> Response {
+    let found = state.find_tx_by_in_msg(&msg_hash);
+    found_tx_proof_chain_response(&state, found, query).await
+}
+
+fn get_proof_chain_by_in_msg_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description(
+        "Build proof chain for a destination transaction of the message. \
+        Only transactions ingested after the inbound message index was added are indexed.",
+    )
+    .tag("proof-api-l2")
+    .response::<200, axum::Json<ProofChainByHashResponse>>()
+    .response::<404, ()>()
+    .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Account transactions list request params.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsQuery {
+    /// Min transaction logical time (inclusive).
+    #[serde(default, alias = "from_lt")]
+    pub from_lt: u64,
+    /// Max number of transactions in the response.
+    /// Default: `100`, max: `1000`.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Account transactions with available proofs.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsResponse {
+    /// Transactions in ascending order.
+    pub transactions: Vec<TransactionResponse>,
+}
+
+/// Transaction with an available proof.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionResponse {
+    /// Transaction logical time.
+    pub lt: u64,
+    /// Transaction hash as hex (if known).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    /// Full id of the block with the transaction.
+    pub block_id: String,
+    /// Seqno of the masterchain block which references the transaction block.
+    pub mc_seqno: u32,
+}
+
+impl From<AccountTx> for TransactionResponse {
+    fn from(tx: AccountTx) -> Self {
+        Self {
+            lt: tx.lt,
+            tx_hash: tx.tx_hash.map(|hash| hash.to_string()),
+            block_id: tx.block_id.to_string(),
+            mc_seqno: tx.mc_seqno,
+        }
+    }
+}
+
+async fn get_transactions_v1(
+    State(state): State<ProofStorage>,
+    Path(TonAddr(address)): Path<TonAddr>,
+    Query(query): Query<TransactionsQuery>,
+) -> Response {
+    const DEFAULT_LIMIT: usize = 100;
+    const MAX_LIMIT: usize = 1000;
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    match state
+        .list_account_transactions(&address, query.from_lt, limit)
+        .await
+    {
+        Ok(transactions) => {
+            rayon_run(move || {
+                let data = serde_json::to_vec(&TransactionsResponse {
+                    transactions: transactions.into_iter().map(Into::into).collect(),
+                })
+                .unwrap();
+
+                // NOTE: New transactions can appear and old ones can be removed.
+                (JSON_HEADERS_DONT_CACHE, axum::body::Bytes::from(data)).into_response()
+            })
+            .await
+        }
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn get_transactions_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("List account transactions with available proofs")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<TransactionsResponse>>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Builds a proof chain for the transaction found in one of the indices.
+async fn found_tx_proof_chain_response(
+    state: &ProofStorage,
+    found: anyhow::Result<Option<(StdAddr, u64)>>,
+    query: TxProofChainQuery,
+) -> Response {
+    let (address, lt) = match found {
+        Ok(Some(tx)) => tx,
+        Ok(None) => {
+            return res_error(ErrorResponse::NotFound {
+                message: "tx not found",
+            });
+        }
+        Err(e) => {
+            return res_error(ErrorResponse::Internal {
+                message: e.to_string(),
+            });
+        }
+    };
+
+    match state
+        .build_proof(&address, lt, query.tx_content(), ProofChainFormat::V1)
+        .await
+    {
         Ok(Some(proof_chain)) => {
             rayon_run(move || {
-                let data = serde_json::to_vec(&ProofChainResponse {
-                    proof_chain: Boc::encode_base64(proof_chain),
+                let (proof_chain, stats) = match ProofChainResponse::new(proof_chain, query.stats) {
+                    Ok(res) => (res.proof_chain, res.stats),
+                    Err(e) => {
+                        return res_error(ErrorResponse::Internal {
+                            message: format!("failed to analyze proof chain: {e}"),
+                        });
+                    }
+                };
+                let data = serde_json::to_vec(&ProofChainByHashResponse {
+                    address: address.to_string(),
+                    lt,
+                    proof_chain,
+                    stats,
                 })
                 .unwrap();
 
@@ -104,25 +421,342 @@ async fn get_proof_chain_v1(
     }
 }
 
-fn get_proof_chain_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
-    op.description("Build proof chain")
+async fn get_out_msg_proof_chain_v1(
+    State(state): State<ProofStorage>,
+    Path((TonAddr(address), lt, MsgHash(msg_hash))): Path<(TonAddr, u64, MsgHash)>,
+    Query(query): Query<ProofChainQuery>,
+) -> Response {
+    out_msg_proof_chain_response(&state, &address, lt, &msg_hash, query, ProofChainFormat::V1).await
+}
+
+fn get_out_msg_proof_chain_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chain for an outbound message")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Account state proof after its latest stored transaction.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StateProofChainResponse {
+    /// Base64 encoded BOC with the proof chain of the block with state update.
+    pub proof_chain: String,
+    /// Base64 encoded BOC with the merkle proof of the shard state.
+    pub state_proof: String,
+}
+
+async fn get_state_proof_chain_v1(
+    State(state): State<ProofStorage>,
+    Path(TonAddr(address)): Path<TonAddr>,
+) -> Response {
+    state_proof_chain_response(&state, &address, ProofChainFormat::V1).await
+}
+
+fn get_state_proof_chain_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build account state proof chain after the latest account transaction")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<StateProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Transaction trace request params.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceQuery {
+    /// Whether to include full inbound messages.
+    #[serde(default)]
+    pub in_msg_body: bool,
+    /// Whether to include all full outbound messages.
+    #[serde(default)]
+    pub out_msg_bodies: bool,
+}
+
+/// Transaction trace with proof chains for each transaction.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceTxResponse {
+    /// Transaction account address.
+    pub address: String,
+    /// Transaction logical time.
+    pub lt: u64,
+    /// Transaction hash as hex.
+    pub tx_hash: String,
+    /// Base64 encoded BOC with the proof chain of the full transaction.
+    pub proof_chain: String,
+    /// Internal outbound messages of the transaction.
+    pub out_msgs: Vec<TraceMsgResponse>,
+}
+
+/// Internal outbound message of the traced transaction.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceMsgResponse {
+    /// Message hash as hex.
+    pub msg_hash: String,
+    /// Destination address.
+    pub dst: String,
+    /// Destination transaction (if it was found).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx: Option<Box<TraceTxResponse>>,
+}
+
+impl From<TxTrace> for TraceTxResponse {
+    fn from(trace: TxTrace) -> Self {
+        Self {
+            address: trace.account.to_string(),
+            lt: trace.lt,
+            tx_hash: trace.tx_hash.to_string(),
+            proof_chain: Boc::encode_base64(trace.proof_chain),
+            out_msgs: trace
+                .out_msgs
+                .into_iter()
+                .map(|msg| TraceMsgResponse {
+                    msg_hash: msg.msg_hash.to_string(),
+                    dst: msg.dst.to_string(),
+                    tx: msg.tx.map(|tx| Box::new(tx.into())),
+                })
+                .collect(),
+        }
+    }
+}
+
+async fn get_trace_v1(
+    State(state): State<ProofStorage>,
+    Path((TonAddr(address), lt)): Path<(TonAddr, u64)>,
+    Query(query): Query<TraceQuery>,
+) -> Response {
+    let content = TxContent {
+        full_tx: true,
+        in_msg_body: query.in_msg_body,
+        out_msg_bodies: query.out_msg_bodies,
+    };
+
+    match state.build_trace(&address, lt, content).await {
+        Ok(Some(trace)) => {
+            rayon_run(move || {
+                let data = serde_json::to_vec(&TraceTxResponse::from(trace)).unwrap();
+
+                // NOTE: Trace can grow while new messages are processed.
+                (JSON_HEADERS_DONT_CACHE, axum::body::Bytes::from(data)).into_response()
+            })
+            .await
+        }
+        Ok(None) => res_error(ErrorResponse::NotFound {
+            message: "tx not found",
+        }),
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn get_trace_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chains for all transactions of the trace")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<TraceTxResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+async fn get_config_proof_v1(
+    State(state): State<ProofStorage>,
+    Path(param): Path<u32>,
+    Query(query): Query<ProofChainQuery>,
+) -> Response {
+    match state.build_config_param_proof(param).await {
+        Ok(Some(proof_chain)) => {
+            rayon_run(move || {
+                let res = match ProofChainResponse::new(proof_chain, query.stats) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        return res_error(ErrorResponse::Internal {
+                            message: format!("failed to analyze proof chain: {e}"),
+                        });
+                    }
+                };
+                let data = serde_json::to_vec(&res).unwrap();
+
+                // NOTE: Config proof is always built for the latest key block.
+                (JSON_HEADERS_DONT_CACHE, axum::body::Bytes::from(data)).into_response()
+            })
+            .await
+        }
+        Ok(None) => res_error(ErrorResponse::NotFound {
+            message: "config param not found",
+        }),
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn get_config_proof_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chain for a config param from the latest key block")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+// === V2 Routes ===
+
+async fn get_proof_chain_v2(
+    State(state): State<ProofStorage>,
+    Path((TonAddr(address), lt)): Path<(TonAddr, u64)>,
+    Query(query): Query<TxProofChainQuery>,
+) -> Response {
+    let format = ProofChainFormat::V2(ProofKind::Tx);
+    tx_proof_chain_response(&state, &address, lt, query, format).await
+}
+
+fn get_proof_chain_v2_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build versioned proof chain")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+async fn get_out_msg_proof_chain_v2(
+    State(state): State<ProofStorage>,
+    Path((TonAddr(address), lt, MsgHash(msg_hash))): Path<(TonAddr, u64, MsgHash)>,
+    Query(query): Query<ProofChainQuery>,
+) -> Response {
+    let format = ProofChainFormat::V2(ProofKind::OutMsg);
+    out_msg_proof_chain_response(&state, &address, lt, &msg_hash, query, format).await
+}
+
+fn get_out_msg_proof_chain_v2_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build versioned proof chain for an outbound message")
         .tag("proof-api-l2")
         .response::<200, axum::Json<ProofChainResponse>>()
         .response::<404, ()>()
         .response::<500, axum::Json<ErrorResponse>>()
 }
 
+async fn get_state_proof_chain_v2(
+    State(state): State<ProofStorage>,
+    Path(TonAddr(address)): Path<TonAddr>,
+) -> Response {
+    let format = ProofChainFormat::V2(ProofKind::State);
+    state_proof_chain_response(&state, &address, format).await
+}
+
+fn get_state_proof_chain_v2_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build versioned account state proof chain after the latest transaction")
+        .tag("proof-api-l2")
+        .response::<200, axum::Json<StateProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Builds a transaction proof chain in the specified format.
+async fn tx_proof_chain_response(
+    state: &ProofStorage,
+    address: &StdAddr,
+    lt: u64,
+    query: TxProofChainQuery,
+    format: ProofChainFormat,
+) -> Response {
+    match state
+        .build_proof(address, lt, query.tx_content(), format)
+        .await
+    {
+        Ok(Some(proof_chain)) => {
+            rayon_run(move || proof_chain_response(proof_chain, query.stats)).await
+        }
+        Ok(None) => res_error(ErrorResponse::NotFound {
+            message: "tx not found",
+        }),
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Builds an outbound message proof chain in the specified format.
+async fn out_msg_proof_chain_response(
+    state: &ProofStorage,
+    address: &StdAddr,
+    lt: u64,
+    msg_hash: &HashBytes,
+    query: ProofChainQuery,
+    format: ProofChainFormat,
+) -> Response {
+    match state
+        .build_out_msg_proof(address, lt, msg_hash, format)
+        .await
+    {
+        Ok(Some(proof_chain)) => {
+            rayon_run(move || proof_chain_response(proof_chain, query.stats)).await
+        }
+        Ok(None) => res_error(ErrorResponse::NotFound {
+            message: "out msg not found",
+        }),
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Builds an account state proof chain in the specified format.
+async fn state_proof_chain_response(
+    state: &ProofStorage,
+    address: &StdAddr,
+    format: ProofChainFormat,
+) -> Response {
+    match state.build_state_proof(address, format).await {
+        Ok(Some(proof)) => {
+            rayon_run(move || {
+                let data = serde_json::to_vec(&StateProofChainResponse {
+                    proof_chain: Boc::encode_base64(proof.proof_chain),
+                    state_proof: Boc::encode_base64(proof.state_proof),
+                })
+                .unwrap();
+
+                // NOTE: State proof is built for the latest account transaction.
+                (JSON_HEADERS_DONT_CACHE, axum::body::Bytes::from(data)).into_response()
+            })
+            .await
+        }
+        Ok(None) => res_error(ErrorResponse::NotFound {
+            message: "account transactions not found",
+        }),
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Serializes the proof chain of an existing item.
+fn proof_chain_response(proof_chain: Cell, stats: bool) -> Response {
+    match ProofChainResponse::new(proof_chain, stats) {
+        Ok(res) => {
+            let data = serde_json::to_vec(&res).unwrap();
+            (JSON_HEADERS_CACHE_1W, axum::body::Bytes::from(data)).into_response()
+        }
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: format!("failed to analyze proof chain: {e}"),
+        }),
+    }
+}
+
 /// General error response.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase", tag = "error")]
 pub enum ErrorResponse {
     Internal { message: String },
+    BadRequest { message: &'static str },
     NotFound { message: &'static str },
 }
 
 fn res_error(error: ErrorResponse) -> Response {
     let status = match &error {
         ErrorResponse::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        ErrorResponse::BadRequest { .. } => StatusCode::BAD_REQUEST,
         ErrorResponse::NotFound { .. } => StatusCode::NOT_FOUND,
     };
 
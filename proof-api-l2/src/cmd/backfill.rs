@@ -0,0 +1,585 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use clap::Parser;
+use futures_util::{StreamExt, TryStreamExt};
+use proof_api_l2::storage::ProofStorage;
+use proof_api_util::block::{self, KeyBlockChainVerifier, TychoModels};
+use tycho_block_util::archive::{ArchiveEntryId, ArchiveEntryType, ArchiveReader};
+use tycho_block_util::block::{BlockProofStuff, BlockStuff};
+use tycho_storage::fs::Dir;
+use tycho_types::models::{BlockId, BlockSignature, ValidatorSet};
+use tycho_types::prelude::*;
+use tycho_util::time::now_sec;
+use tycho_util::{FastHashMap, FastHashSet};
+
+use super::run::NodeConfig;
+
+/// Fill the proofs storage from local archive files.
+///
+/// Archives must be specified in ascending order. Key blocks are
+/// followed starting from the trusted key block (it must be present
+/// in archives), only masterchain blocks after it are stored.
+///
+/// Already stored masterchain blocks are skipped so that an interrupted
+/// backfill can be restarted with the same arguments.
+///
+/// NOTE: Blocks older than `min_proof_ttl` are skipped since they
+/// would be removed by the GC of the running node.
+#[derive(Parser)]
+pub struct Cmd {
+    /// Path to the node config.
+    #[clap(short, long)]
+    config: PathBuf,
+
+    /// Path to the node storage root directory.
+    ///
+    /// NOTE: The node must be stopped (the proofs storage can't be shared).
+    #[clap(long)]
+    root_dir: PathBuf,
+
+    /// Archive files (raw or zstd compressed).
+    #[clap(required = true)]
+    archives: Vec<PathBuf>,
+
+    /// Full id of the trusted key block to start from
+    /// (e.g. the last key block before the range).
+    ///
+    /// NOTE: The key block itself is not stored since its signatures
+    /// can't be verified.
+    #[clap(long)]
+    trusted_key_block: BlockId,
+
+    /// Min masterchain block seqno (inclusive).
+    #[clap(long)]
+    from_seqno: Option<u32>,
+
+    /// Max masterchain block seqno (inclusive).
+    #[clap(long)]
+    to_seqno: Option<u32>,
+
+    /// Min masterchain block gen utime (inclusive).
+    #[clap(long)]
+    from_time: Option<u32>,
+
+    /// Max masterchain block gen utime (inclusive).
+    #[clap(long)]
+    to_time: Option<u32>,
+
+    /// Number of masterchain blocks processed concurrently.
+    #[clap(long, default_value_t = 4)]
+    jobs: usize,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        let node_config =
+            NodeConfig::from_file(&self.config).context("failed to load node config")?;
+
+        tycho_util::cli::logger::init_logger(&node_config.logger_config, None)?;
+
+        rayon::ThreadPoolBuilder::new()
+            .stack_size(8 * 1024 * 1024)
+            .thread_name(|_| "rayon_worker".to_string())
+            .num_threads(node_config.threads.rayon_threads)
+            .build_global()
+            .unwrap();
+
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .worker_threads(node_config.threads.tokio_workers)
+            .build()?
+            .block_on(self.run_impl(node_config))
+    }
+
+    async fn run_impl(self, node_config: NodeConfig) -> Result<()> {
+        anyhow::ensure!(
+            self.trusted_key_block.is_masterchain(),
+            "trusted key block must be a masterchain block"
+        );
+        if let Some(from_seqno) = self.from_seqno {
+            anyhow::ensure!(
+                from_seqno > self.trusted_key_block.seqno,
+                "from_seqno must be after the trusted key block"
+            );
+        }
+
+        let proof_storage_config = node_config.user_config.proof_storage;
+
+        let min_gen_utime =
+            now_sec().saturating_sub(proof_storage_config.min_proof_ttl.as_secs() as u32);
+        if let Some(to_time) = self.to_time {
+            anyhow::ensure!(
+                to_time >= min_gen_utime,
+                "the whole range is older than min_proof_ttl and would be removed by GC"
+            );
+        }
+
+        let proofs = ProofStorage::new(&Dir::new(&self.root_dir)?, proof_storage_config)
+            .await
+            .context("failed to create proof storage")?;
+        tracing::info!("created proofs storage");
+
+        let mut state = BackfillState::new(self.trusted_key_block, min_gen_utime);
+        for path in &self.archives {
+            tracing::info!(path = %path.display(), "processing archive");
+
+            let data = tokio::fs::read(path)
+                .await
+                .with_context(|| format!("failed to read archive {}", path.display()))?;
+            let archive = tokio::task::spawn_blocking(move || ParsedArchive::parse(data))
+                .await?
+                .with_context(|| format!("failed to parse archive {}", path.display()))?;
+
+            let jobs = state.prepare_jobs(&self, &proofs, &archive)?;
+            let job_count = jobs.len();
+
+            futures_util::stream::iter(jobs)
+                .map(|job| job.run(&proofs))
+                .buffered(self.jobs.max(1))
+                .try_for_each(|mc_seqno| {
+                    tracing::info!(mc_seqno, "stored masterchain block");
+                    futures_util::future::ok(())
+                })
+                .await?;
+
+            proofs.update_snapshot();
+            tracing::info!(path = %path.display(), job_count, "processed archive");
+        }
+
+        anyhow::ensure!(
+            state.key_blocks.is_some(),
+            "trusted key block not found in archives"
+        );
+
+        tracing::info!("backfill finished");
+        Ok(())
+    }
+
+    fn contains(&self, mc_seqno: u32, gen_utime: u32) -> bool {
+        self.from_seqno.is_none_or(|from| mc_seqno >= from)
+            && self.to_seqno.is_none_or(|to| mc_seqno <= to)
+            && self.from_time.is_none_or(|from| gen_utime >= from)
+            && self.to_time.is_none_or(|to| gen_utime <= to)
+    }
+}
+
+struct BackfillState {
+    trusted_key_block: BlockId,
+    /// Blocks older than this would be removed by the GC.
+    min_gen_utime: u32,
+    key_blocks: Option<KeyBlockChainVerifier<TychoModels>>,
+    /// Latest shard blocks of the previous masterchain block.
+    prev_shard_blocks: FastHashSet<BlockId>,
+}
+
+impl BackfillState {
+    fn new(trusted_key_block: BlockId, min_gen_utime: u32) -> Self {
+        Self {
+            trusted_key_block,
+            min_gen_utime,
+            key_blocks: None,
+            prev_shard_blocks: FastHashSet::default(),
+        }
+    }
+
+    /// Follows key blocks and groups archive blocks by masterchain blocks.
+    fn prepare_jobs(
+        &mut self,
+        cmd: &Cmd,
+        proofs: &ProofStorage,
+        archive: &ParsedArchive,
+    ) -> Result<Vec<BackfillJob>> {
+        let mut jobs = Vec::new();
+        let mut outdated = 0usize;
+        for mc_block_id in archive.mc_block_ids.values() {
+            let mc_block = archive.load_block(mc_block_id)?;
+            let info = mc_block.load_info()?;
+            let custom = mc_block.load_custom()?;
+
+            let mut latest_shard_blocks = Vec::new();
+            for item in custom.shards.latest_blocks() {
+                latest_shard_blocks.push(item?);
+            }
+            let prev_shard_blocks = std::mem::replace(
+                &mut self.prev_shard_blocks,
+                latest_shard_blocks.iter().copied().collect(),
+            );
+
+            // NOTE: Vset is taken before accepting the key block since
+            // key blocks are signed by the previous vset.
+            let vset = self
+                .key_blocks
+                .as_ref()
+                .map(|verifier| (verifier.current_vset().clone(), verifier.signature_id()));
+
+            let signatures = archive.load_signatures(mc_block_id)?;
+
+            if info.key_block {
+                let config = custom.config.as_ref().context("key block without config")?;
+                let signature_id =
+                    block::signature_id_from_config(mc_block.as_ref().global_id, config)
+                        .context("failed to get signature id")?;
+
+                match &mut self.key_blocks {
+                    Some(verifier) => {
+                        verifier
+                            .accept(mc_block_id, mc_block.root_cell(), signatures.values())
+                            .context("invalid key block")?;
+                        verifier.set_signature_id(signature_id);
+                    }
+                    None if mc_block_id.seqno == self.trusted_key_block.seqno => {
+                        // NOTE: Block data is checked against the full id on load.
+                        anyhow::ensure!(
+                            *mc_block_id == self.trusted_key_block,
+                            "trusted key block mismatch: expected {}, got {mc_block_id}",
+                            self.trusted_key_block
+                        );
+
+                        let current_vset = config
+                            .get_current_validator_set()
+                            .context("failed to get current validator set")?;
+
+                        tracing::info!(%mc_block_id, "found trusted key block");
+                        self.key_blocks = Some(KeyBlockChainVerifier::with_key_block(
+                            *mc_block_id,
+                            current_vset,
+                            signature_id,
+                        ));
+                    }
+                    None => {}
+                }
+            }
+
+            let Some((vset, signature_id)) = vset else {
+                anyhow::ensure!(
+                    mc_block_id.seqno <= self.trusted_key_block.seqno,
+                    "trusted key block not found in archives"
+                );
+                tracing::debug!(%mc_block_id, "skipped block before the trusted key block");
+                continue;
+            };
+
+            if !cmd.contains(mc_block_id.seqno, info.gen_utime)
+                || proofs.has_mc_block(mc_block_id.seqno)?
+            {
+                continue;
+            }
+
+            if info.gen_utime < self.min_gen_utime {
+                outdated += 1;
+                continue;
+            }
+
+            // Collect all shard blocks referenced by this masterchain block.
+            let mut blocks = Vec::new();
+            let mut stack = latest_shard_blocks;
+            let mut visited = FastHashSet::default();
+            while let Some(block_id) = stack.pop() {
+                if prev_shard_blocks.contains(&block_id) || !visited.insert(block_id) {
+                    continue;
+                }
+                let Some(block) = archive.try_load_block(&block_id)? else {
+                    // NOTE: Older blocks can be absent only for the first
+                    // masterchain block of the first archive.
+                    anyhow::ensure!(
+                        prev_shard_blocks.is_empty(),
+                        "shard block {block_id} not found in archive (referenced by {mc_block_id})"
+                    );
+                    tracing::warn!(%block_id, %mc_block_id, "skipped missing shard block");
+                    continue;
+                };
+
+                let (prev_id, prev_id_alt) = block.construct_prev_id()?;
+                stack.push(prev_id);
+                stack.extend(prev_id_alt);
+                blocks.push(block);
+            }
+
+            jobs.push(BackfillJob {
+                mc_block,
+                signatures,
+                shard_blocks: blocks,
+                vset,
+                signature_id,
+                // NOTE: Key blocks are already verified by the key blocks verifier.
+                check_signatures: !info.key_block,
+            });
+        }
+
+        if outdated > 0 {
+            tracing::warn!(
+                outdated,
+                min_gen_utime = self.min_gen_utime,
+                "skipped blocks older than min_proof_ttl"
+            );
+        }
+
+        Ok(jobs)
+    }
+}
+
+/// Masterchain block with all its shard blocks.
+struct BackfillJob {
+    mc_block: BlockStuff,
+    signatures: Dict<u16, BlockSignature>,
+    shard_blocks: Vec<BlockStuff>,
+    vset: ValidatorSet,
+    signature_id: Option<i32>,
+    check_signatures: bool,
+}
+
+impl BackfillJob {
+    async fn run(self, proofs: &ProofStorage) -> Result<u32> {
+        let mc_seqno = self.mc_block.id().seqno;
+
+        // NOTE: Masterchain block is stored last so that it can be used
+        // as a marker of the processed blocks group.
+        futures_util::future::try_join_all(self.shard_blocks.into_iter().map(|block| {
+            proofs.backfill_block(
+                block,
+                Dict::new(),
+                mc_seqno,
+                self.vset.clone(),
+                self.signature_id,
+                false,
+            )
+        }))
+        .await?;
+
+        proofs
+            .backfill_block(
+                self.mc_block,
+                self.signatures,
+                mc_seqno,
+                self.vset,
+                self.signature_id,
+                self.check_signatures,
+            )
+            .await?;
+
+        Ok(mc_seqno)
+    }
+}
+
+/// Blocks and signatures from a single archive.
+#[derive(Default)]
+struct ParsedArchive {
+    mc_block_ids: BTreeMap<u32, BlockId>,
+    /// Slices of the (decompressed) archive data.
+    blocks: FastHashMap<BlockId, Bytes>,
+    /// Signatures from masterchain block proofs.
+    signatures: FastHashMap<BlockId, Dict<u16, BlockSignature>>,
+}
+
+impl ParsedArchive {
+    fn parse(data: Vec<u8>) -> Result<Self> {
+        const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+        let data = if data.starts_with(&ZSTD_MAGIC) {
+            let mut output = Vec::new();
+            tycho_util::compression::zstd_decompress(&data, &mut output)?;
+            Bytes::from(output)
+        } else {
+            Bytes::from(data)
+        };
+
+        let mut res = Self::default();
+        for entry in ArchiveReader::new(&data)? {
+            let entry = entry?;
+            let id = ArchiveEntryId::from_filename(entry.name)?;
+            match id.ty {
+                ArchiveEntryType::Block => {
+                    if id.block_id.is_masterchain() {
+                        res.mc_block_ids.insert(id.block_id.seqno, id.block_id);
+                    }
+                    res.blocks.insert(id.block_id, data.slice_ref(entry.data));
+                }
+                ArchiveEntryType::Proof if id.block_id.is_masterchain() => {
+                    let proof = BlockProofStuff::deserialize(&id.block_id, entry.data)?;
+                    if let Some(signatures) = &proof.as_ref().signatures {
+                        res.signatures
+                            .insert(id.block_id, signatures.signatures.clone());
+                    }
+                }
+                ArchiveEntryType::Proof | ArchiveEntryType::QueueDiff => {}
+            }
+        }
+
+        Ok(res)
+    }
+
+    fn load_block(&self, block_id: &BlockId) -> Result<BlockStuff> {
+        self.try_load_block(block_id)?
+            .with_context(|| format!("block not found in archive: {block_id}"))
+    }
+
+    fn try_load_block(&self, block_id: &BlockId) -> Result<Option<BlockStuff>> {
+        let Some(data) = self.blocks.get(block_id) else {
+            return Ok(None);
+        };
+        BlockStuff::deserialize_checked(block_id, data).map(Some)
+    }
+
+    fn load_signatures(&self, mc_block_id: &BlockId) -> Result<Dict<u16, BlockSignature>> {
+        self.signatures
+            .get(mc_block_id)
+            .cloned()
+            .with_context(|| format!("signed block proof not found in archive: {mc_block_id}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proof_api_l2::storage::ProofStorageConfig;
+    use proof_api_util::block::fixtures::{
+        BlockBuilder, Network, Validators, make_signatures_dict,
+    };
+    use tycho_types::models::ShardIdent;
+
+    use super::*;
+
+    struct TestArchive {
+        now: u32,
+        archive: ParsedArchive,
+    }
+
+    impl TestArchive {
+        fn new() -> Self {
+            Self {
+                now: now_sec(),
+                archive: ParsedArchive::default(),
+            }
+        }
+
+        /// Adds a block with the file hash of its serialized data.
+        fn add_block(&mut self, builder: BlockBuilder) -> Result<BlockId> {
+            let built = builder.with_gen_utime(self.now).build()?;
+            let data = Boc::encode(&built.root);
+            let block_id = BlockId {
+                file_hash: Boc::file_hash_blake(&data),
+                ..built.id
+            };
+            if block_id.is_masterchain() {
+                self.archive.mc_block_ids.insert(block_id.seqno, block_id);
+            }
+            self.archive.blocks.insert(block_id, Bytes::from(data));
+            Ok(block_id)
+        }
+
+        fn add_signatures(&mut self, block_id: &BlockId, validators: &Validators) -> Result<()> {
+            let signatures = make_signatures_dict(&validators.sign_all(block_id, None))?;
+            self.archive.signatures.insert(*block_id, signatures);
+            Ok(())
+        }
+    }
+
+    fn make_cmd(trusted_key_block: &BlockId) -> Cmd {
+        Cmd::parse_from([
+            "backfill",
+            "--config",
+            "config.json",
+            "--root-dir",
+            "db",
+            "--trusted-key-block",
+            &trusted_key_block.to_string(),
+            "archive",
+        ])
+    }
+
+    fn job_seqnos(jobs: &[BackfillJob]) -> Vec<(u32, usize, bool)> {
+        jobs.iter()
+            .map(|job| {
+                let seqno = job.mc_block.id().seqno;
+                (seqno, job.shard_blocks.len(), job.check_signatures)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn prepare_and_resume_jobs() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let proofs =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+
+        let validators1 = Validators::new(1, 4);
+        let validators2 = Validators::new(2, 4);
+        let validators3 = Validators::new(3, 4);
+        let vset2 = validators2.make_vset(2000, 4);
+        let vset3 = validators3.make_vset(3000, 4);
+
+        // mc1 -> key mc2 (trusted) -> mc3 -> key mc4,
+        // each referencing the next shard block.
+        let mut test = TestArchive::new();
+        let mut shard_ids = Vec::new();
+        let mut mc_ids = Vec::new();
+        for seqno in 1..=4u32 {
+            let mut shard = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10 + seqno);
+            if let Some(prev) = shard_ids.last() {
+                shard = shard.with_prev_block(prev);
+            }
+            let shard_id = test.add_block(shard)?;
+            shard_ids.push(shard_id);
+
+            let mut mc = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, seqno)
+                .with_shard_block(&shard_id);
+            if seqno > 2 {
+                mc = mc.with_prev_key_block_seqno(2);
+            }
+            mc = match seqno {
+                2 => mc.with_key_block_config(vset2.clone(), None),
+                4 => mc.with_key_block_config(vset3.clone(), Some(vset2.clone())),
+                _ => mc,
+            };
+            let mc_id = test.add_block(mc)?;
+            let signed_by = if seqno <= 2 {
+                &validators1
+            } else {
+                &validators2
+            };
+            test.add_signatures(&mc_id, signed_by)?;
+            mc_ids.push(mc_id);
+        }
+
+        let cmd = make_cmd(&mc_ids[1]);
+
+        // Blocks up to the trusted key block are skipped,
+        // shard blocks of skipped mc blocks are not included.
+        let mut state = BackfillState::new(mc_ids[1], test.now - 100);
+        let jobs = state.prepare_jobs(&cmd, &proofs, &test.archive)?;
+        assert_eq!(job_seqnos(&jobs), [(3, 1, true), (4, 1, false)]);
+
+        // Store only the first job (interrupted backfill).
+        for job in jobs.into_iter().take(1) {
+            job.run(&proofs).await?;
+        }
+        assert!(proofs.has_mc_block(3)?);
+        assert!(!proofs.has_mc_block(4)?);
+
+        // Already stored blocks are skipped on restart.
+        let mut state = BackfillState::new(mc_ids[1], test.now - 100);
+        let jobs = state.prepare_jobs(&cmd, &proofs, &test.archive)?;
+        assert_eq!(job_seqnos(&jobs), [(4, 1, false)]);
+
+        // Outdated blocks are skipped.
+        let mut state = BackfillState::new(mc_ids[1], test.now + 100);
+        assert!(state.prepare_jobs(&cmd, &proofs, &test.archive)?.is_empty());
+
+        // Trusted key block must match the full id.
+        let fake_id = BlockId {
+            root_hash: HashBytes([0x11; 32]),
+            ..mc_ids[1]
+        };
+        let mut state = BackfillState::new(fake_id, test.now - 100);
+        assert!(state.prepare_jobs(&cmd, &proofs, &test.archive).is_err());
+
+        // Missing shard blocks are not skipped silently.
+        test.archive.blocks.remove(&shard_ids[3]);
+        let mut state = BackfillState::new(mc_ids[1], test.now - 100);
+        assert!(state.prepare_jobs(&cmd, &proofs, &test.archive).is_err());
+
+        Ok(())
+    }
+}
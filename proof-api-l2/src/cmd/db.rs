@@ -0,0 +1,129 @@
+use std::future::Future;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use proof_api_l2::storage::{ProofStorage, SnapshotManifest};
+use tycho_storage::fs::Dir;
+
+use super::run::NodeConfig;
+
+/// Proofs storage maintenance.
+#[derive(Parser)]
+pub struct Cmd {
+    #[clap(subcommand)]
+    cmd: SubCmd,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        match self.cmd {
+            SubCmd::Export(cmd) => cmd.run(),
+            SubCmd::Import(cmd) => cmd.run(),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum SubCmd {
+    Export(CmdExport),
+    Import(CmdImport),
+}
+
+/// Write a snapshot of the stopped node proofs storage into a directory.
+#[derive(Parser)]
+struct CmdExport {
+    #[clap(flatten)]
+    storage: StorageArgs,
+
+    /// Path to the snapshot directory.
+    path: PathBuf,
+}
+
+impl CmdExport {
+    fn run(self) -> Result<()> {
+        self.storage.run(move |proofs| async move {
+            let manifest = proofs
+                .export_snapshot(&self.path)
+                .await
+                .context("failed to export snapshot")?;
+            log_manifest("exported snapshot", &manifest);
+            Ok(())
+        })
+    }
+}
+
+/// Fill an empty proofs storage from a snapshot directory.
+#[derive(Parser)]
+struct CmdImport {
+    #[clap(flatten)]
+    storage: StorageArgs,
+
+    /// Path to the snapshot directory.
+    path: PathBuf,
+}
+
+impl CmdImport {
+    fn run(self) -> Result<()> {
+        self.storage.run(move |proofs| async move {
+            let manifest = proofs
+                .import_snapshot(&self.path)
+                .await
+                .context("failed to import snapshot")?;
+            log_manifest("imported snapshot", &manifest);
+            Ok(())
+        })
+    }
+}
+
+#[derive(Parser)]
+struct StorageArgs {
+    /// Path to the node config.
+    #[clap(short, long)]
+    config: PathBuf,
+
+    /// Path to the node storage root directory.
+    ///
+    /// NOTE: The node must be stopped (the proofs storage can't be shared).
+    #[clap(long)]
+    root_dir: PathBuf,
+}
+
+impl StorageArgs {
+    fn run<F, Fut>(self, f: F) -> Result<()>
+    where
+        F: FnOnce(ProofStorage) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let node_config =
+            NodeConfig::from_file(&self.config).context("failed to load node config")?;
+
+        tycho_util::cli::logger::init_logger(&node_config.logger_config, None)?;
+
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .worker_threads(node_config.threads.tokio_workers)
+            .build()?
+            .block_on(async move {
+                let proofs = ProofStorage::new(
+                    &Dir::new(&self.root_dir)?,
+                    node_config.user_config.proof_storage,
+                )
+                .await
+                .context("failed to create proof storage")?;
+                tracing::info!("created proofs storage");
+
+                f(proofs).await
+            })
+    }
+}
+
+fn log_manifest(message: &str, manifest: &SnapshotManifest) {
+    tracing::info!(
+        schema_version = ?manifest.schema_version,
+        min_mc_seqno = ?manifest.min_mc_seqno,
+        max_mc_seqno = ?manifest.max_mc_seqno,
+        tables = manifest.tables.len(),
+        "{message}"
+    );
+}
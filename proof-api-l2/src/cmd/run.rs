@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use anyhow::{Context, Result};
 use clap::Parser;
 use futures_util::future::BoxFuture;
@@ -289,15 +287,17 @@ impl LightSubscriber {
 
         // Update current vset on key blocks.
         if cx.is_key_block {
-            let custom = cx.block.load_custom()?;
-            let config = custom.config.as_ref().context("key block without config")?;
-
-            let current_vset = config
-                .get_current_validator_set()
-                .context("failed to get current validator set")
-                .map(Arc::new)?;
+            let proof = self
+                .storage
+                .block_storage()
+                .load_block_proof(&handle)
+                .await?;
+            let Some(signatures) = &proof.as_ref().signatures else {
+                anyhow::bail!("key block proof without signatures: {}", cx.block.id());
+            };
 
-            self.proofs.set_current_vset(current_vset);
+            self.proofs
+                .accept_key_block(&cx.block, &signatures.signatures)?;
         }
 
         // Done
@@ -324,11 +324,11 @@ impl BlockSubscriber for LightSubscriber {
     }
 }
 
-type NodeConfig = tycho_light_node::NodeConfig<NodeConfigExtra>;
+pub(super) type NodeConfig = tycho_light_node::NodeConfig<NodeConfigExtra>;
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
-struct NodeConfigExtra {
+pub(super) struct NodeConfigExtra {
     pub api: ApiConfig,
     pub proof_storage: ProofStorageConfig,
 }
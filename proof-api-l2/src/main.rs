@@ -4,6 +4,8 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 
 mod cmd {
+    pub mod backfill;
+    pub mod db;
     pub mod run;
 }
 
@@ -42,6 +44,8 @@ impl App {
     pub fn run(self) -> Result<()> {
         match self.cmd {
             SubCmd::Run(cmd) => cmd.run(),
+            SubCmd::Backfill(cmd) => cmd.run(),
+            SubCmd::Db(cmd) => cmd.run(),
         }
     }
 }
@@ -49,4 +53,6 @@ impl App {
 #[derive(Subcommand)]
 enum SubCmd {
     Run(cmd::run::Cmd),
+    Backfill(cmd::backfill::Cmd),
+    Db(cmd::db::Cmd),
 }
@@ -1,11 +1,15 @@
+use std::collections::BTreeMap;
 use std::future::Future;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use arc_swap::{ArcSwap, ArcSwapAny, ArcSwapOption};
 use bytesize::ByteSize;
-use proof_api_util::block::{self, TychoModels};
+use proof_api_util::block::{
+    self, BlockchainBlock, BlockchainBlockInfo, KeyBlockChainVerifier, ProofChainFormat,
+    SignaturesMode, TxContent, TychoModels,
+};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use tycho_block_util::block::BlockStuff;
@@ -17,15 +21,20 @@ use tycho_types::models::{
 };
 use tycho_types::prelude::*;
 use tycho_util::futures::JoinTask;
-use tycho_util::serde_helpers;
 use tycho_util::sync::CancellationFlag;
 use tycho_util::time::now_sec;
+use tycho_util::{FastHashMap, serde_helpers};
 use weedb::{
     Caches, MigrationError, OwnedSnapshot, Semver, Tables, VersionProvider, WeeDb, WeeDbRaw,
     rocksdb,
 };
 
+pub use self::snapshot::{SnapshotManifest, SnapshotTable};
+pub use self::trace::{TraceMsg, TxTrace};
+
+mod snapshot;
 pub mod tables;
+mod trace;
 
 const PROOFS_SUBDIR: &str = "proofs";
 const STORE_TIMINGS_STEP: u32 = 100; // Store timings every 100 mc blocks.
@@ -43,6 +52,8 @@ pub struct ProofStorageConfig {
     /// Default: `10 minutes`
     #[serde(with = "serde_helpers::humantime")]
     pub compaction_interval: Duration,
+    /// Default: `all`.
+    pub signatures_mode: SignaturesMode,
 }
 
 impl Default for ProofStorageConfig {
@@ -53,6 +64,7 @@ impl Default for ProofStorageConfig {
             rocksdb_enable_metrics: false,
             min_proof_ttl: Duration::from_secs(14 * 86400),
             compaction_interval: Duration::from_secs(10 * 60),
+            signatures_mode: SignaturesMode::All,
         }
     }
 }
@@ -65,9 +77,12 @@ pub struct ProofStorage {
 
 struct Inner {
     db: ProofDb,
+    core_storage: OnceLock<CoreStorage>,
     snapshot: ArcSwap<OwnedSnapshot>,
-    current_vset: ArcSwapOption<ValidatorSet>,
+    current_vset: ArcSwapOption<CurrentVset>,
+    key_blocks: Mutex<Option<KeyBlockChainVerifier<TychoModels>>>,
     min_proof_ttl_sec: u32,
+    signatures_mode: SignaturesMode,
     _compaction_handle: JoinTask<()>,
 }
 
@@ -119,6 +134,7 @@ impl ProofStorage {
             .build()?;
 
         db.apply_migrations().await?;
+        snapshot::clear_interrupted_import(&db)?;
 
         trigger_compaction(&db).await?;
 
@@ -145,13 +161,16 @@ impl ProofStorage {
         Ok(Self {
             inner: Arc::new(Inner {
                 db,
+                core_storage: OnceLock::new(),
                 snapshot: ArcSwap::new(Arc::new(snapshot)),
                 current_vset: ArcSwapAny::default(),
+                key_blocks: Mutex::new(None),
                 min_proof_ttl_sec: config
                     .min_proof_ttl
                     .as_secs()
                     .try_into()
                     .unwrap_or(u32::MAX),
+                signatures_mode: config.signatures_mode,
                 _compaction_handle: compaction_handle,
             }),
         })
@@ -164,7 +183,7 @@ impl ProofStorage {
         let blocks = storage.block_storage();
 
         // Init current vset.
-        let current_vset = if init_block_id.seqno == 0 {
+        let (key_block_id, current_vset, signature_id) = if init_block_id.seqno == 0 {
             // Load zerostate
             let zerostate = states
                 .load_state(0, init_block_id)
@@ -172,11 +191,14 @@ impl ProofStorage {
                 .context("failed to load zerostate")?;
 
             // Get current validator set from the state.
-            zerostate
-                .config_params()?
+            let config = zerostate.config_params()?;
+            let vset = config
                 .get_current_validator_set()
-                .context("failed to get current validator set")
-                .map(Arc::new)?
+                .context("failed to get current validator set")?;
+            let signature_id =
+                block::signature_id_from_config(zerostate.state().global_id, &config)
+                    .context("failed to get signature id")?;
+            (*init_block_id, vset, signature_id)
         } else {
             // Find the latest key block (relative to the `init_block_id`).
             let key_block_handle = handles
@@ -195,52 +217,117 @@ impl ProofStorage {
             let custom = extra.load_custom()?.context("invalid key block")?;
             let config = custom.config.context("key block without config")?;
 
-            config
+            let vset = config
                 .get_current_validator_set()
-                .context("failed to get current validator set")
-                .map(Arc::new)?
+                .context("failed to get current validator set")?;
+            let signature_id = block::signature_id_from_config(block.global_id, &config)
+                .context("failed to get signature id")?;
+            (*key_block_handle.id(), vset, signature_id)
         };
 
-        self.set_current_vset(current_vset);
+        // Start following key blocks from the trusted one.
+        self.init_key_blocks(key_block_id, current_vset, signature_id);
+
+        // Remember core storage to access full blocks.
+        self.inner.core_storage.get_or_init(|| storage.clone());
 
         // Done
         Ok(())
     }
 
+    /// Sets the trusted key block (or zerostate) with its current vset.
+    pub fn init_key_blocks(
+        &self,
+        key_block_id: BlockId,
+        vset: ValidatorSet,
+        signature_id: Option<i32>,
+    ) {
+        self.set_current_vset(vset.clone(), signature_id);
+        *self.inner.key_blocks.lock().unwrap() = Some(KeyBlockChainVerifier::with_key_block(
+            key_block_id,
+            vset,
+            signature_id,
+        ));
+    }
+
     pub fn update_snapshot(&self) {
         let snapshot = self.inner.db.owned_snapshot();
         self.inner.snapshot.store(Arc::new(snapshot));
     }
 
-    pub fn set_current_vset(&self, vset: Arc<ValidatorSet>) {
-        self.inner.current_vset.store(Some(vset));
+    /// Verifies the next key block and updates the current vset.
+    pub fn accept_key_block(
+        &self,
+        block: &BlockStuff,
+        signatures: &Dict<u16, BlockSignature>,
+    ) -> Result<()> {
+        let custom = block.load_custom()?;
+        let config = custom.config.as_ref().context("key block without config")?;
+        let signature_id = block::signature_id_from_config(block.as_ref().global_id, config)
+            .context("failed to get signature id")?;
+
+        let mut key_blocks = self.inner.key_blocks.lock().unwrap();
+        let verifier = key_blocks
+            .as_mut()
+            .context("key blocks verifier is not initialized")?;
+
+        verifier
+            .accept(block.id(), block.root_cell(), signatures.values())
+            .context("invalid key block")?;
+        verifier.set_signature_id(signature_id);
+
+        self.set_current_vset(verifier.current_vset().clone(), signature_id);
+        Ok(())
     }
 
-    pub async fn build_proof(&self, account: &StdAddr, lt: u64) -> Result<Option<Cell>> {
-        let this = self.inner.as_ref();
+    fn set_current_vset(&self, vset: ValidatorSet, signature_id: Option<i32>) {
+        self.inner
+            .current_vset
+            .store(Some(Arc::new(CurrentVset { vset, signature_id })));
+    }
 
-        let mut tx_key = [0u8; tables::Transactions::KEY_LEN];
-        tx_key[0..8].copy_from_slice(&lt.to_be_bytes());
-        tx_key[8] = account.workchain as u8;
-        tx_key[9..41].copy_from_slice(account.address.as_slice());
+    /// Builds a proof chain for the specified transaction.
+    ///
+    /// NOTE: Requires the full block data to be present in the core storage
+    /// if `content` is not empty (pruned blocks contain only transaction hashes).
+    pub async fn build_proof(
+        &self,
+        account: &StdAddr,
+        lt: u64,
+        content: TxContent,
+        format: ProofChainFormat,
+    ) -> Result<Option<Cell>> {
+        let Some(tx_block) = self.find_tx_block(account, lt)? else {
+            return Ok(None);
+        };
 
-        let mut block_key;
-        let ref_by_mc_seqno;
-        match this.db.transactions.get(tx_key)? {
-            Some(value) => {
-                let value = value.as_ref();
-                block_key = <[u8; 13]>::try_from(&value[..13]).unwrap();
-                ref_by_mc_seqno = u32::from_le_bytes(value[13..17].try_into().unwrap());
-            }
-            None => return Ok(None),
+        let full_block = if content.is_empty() {
+            None
+        } else {
+            let (block_id, block) = self
+                .load_full_block(&tx_block)
+                .await?
+                .context("full block data not found")?;
+            Some((block_id.file_hash, block.root_cell().clone()))
         };
 
-        let tx_block_seqno = u32::from_be_bytes(block_key[9..13].try_into().unwrap());
-        let shard = ShardIdent::new(
-            block_key[0] as i8 as i32,
-            u64::from_be_bytes(block_key[1..9].try_into().unwrap()),
-        )
-        .unwrap();
+        self.build_proof_ext(account, lt, tx_block, full_block, content, format)
+            .await
+            .map(Some)
+    }
+
+    /// Builds a proof chain for the transaction from the full block
+    /// (file hash and root) or from the stored pruned block.
+    async fn build_proof_ext(
+        &self,
+        account: &StdAddr,
+        lt: u64,
+        tx_block: TxBlockRef,
+        full_block: Option<(HashBytes, Cell)>,
+        content: TxContent,
+        format: ProofChainFormat,
+    ) -> Result<Cell> {
+        let this = self.inner.as_ref();
 
         let cancelled = CancellationFlag::new();
         scopeguard::defer! {
@@ -256,109 +343,747 @@ impl ProofStorage {
         tokio::task::spawn_blocking(move || {
             check(&cancelled)?;
 
-            let pruned_blocks_cf = &db.pruned_blocks.cf();
-            let pivot_blocks_cf = &db.pivot_blocks.cf();
-            let signatures_cf = &db.signatures.cf();
-
-            let (tx_block_hash, block_with_tx) = snapshot
-                .get_pinned_cf_opt(
-                    pruned_blocks_cf,
-                    block_key.as_slice(),
-                    db.pruned_blocks.new_read_config(),
-                )?
-                .context("block not found")
-                .and_then(decode_block)?;
+            let (tx_block_hash, block_with_tx) = match full_block {
+                Some(full_block) => full_block,
+                None => snapshot
+                    .get_pinned_cf_opt(
+                        &db.pruned_blocks.cf(),
+                        tx_block.block_key.as_slice(),
+                        db.pruned_blocks.new_read_config(),
+                    )?
+                    .context("block not found")
+                    .and_then(decode_block)?,
+            };
 
             check(&cancelled)?;
 
-            let tx_proof =
-                block::make_tx_proof::<TychoModels>(block_with_tx, &account, lt, is_masterchain)?
-                    .context("tx not found in block")?;
+            let tx_proof = block::make_tx_proof_ext::<TychoModels>(
+                block_with_tx,
+                &account,
+                lt,
+                is_masterchain,
+                content,
+            )?
+            .context("tx not found in block")?;
+
+            make_proof_chain_blocking(
+                &db,
+                &snapshot,
+                &cancelled,
+                tx_block,
+                &tx_block_hash,
+                &account,
+                tx_proof,
+                format,
+            )
+        })
+        .await?
+    }
 
-            check(&cancelled)?;
+    /// Builds proof chains for multiple transactions.
+    ///
+    /// Transactions are grouped by blocks so that each group shares
+    /// the same proof chain. Unknown transactions are skipped.
+    pub async fn build_proofs(&self, transactions: &[(StdAddr, u64)]) -> Result<Vec<BatchProof>> {
+        let this = self.inner.as_ref();
 
-            // Get signatures.
-            let (vset_utime_since, signatures) = snapshot
-                .get_pinned_cf_opt(
-                    signatures_cf,
-                    ref_by_mc_seqno.to_be_bytes(),
-                    db.signatures.new_read_config(),
-                )?
-                .context("signatures not found")
-                .and_then(decode_signatures)?;
-
-            // Get all required blocks.
-            let file_hash;
-            let mc_proof;
-            let mut shard_proofs = Vec::new();
-            if is_masterchain {
-                // No shard blocks are required in addition to masterchain proof.
-                file_hash = tx_block_hash;
-                mc_proof = tx_proof;
-            } else {
-                // Get pivot mc block.
-                let mut mc_block_key = [0; tables::PivotBlocks::KEY_LEN];
-                mc_block_key[0] = -1i8 as u8;
-                mc_block_key[1..9].copy_from_slice(&ShardIdent::MASTERCHAIN.prefix().to_be_bytes());
-                mc_block_key[9..13].copy_from_slice(&ref_by_mc_seqno.to_be_bytes());
-                let (mc_block_hash, mc_block) = snapshot
+        let mut groups = BTreeMap::<BlockKey, (TxBlockRef, Vec<(StdAddr, u64)>)>::new();
+        for (account, lt) in transactions {
+            let Some(tx_block) = self.find_tx_block(account, *lt)? else {
+                continue;
+            };
+
+            let (_, items) = groups
+                .entry(tx_block.block_key)
+                .or_insert_with(|| (tx_block, Vec::new()));
+            if !items.iter().any(|item| item.0 == *account && item.1 == *lt) {
+                items.push((account.clone(), *lt));
+            }
+        }
+
+        if groups.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let cancelled = CancellationFlag::new();
+        scopeguard::defer! {
+            cancelled.cancel();
+        }
+
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        let cancelled = cancelled.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut result = Vec::with_capacity(groups.len());
+            for (tx_block, mut pending) in groups.into_values() {
+                check(&cancelled)?;
+
+                let (tx_block_hash, block_with_txs) = snapshot
                     .get_pinned_cf_opt(
-                        pivot_blocks_cf,
-                        mc_block_key.as_slice(),
-                        db.pivot_blocks.new_read_config(),
+                        &db.pruned_blocks.cf(),
+                        tx_block.block_key.as_slice(),
+                        db.pruned_blocks.new_read_config(),
                     )?
-                    .context("ref mc block not found")
+                    .context("block not found")
                     .and_then(decode_block)?;
 
-                let mc = block::make_mc_proof::<TychoModels>(mc_block, shard)?;
-                file_hash = mc_block_hash;
-                mc_proof = mc.root;
+                let (shard, _) = parse_block_key(&tx_block.block_key);
 
-                anyhow::ensure!(
-                    mc.latest_shard_seqno >= tx_block_seqno,
-                    "stored masterchain block has some strange shard description"
-                );
+                // NOTE: Accounts from the same block can have different paths
+                // to the masterchain block in case of splits or merges.
+                while !pending.is_empty() {
+                    check(&cancelled)?;
+
+                    let first = pending.remove(0);
+                    let parts = ProofChainParts::load(
+                        &db,
+                        &snapshot,
+                        &cancelled,
+                        tx_block,
+                        &tx_block_hash,
+                        &first.0.address,
+                    )?;
+
+                    let (same, rest): (Vec<_>, Vec<_>) = pending
+                        .into_iter()
+                        .partition(|(account, _)| parts.is_suitable_for(&account.address));
+
+                    let mut transactions = vec![first];
+                    transactions.extend(same);
+                    pending = rest;
 
-                // Iterate intermediate shard blocks in reverse order until the latest one.
-                for seqno in (tx_block_seqno + 1..=mc.latest_shard_seqno).rev() {
                     check(&cancelled)?;
 
-                    block_key[9..13].copy_from_slice(&seqno.to_be_bytes());
-                    let (_, sc_block) = snapshot
-                        .get_pinned_cf_opt(
-                            pivot_blocks_cf,
-                            block_key.as_slice(),
-                            db.pivot_blocks.new_read_config(),
-                        )?
-                        .context("pivot shard block not found")
-                        .and_then(decode_block)?;
-
-                    shard_proofs.push(sc_block);
+                    let txs_proof = block::make_multi_tx_proof::<TychoModels, _>(
+                        block_with_txs.clone(),
+                        transactions
+                            .iter()
+                            .map(|(account, lt)| (&account.address, *lt)),
+                        shard.is_masterchain(),
+                    )?
+                    .context("tx not found in block")?;
+
+                    result.push(BatchProof {
+                        transactions,
+                        proof_chain: parts.finish(txs_proof, ProofChainFormat::V1)?,
+                    });
                 }
+            }
 
-                shard_proofs.push(tx_proof);
+            Ok::<_, anyhow::Error>(result)
+        })
+        .await?
+    }
+
+    /// Builds a proof chain for an outbound message of the specified transaction.
+    ///
+    /// NOTE: Requires the full block data to be present in the core storage.
+    pub async fn build_out_msg_proof(
+        &self,
+        account: &StdAddr,
+        lt: u64,
+        msg_hash: &HashBytes,
+        format: ProofChainFormat,
+    ) -> Result<Option<Cell>> {
+        let this = self.inner.as_ref();
+
+        let Some(tx_block) = self.find_tx_block(account, lt)? else {
+            return Ok(None);
+        };
+
+        let Some((block_id, block)) = self.load_full_block(&tx_block).await? else {
+            return Ok(None);
+        };
+
+        let cancelled = CancellationFlag::new();
+        scopeguard::defer! {
+            cancelled.cancel();
+        }
+
+        let is_masterchain = account.is_masterchain();
+        let address = account.address;
+        let msg_hash = *msg_hash;
+
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        let cancelled = cancelled.clone();
+        tokio::task::spawn_blocking(move || {
+            check(&cancelled)?;
+
+            let Some(out_msg_proof) = block::make_out_msg_proof::<TychoModels>(
+                block.root_cell().clone(),
+                &address,
+                lt,
+                &msg_hash,
+                is_masterchain,
+            )?
+            else {
+                return Ok(None);
+            };
+
+            make_proof_chain_blocking(
+                &db,
+                &snapshot,
+                &cancelled,
+                tx_block,
+                &block_id.file_hash,
+                &address,
+                out_msg_proof,
+                format,
+            )
+            .map(Some)
+        })
+        .await?
+    }
+
+    /// Builds a proof of the account state after its latest stored transaction.
+    ///
+    /// The state is taken from the merkle update of the transaction block,
+    /// so shard states are not required.
+    ///
+    /// NOTE: Requires the full block data to be present in the core storage.
+    pub async fn build_state_proof(
+        &self,
+        account: &StdAddr,
+        format: ProofChainFormat,
+    ) -> Result<Option<StateProofChain>> {
+        let Some(tx_block) = self.find_latest_tx_block(account)? else {
+            return Ok(None);
+        };
+        let Some((block_id, block)) = self.load_full_block(&tx_block).await? else {
+            return Ok(None);
+        };
+
+        let full_block = (block_id.file_hash, block.root_cell().clone());
+        self.build_state_proof_ext(account, tx_block, full_block, format)
+            .await
+            .map(Some)
+    }
+
+    async fn build_state_proof_ext(
+        &self,
+        account: &StdAddr,
+        tx_block: TxBlockRef,
+        (file_hash, block_root): (HashBytes, Cell),
+        format: ProofChainFormat,
+    ) -> Result<StateProofChain> {
+        let this = self.inner.as_ref();
+
+        let cancelled = CancellationFlag::new();
+        scopeguard::defer! {
+            cancelled.cancel();
+        }
+
+        let is_masterchain = account.is_masterchain();
+        let address = account.address;
+
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        let cancelled = cancelled.clone();
+        tokio::task::spawn_blocking(move || {
+            check(&cancelled)?;
+
+            let proof = block::make_account_state_proof_from_update::<TychoModels>(
+                block_root,
+                &address,
+                is_masterchain,
+            )?;
+
+            let proof_chain = make_proof_chain_blocking(
+                &db,
+                &snapshot,
+                &cancelled,
+                tx_block,
+                &file_hash,
+                &address,
+                proof.block,
+                format,
+            )?;
+
+            Ok(StateProofChain {
+                proof_chain,
+                state_proof: proof.state,
+            })
+        })
+        .await?
+    }
+
+    /// Builds proof chains which show that the account has no transaction
+    /// with the specified lt (or no transactions at all if `lt` is `None`)
+    /// in the blocks committed by the masterchain block.
+    ///
+    /// For shard accounts there is a proof chain for each block of the account
+    /// shard since the one referenced by the previous masterchain block
+    /// (from the latest to the oldest one).
+    ///
+    /// Returns `None` if some block is unknown, there is such transaction
+    /// or the masterchain block has no new blocks of the account shard.
+    pub async fn build_tx_absence_proofs(
+        &self,
+        account: &StdAddr,
+        mc_seqno: u32,
+        lt: Option<u64>,
+        format: ProofChainFormat,
+    ) -> Result<Option<Vec<Cell>>> {
+        let this = self.inner.as_ref();
+
+        let cancelled = CancellationFlag::new();
+        scopeguard::defer! {
+            cancelled.cancel();
+        }
+
+        let is_masterchain = account.is_masterchain();
+        let workchain = account.workchain as i32;
+        let address = account.address;
+
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        let cancelled = cancelled.clone();
+        tokio::task::spawn_blocking(move || {
+            check(&cancelled)?;
+
+            let pivot_blocks_cf = &db.pivot_blocks.cf();
+            let load_pivot_block = |block_key: &BlockKey| -> Result<Option<Cell>> {
+                let Some(block) = snapshot.get_pinned_cf_opt(
+                    pivot_blocks_cf,
+                    block_key.as_slice(),
+                    db.pivot_blocks.new_read_config(),
+                )?
+                else {
+                    return Ok(None);
+                };
+                decode_block(block).map(|(_, block)| Some(block))
+            };
+
+            // Find all blocks with the account.
+            let mc_block_key = make_block_key(&ShardIdent::MASTERCHAIN, mc_seqno);
+            let block_keys = if is_masterchain {
+                vec![mc_block_key]
+            } else {
+                let Some(prev_mc_seqno) = mc_seqno.checked_sub(1) else {
+                    return Ok(None);
+                };
+                let prev_mc_block_key = make_block_key(&ShardIdent::MASTERCHAIN, prev_mc_seqno);
+
+                let (Some(mc_block), Some(prev_mc_block)) = (
+                    load_pivot_block(&mc_block_key)?,
+                    load_pivot_block(&prev_mc_block_key)?,
+                ) else {
+                    return Ok(None);
+                };
+
+                let mc = block::make_mc_proof::<TychoModels>(mc_block, workchain, &address)?;
+                let prev_mc =
+                    block::make_mc_proof::<TychoModels>(prev_mc_block, workchain, &address)?;
+
+                // NOTE: Shard blocks between the latest ones referenced by
+                // the previous and the current masterchain blocks are committed
+                // together (possibly from other shards in case of splits or merges).
+                let mut block_keys = Vec::new();
+                block::collect_pivot_chain::<TychoModels, _, anyhow::Error>(
+                    &mc.latest_shard_block_id,
+                    &prev_mc.latest_shard_block_id.as_short_id(),
+                    &address,
+                    |block_id| {
+                        check(&cancelled)?;
+
+                        let block_key = make_block_key(&block_id.shard, block_id.seqno);
+                        let block = load_pivot_block(&block_key)?;
+                        block_keys.push(block_key);
+                        block.context("pivot shard block not found")
+                    },
+                )
+                .context("stored masterchain blocks have some strange shard descriptions")?;
+
+                // NOTE: There is nothing to prove without new blocks.
+                if block_keys.is_empty() {
+                    return Ok(None);
+                }
+                block_keys
+            };
+
+            let mut result = Vec::with_capacity(block_keys.len());
+            for block_key in block_keys {
+                check(&cancelled)?;
+
+                let Some(block) = snapshot.get_pinned_cf_opt(
+                    &db.pruned_blocks.cf(),
+                    block_key.as_slice(),
+                    db.pruned_blocks.new_read_config(),
+                )?
+                else {
+                    return Ok(None);
+                };
+                let (block_hash, block) = decode_block(block)?;
+
+                let absence_proof = match lt {
+                    Some(lt) => block::make_tx_absence_proof::<TychoModels>(
+                        block,
+                        &address,
+                        lt,
+                        is_masterchain,
+                    )?,
+                    None => block::make_account_absence_proof::<TychoModels>(
+                        block,
+                        &address,
+                        is_masterchain,
+                    )?,
+                };
+                let Some(absence_proof) = absence_proof else {
+                    return Ok(None);
+                };
+
+                let tx_block = TxBlockRef {
+                    block_key,
+                    ref_by_mc_seqno: mc_seqno,
+                };
+                result.push(make_proof_chain_blocking(
+                    &db,
+                    &snapshot,
+                    &cancelled,
+                    tx_block,
+                    &block_hash,
+                    &address,
+                    absence_proof,
+                    format,
+                )?);
             }
 
+            Ok(Some(result))
+        })
+        .await?
+    }
+
+    /// Builds a proof chain for the config param from the latest stored key block.
+    ///
+    /// NOTE: Requires the full key block data to be present in the core storage.
+    pub async fn build_config_param_proof(&self, param: u32) -> Result<Option<Cell>> {
+        let storage = self
+            .inner
+            .core_storage
+            .get()
+            .context("proof storage is not initialized")?;
+
+        let Some(key_block_id) = self.find_latest_key_block_id().await? else {
+            return Ok(None);
+        };
+
+        let Some(handle) = storage.block_handle_storage().load_handle(&key_block_id) else {
+            return Ok(None);
+        };
+        let block = storage
+            .block_storage()
+            .load_block_data(&handle)
+            .await
+            .context("failed to load key block data")?;
+
+        self.build_config_param_proof_ext(&key_block_id, block.root_cell().clone(), param)
+            .await
+    }
+
+    async fn build_config_param_proof_ext(
+        &self,
+        key_block_id: &BlockId,
+        block_root: Cell,
+        param: u32,
+    ) -> Result<Option<Cell>> {
+        let this = self.inner.as_ref();
+
+        let cancelled = CancellationFlag::new();
+        scopeguard::defer! {
+            cancelled.cancel();
+        }
+
+        let key_block_id = *key_block_id;
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        let cancelled = cancelled.clone();
+        tokio::task::spawn_blocking(move || {
+            check(&cancelled)?;
+
+            let Some(signatures) = snapshot.get_pinned_cf_opt(
+                &db.signatures.cf(),
+                key_block_id.seqno.to_be_bytes(),
+                db.signatures.new_read_config(),
+            )?
+            else {
+                return Ok(None);
+            };
+            let (vset_utime_since, signatures) = decode_signatures(signatures)?;
+
             check(&cancelled)?;
 
+            let Some(param_proof) =
+                block::make_config_param_proof::<TychoModels>(block_root, param)?
+            else {
+                return Ok(None);
+            };
+
             let proof_chain = block::make_proof_chain(
-                &file_hash,
-                mc_proof,
-                &shard_proofs,
+                &key_block_id.file_hash,
+                param_proof,
+                &[],
                 vset_utime_since,
                 signatures,
             )?;
-            Ok::<_, anyhow::Error>(Some(proof_chain))
+            Ok(Some(proof_chain))
         })
         .await?
     }
 
-    #[tracing::instrument(skip_all)]
-    pub async fn store_block(
+    /// Finds the latest key block with stored signatures.
+    ///
+    /// NOTE: Unlike the key block of the current validator set,
+    /// it doesn't depend on the blocks applied since the start.
+    async fn find_latest_key_block_id(&self) -> Result<Option<BlockId>> {
+        let this = self.inner.as_ref();
+
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        tokio::task::spawn_blocking(move || find_latest_key_block_id(&db, &snapshot)).await?
+    }
+
+    /// Finds the account and lt of the transaction by its hash.
+    pub fn find_tx_by_hash(&self, tx_hash: &HashBytes) -> Result<Option<(StdAddr, u64)>> {
+        Ok(self
+            .inner
+            .db
+            .tx_hashes
+            .get(tx_hash.as_slice())?
+            .map(|value| parse_tx_key(value.as_ref())))
+    }
+
+    /// Finds the account and lt of the transaction by its inbound message hash.
+    pub fn find_tx_by_in_msg(&self, msg_hash: &HashBytes) -> Result<Option<(StdAddr, u64)>> {
+        Ok(self
+            .inner
+            .db
+            .in_msgs
+            .get(msg_hash.as_slice())?
+            .map(|value| parse_tx_key(value.as_ref())))
+    }
+
+    /// Lists account transactions with available proofs in ascending order
+    /// starting from `from_lt` (inclusive).
+    pub async fn list_account_transactions(
+        &self,
+        account: &StdAddr,
+        from_lt: u64,
+        limit: usize,
+    ) -> Result<Vec<AccountTx>> {
+        let this = self.inner.as_ref();
+
+        let mut from_key = [0; tables::AccountTransactions::KEY_LEN];
+        from_key[0] = account.workchain as u8;
+        from_key[1..33].copy_from_slice(account.address.as_slice());
+        from_key[33..41].copy_from_slice(&from_lt.to_be_bytes());
+
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        tokio::task::spawn_blocking(move || {
+            let mut block_ids = FastHashMap::<BlockKey, Option<BlockId>>::default();
+
+            let mut iter = snapshot.raw_iterator_cf_opt(
+                &db.account_transactions.cf(),
+                db.account_transactions.new_read_config(),
+            );
+            iter.seek(from_key);
+
+            let mut result = Vec::new();
+            while result.len() < limit {
+                let (Some(key), Some(value)) = (iter.key(), iter.value()) else {
+                    break;
+                };
+                if key[..33] != from_key[..33] {
+                    break;
+                }
+
+                let lt = u64::from_be_bytes(key[33..41].try_into().unwrap());
+                let tx_block = TxBlockRef::from_tx_value(value);
+                let tx_hash = parse_tx_hash(value);
+
+                let block_id = match block_ids.get(&tx_block.block_key) {
+                    Some(block_id) => *block_id,
+                    None => {
+                        let block_id = load_block_id(&db, &snapshot, &tx_block.block_key)?;
+                        block_ids.insert(tx_block.block_key, block_id);
+                        block_id
+                    }
+                };
+
+                // NOTE: Block can be already removed by the GC.
+                if let Some(block_id) = block_id {
+                    result.push(AccountTx {
+                        lt,
+                        tx_hash,
+                        block_id,
+                        mc_seqno: tx_block.ref_by_mc_seqno,
+                    });
+                }
+                iter.next();
+            }
+            iter.status()?;
+
+            Ok::<_, anyhow::Error>(result)
+        })
+        .await?
+    }
+
+    /// Finds the block of the latest stored account transaction.
+    fn find_latest_tx_block(&self, account: &StdAddr) -> Result<Option<TxBlockRef>> {
+        let this = self.inner.as_ref();
+        let snapshot = this.snapshot.load();
+
+        let mut to_key = [0; tables::AccountTransactions::KEY_LEN];
+        to_key[0] = account.workchain as u8;
+        to_key[1..33].copy_from_slice(account.address.as_slice());
+        to_key[33..41].copy_from_slice(&u64::MAX.to_be_bytes());
+
+        let mut iter = snapshot.raw_iterator_cf_opt(
+            &this.db.account_transactions.cf(),
+            this.db.account_transactions.new_read_config(),
+        );
+        iter.seek_for_prev(to_key);
+        iter.status()?;
+
+        Ok(match (iter.key(), iter.value()) {
+            (Some(key), Some(value)) if key[..33] == to_key[..33] => {
+                Some(TxBlockRef::from_tx_value(value))
+            }
+            _ => None,
+        })
+    }
+
+    fn find_tx_block(&self, account: &StdAddr, lt: u64) -> Result<Option<TxBlockRef>> {
+        let mut tx_key = [0u8; tables::Transactions::KEY_LEN];
+        tx_key[0..8].copy_from_slice(&lt.to_be_bytes());
+        tx_key[8] = account.workchain as u8;
+        tx_key[9..41].copy_from_slice(account.address.as_slice());
+
+        Ok(self
+            .inner
+            .db
+            .transactions
+            .get(tx_key)?
+            .map(|value| TxBlockRef::from_tx_value(value.as_ref())))
+    }
+
+    /// Loads the full data of the transaction block from the core storage.
+    async fn load_full_block(
+        &self,
+        tx_block: &TxBlockRef,
+    ) -> Result<Option<(BlockId, BlockStuff)>> {
+        let storage = self
+            .inner
+            .core_storage
+            .get()
+            .context("proof storage is not initialized")?;
+
+        let Some(block_id) = self.find_block_id(&tx_block.block_key).await? else {
+            return Ok(None);
+        };
+
+        let Some(handle) = storage.block_handle_storage().load_handle(&block_id) else {
+            return Ok(None);
+        };
+        let block = storage
+            .block_storage()
+            .load_block_data(&handle)
+            .await
+            .context("failed to load block data")?;
+
+        Ok(Some((block_id, block)))
+    }
+
+    /// Reconstructs the full block id from the stored pruned block.
+    async fn find_block_id(&self, block_key: &BlockKey) -> Result<Option<BlockId>> {
+        let this = self.inner.as_ref();
+
+        let block_key = *block_key;
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        tokio::task::spawn_blocking(move || load_block_id(&db, &snapshot, &block_key)).await?
+    }
+
+    /// Stores a new block.
+    ///
+    /// NOTE: Signatures of masterchain blocks must be already verified
+    /// (by the block strider or by [`accept_key_block`]).
+    ///
+    /// [`accept_key_block`]: Self::accept_key_block
+    #[tracing::instrument(skip_all)]
+    pub async fn store_block(
+        &self,
+        block: BlockStuff,
+        signatures: Dict<u16, BlockSignature>,
+        ref_by_mc_seqno: u32,
+    ) -> Result<()> {
+        let current_vset = self
+            .inner
+            .current_vset
+            .load_full()
+            .context("no current vset found")?;
+
+        self.store_block_impl(
+            block,
+            signatures,
+            ref_by_mc_seqno,
+            current_vset,
+            false,
+            false,
+        )
+        .await
+    }
+
+    /// Stores an old block with signatures of the specified vset.
+    ///
+    /// Signatures are verified only if `check_signatures` is set
+    /// (e.g. key blocks are already verified by the key blocks verifier).
+    ///
+    /// Unlike [`store_block`], outdated blocks are not skipped and the GC
+    /// is not triggered (outdated data will be removed by the live GC).
+    ///
+    /// [`store_block`]: Self::store_block
+    #[tracing::instrument(skip_all)]
+    pub async fn backfill_block(
+        &self,
+        block: BlockStuff,
+        signatures: Dict<u16, BlockSignature>,
+        ref_by_mc_seqno: u32,
+        vset: ValidatorSet,
+        signature_id: Option<i32>,
+        check_signatures: bool,
+    ) -> Result<()> {
+        let current_vset = Arc::new(CurrentVset { vset, signature_id });
+        self.store_block_impl(
+            block,
+            signatures,
+            ref_by_mc_seqno,
+            current_vset,
+            true,
+            check_signatures,
+        )
+        .await
+    }
+
+    /// Returns whether the masterchain block was stored with its signatures.
+    pub fn has_mc_block(&self, mc_seqno: u32) -> Result<bool> {
+        Ok(self
+            .inner
+            .db
+            .signatures
+            .get(mc_seqno.to_be_bytes())?
+            .is_some())
+    }
+
+    async fn store_block_impl(
         &self,
         block: BlockStuff,
         signatures: Dict<u16, BlockSignature>,
         ref_by_mc_seqno: u32,
+        current_vset: Arc<CurrentVset>,
+        backfill: bool,
+        check_signatures: bool,
     ) -> Result<()> {
         let block_id = *block.id();
         let Ok::<i8, _>(workchain) = block_id.shard.workchain().try_into() else {
@@ -371,7 +1096,7 @@ impl ProofStorage {
         let min_proof_ttl = self.inner.min_proof_ttl_sec;
 
         let gen_utime = block.load_info()?.gen_utime;
-        if now.saturating_sub(gen_utime) > min_proof_ttl {
+        if !backfill && now.saturating_sub(gen_utime) > min_proof_ttl {
             tracing::debug!(gen_utime, now, "skipped outdated block");
             return Ok(());
         }
@@ -383,11 +1108,7 @@ impl ProofStorage {
 
         let span = tracing::Span::current();
 
-        let vset = self
-            .inner
-            .current_vset
-            .load_full()
-            .context("no current vset found")?;
+        let signatures_mode = self.inner.signatures_mode;
 
         let db = self.inner.db.clone();
         let cancelled = cancelled.clone();
@@ -399,11 +1120,19 @@ impl ProofStorage {
             let is_masterchain = block_id.is_masterchain();
 
             let signatures_rx = if is_masterchain {
-                let vset = vset.clone();
                 let (signatures_tx, signatures_rx) = tokio::sync::oneshot::channel();
                 rayon::spawn(move || {
-                    let res = block::prepare_signatures(signatures.values(), &vset)
-                        .map(|cell| encode_signatures(vset.utime_since, cell));
+                    let CurrentVset { vset, signature_id } = current_vset.as_ref();
+                    let res = if check_signatures {
+                        block::check_signatures(&block_id, signatures.values(), vset, *signature_id)
+                    } else {
+                        Ok(())
+                    }
+                    .and_then(|_| {
+                        block::prepare_signatures_ext(signatures.values(), vset, signatures_mode)
+                    })
+                    .inspect(|cell| warn_signatures_outside_main_set(cell, vset))
+                    .map(|cell| encode_signatures(vset.utime_since, cell));
 
                     signatures_tx.send(res).ok();
                 });
@@ -412,6 +1141,20 @@ impl ProofStorage {
                 None
             };
 
+            let (in_msgs_tx, in_msgs_rx) = tokio::sync::oneshot::channel();
+            rayon::spawn({
+                let block = block.root_cell().clone();
+                move || {
+                    let res = block::collect_in_msg_hashes::<TychoModels>(&block).map(|items| {
+                        items
+                            .into_iter()
+                            .map(|(account, lt, msg_hash)| ((account, lt), msg_hash))
+                            .collect::<FastHashMap<_, _>>()
+                    });
+                    in_msgs_tx.send(res).ok();
+                }
+            });
+
             let (pivot_tx, pivot_rx) = tokio::sync::oneshot::channel();
             rayon::spawn({
                 let block = block.root_cell().clone();
@@ -431,30 +1174,40 @@ impl ProofStorage {
             let pruned_blocks_cf = &db.pruned_blocks.cf();
             let pivot_blocks_cf = &db.pivot_blocks.cf();
             let transactions_cf = &db.transactions.cf();
+            let tx_hashes_cf = &db.tx_hashes.cf();
+            let in_msgs_cf = &db.in_msgs.cf();
+            let account_transactions_cf = &db.account_transactions.cf();
             let signatures_cf = &db.signatures.cf();
             let timings_cf = &db.timings.cf();
             let mut batch = rocksdb::WriteBatch::new();
 
             // Add timings for masterchain blocks.
-            let remove_bound_rx;
+            let mut remove_bound_rx = None;
             if is_masterchain && block_id.seqno.is_multiple_of(STORE_TIMINGS_STEP) {
-                let remove_until = now.saturating_sub(min_proof_ttl);
-                let db = db.clone();
-
-                let (tx, rx) = tokio::sync::oneshot::channel();
-                rayon::spawn(move || {
-                    let res = find_outdated_bound(&db, remove_until);
-                    tx.send(res).ok();
-                });
-                remove_bound_rx = Some(rx);
+                if !backfill {
+                    let remove_until = now.saturating_sub(min_proof_ttl);
+                    let db = db.clone();
+
+                    let (tx, rx) = tokio::sync::oneshot::channel();
+                    rayon::spawn(move || {
+                        let res = find_outdated_bound(&db, remove_until).and_then(|bound| {
+                            // NOTE: Secondary indices are cleared before
+                            // the transactions range is removed.
+                            if let Some(bound) = &bound {
+                                remove_outdated_tx_indices(&db, bound.lt)?;
+                            }
+                            Ok(bound)
+                        });
+                        tx.send(res).ok();
+                    });
+                    remove_bound_rx = Some(rx);
+                }
 
                 batch.put_cf(
                     timings_cf,
                     gen_utime.to_be_bytes(),
                     block_id.seqno.to_le_bytes(),
                 );
-            } else {
-                remove_bound_rx = None;
             }
 
             // Prepare tx key/value buffers.
@@ -467,19 +1220,14 @@ impl ProofStorage {
             let mut tx_key = [0; tables::Transactions::KEY_LEN];
             tx_key[8] = workchain as u8;
 
-            // Build pruned block and fill batch with new transactions.
+            // Build pruned block and collect new transactions.
             let started_at = Instant::now();
-            let mut debounced = cancelled.debounce(100);
-            let pruned = block::make_pruned_block::<TychoModels, _>(
+            let mut transactions = Vec::new();
+            let pruned = block::make_pruned_block::<TychoModels, _, _>(
                 block.root_cell().clone(),
-                |account, lt| {
-                    if debounced.check() {
-                        return Err(Error::Cancelled);
-                    }
-
-                    tx_key[0..8].copy_from_slice(&lt.to_be_bytes());
-                    tx_key[9..41].copy_from_slice(account.as_slice());
-                    batch.put_cf(transactions_cf, tx_key.as_slice(), tx_value.as_slice());
+                || cancelled.check(),
+                |account, lt, tx_hash| {
+                    transactions.push((*account, lt, *tx_hash));
                     Ok(())
                 },
             )
@@ -491,6 +1239,33 @@ impl ProofStorage {
 
             check(&cancelled)?;
 
+            // Wait for inbound messages of transactions (collected in parallel
+            // with the pruned block) and fill batch with new transactions.
+            let in_msgs = in_msgs_rx.blocking_recv()??;
+            for (account, lt, tx_hash) in transactions {
+                tx_key[0..8].copy_from_slice(&lt.to_be_bytes());
+                tx_key[9..41].copy_from_slice(account.as_slice());
+                tx_value[17..49].copy_from_slice(tx_hash.as_slice());
+
+                let value_len = match in_msgs.get(&(account, lt)) {
+                    Some(msg_hash) => {
+                        tx_value[49..81].copy_from_slice(msg_hash.as_slice());
+                        batch.put_cf(in_msgs_cf, msg_hash.as_slice(), tx_key.as_slice());
+                        tables::Transactions::VALUE_LEN
+                    }
+                    None => tables::Transactions::VALUE_LEN - 32,
+                };
+
+                let tx_value = &tx_value[..value_len];
+                batch.put_cf(transactions_cf, tx_key.as_slice(), tx_value);
+                batch.put_cf(
+                    account_transactions_cf,
+                    make_account_tx_key(&tx_key),
+                    tx_value,
+                );
+                batch.put_cf(tx_hashes_cf, tx_hash.as_slice(), tx_key.as_slice());
+            }
+
             batch.put_cf(pruned_blocks_cf, &tx_value[0..13], pruned);
 
             // Wait for signatures and put them to the batch.
@@ -569,28 +1344,356 @@ impl OutdatedBound {
     }
 
     fn iter_block_keys(&self) -> impl Iterator<Item = (BlockKey, BlockKey)> + '_ {
-        self.blocks.iter().filter_map(|block_id| {
-            let Ok::<i8, _>(workchain) = block_id.shard.workchain().try_into() else {
-                return None;
-            };
+        self.blocks.iter().flat_map(|block_id| {
+            // NOTE: Blocks before splits or merges are stored under
+            // the parent or child shards. Seqno grows across them.
+            let mut shards = vec![block_id.shard];
+            let mut shard = block_id.shard;
+            while let Some(parent) = shard.merge() {
+                shards.push(parent);
+                shard = parent;
+            }
+            if let Some((left, right)) = block_id.shard.split() {
+                shards.push(left);
+                shards.push(right);
+            }
+
+            shards.into_iter().filter_map(move |shard| {
+                let Ok::<i8, _>(workchain) = shard.workchain().try_into() else {
+                    return None;
+                };
 
-            // Use next seqno to remove everything before this key.
-            let seqno = block_id.seqno + 1;
+                // Use next seqno to remove everything before this key.
+                let seqno = block_id.seqno + 1;
 
-            let mut key = [0; tables::PivotBlocks::KEY_LEN];
-            key[0] = workchain as u8;
-            key[1..9].copy_from_slice(&block_id.shard.prefix().to_be_bytes());
+                let mut key = [0; tables::PivotBlocks::KEY_LEN];
+                key[0] = workchain as u8;
+                key[1..9].copy_from_slice(&shard.prefix().to_be_bytes());
 
-            let from = key;
-            key[9..13].copy_from_slice(&seqno.to_be_bytes());
+                let from = key;
+                key[9..13].copy_from_slice(&seqno.to_be_bytes());
 
-            Some((from, key))
+                Some((from, key))
+            })
         })
     }
 }
 
 type BlockKey = [u8; tables::PivotBlocks::KEY_LEN];
 
+/// Proof chain for a group of transactions from the same block.
+pub struct BatchProof {
+    pub transactions: Vec<(StdAddr, u64)>,
+    pub proof_chain: Cell,
+}
+
+/// Account state proof wrapped into a proof chain.
+pub struct StateProofChain {
+    /// Proof chain with the `state_update` of the target block.
+    pub proof_chain: Cell,
+    /// Merkle proof of the shard state with the account.
+    pub state_proof: Cell,
+}
+
+/// Account transaction with an available proof.
+pub struct AccountTx {
+    pub lt: u64,
+    /// `None` for transactions stored before the hashes index.
+    pub tx_hash: Option<HashBytes>,
+    pub block_id: BlockId,
+    /// Seqno of the masterchain block which references the transaction block.
+    pub mc_seqno: u32,
+}
+
+/// Location of the block with transaction.
+#[derive(Clone, Copy)]
+struct TxBlockRef {
+    block_key: BlockKey,
+    ref_by_mc_seqno: u32,
+}
+
+impl TxBlockRef {
+    fn from_tx_value(value: &[u8]) -> Self {
+        Self {
+            block_key: value[..13].try_into().unwrap(),
+            ref_by_mc_seqno: u32::from_le_bytes(value[13..17].try_into().unwrap()),
+        }
+    }
+}
+
+/// Returns the transaction hash from the transactions index value
+/// (if it was stored).
+fn parse_tx_hash(value: &[u8]) -> Option<HashBytes> {
+    value.get(17..49).map(HashBytes::from_slice)
+}
+
+/// Returns the inbound message hash from the transactions index value
+/// (if the transaction has an inbound message).
+fn parse_in_msg_hash(value: &[u8]) -> Option<HashBytes> {
+    value.get(49..81).map(HashBytes::from_slice)
+}
+
+/// Converts the transactions index key into the account transactions index key.
+fn make_account_tx_key(
+    tx_key: &[u8; tables::Transactions::KEY_LEN],
+) -> [u8; tables::AccountTransactions::KEY_LEN] {
+    let mut key = [0; tables::AccountTransactions::KEY_LEN];
+    key[0..33].copy_from_slice(&tx_key[8..41]);
+    key[33..41].copy_from_slice(&tx_key[0..8]);
+    key
+}
+
+fn parse_tx_key(key: &[u8]) -> (StdAddr, u64) {
+    let lt = u64::from_be_bytes(key[..8].try_into().unwrap());
+    let account = StdAddr::new(key[8] as i8, HashBytes::from_slice(&key[9..41]));
+    (account, lt)
+}
+
+fn make_block_key(shard: &ShardIdent, seqno: u32) -> BlockKey {
+    let mut key = [0; tables::PivotBlocks::KEY_LEN];
+    key[0] = shard.workchain() as i8 as u8;
+    key[1..9].copy_from_slice(&shard.prefix().to_be_bytes());
+    key[9..13].copy_from_slice(&seqno.to_be_bytes());
+    key
+}
+
+fn parse_block_key(block_key: &BlockKey) -> (ShardIdent, u32) {
+    let shard = ShardIdent::new(
+        block_key[0] as i8 as i32,
+        u64::from_be_bytes(block_key[1..9].try_into().unwrap()),
+    )
+    .unwrap();
+    let seqno = u32::from_be_bytes(block_key[9..13].try_into().unwrap());
+    (shard, seqno)
+}
+
+/// Reconstructs the full block id from the stored pruned block.
+fn load_block_id(
+    db: &ProofDb,
+    snapshot: &OwnedSnapshot,
+    block_key: &BlockKey,
+) -> Result<Option<BlockId>> {
+    let Some(data) = snapshot.get_pinned_cf_opt(
+        &db.pruned_blocks.cf(),
+        block_key.as_slice(),
+        db.pruned_blocks.new_read_config(),
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let (file_hash, block) = decode_block(data)?;
+    let (shard, seqno) = parse_block_key(block_key);
+    Ok(Some(BlockId {
+        shard,
+        seqno,
+        root_hash: *block.hash(0),
+        file_hash,
+    }))
+}
+
+fn find_latest_key_block_id(db: &ProofDb, snapshot: &OwnedSnapshot) -> Result<Option<BlockId>> {
+    let mut iter =
+        snapshot.raw_iterator_cf_opt(&db.signatures.cf(), db.signatures.new_read_config());
+    iter.seek_to_last();
+
+    loop {
+        let Some(key) = iter.key() else {
+            iter.status()?;
+            return Ok(None);
+        };
+        let seqno = u32::from_be_bytes(key[..4].try_into().unwrap());
+
+        // NOTE: Masterchain pivot blocks contain the full block info.
+        let block_key = make_block_key(&ShardIdent::MASTERCHAIN, seqno);
+        let Some(data) = snapshot.get_pinned_cf_opt(
+            &db.pivot_blocks.cf(),
+            block_key.as_slice(),
+            db.pivot_blocks.new_read_config(),
+        )?
+        else {
+            return Ok(None);
+        };
+        let (file_hash, mc_block) = decode_block(data)?;
+
+        let info = mc_block.parse::<block::tycho::TychoBlock>()?.load_info()?;
+        if info.is_key_block() {
+            return Ok(Some(BlockId {
+                shard: ShardIdent::MASTERCHAIN,
+                seqno,
+                root_hash: *mc_block.hash(0),
+                file_hash,
+            }));
+        }
+
+        // NOTE: Zerostate has no signatures.
+        let prev_key_block_seqno = info.prev_key_block_seqno();
+        if prev_key_block_seqno == 0 {
+            return Ok(None);
+        }
+        iter.seek_for_prev(prev_key_block_seqno.to_be_bytes());
+    }
+}
+
+/// Wraps a proof for the item in the transaction block into a proof chain.
+///
+/// NOTE: Target proof for masterchain blocks must include block info.
+fn make_proof_chain_blocking(
+    db: &ProofDb,
+    snapshot: &OwnedSnapshot,
+    cancelled: &CancellationFlag,
+    tx_block: TxBlockRef,
+    tx_block_hash: &HashBytes,
+    account: &HashBytes,
+    target_proof: Cell,
+    format: ProofChainFormat,
+) -> Result<Cell> {
+    ProofChainParts::load(db, snapshot, cancelled, tx_block, tx_block_hash, account)?
+        .finish(target_proof, format)
+}
+
+/// Parts of the proof chain which don't depend on the target proof.
+#[derive(Clone)]
+struct ProofChainParts {
+    file_hash: HashBytes,
+    /// `None` for masterchain blocks (target proof is used instead).
+    mc_proof: Option<Cell>,
+    shard_proofs: Vec<Cell>,
+    /// The smallest shard of the blocks on the path to the masterchain block.
+    path_shard: ShardIdent,
+    vset_utime_since: u32,
+    signatures: Cell,
+}
+
+impl ProofChainParts {
+    fn load(
+        db: &ProofDb,
+        snapshot: &OwnedSnapshot,
+        cancelled: &CancellationFlag,
+        tx_block: TxBlockRef,
+        tx_block_hash: &HashBytes,
+        account: &HashBytes,
+    ) -> Result<Self> {
+        let TxBlockRef {
+            block_key,
+            ref_by_mc_seqno,
+        } = tx_block;
+        let (shard, tx_block_seqno) = parse_block_key(&block_key);
+
+        let pivot_blocks_cf = &db.pivot_blocks.cf();
+        let signatures_cf = &db.signatures.cf();
+
+        check(cancelled)?;
+
+        // Get signatures.
+        let (vset_utime_since, signatures) = snapshot
+            .get_pinned_cf_opt(
+                signatures_cf,
+                ref_by_mc_seqno.to_be_bytes(),
+                db.signatures.new_read_config(),
+            )?
+            .context("signatures not found")
+            .and_then(decode_signatures)?;
+
+        if shard.is_masterchain() {
+            // No shard blocks are required in addition to masterchain proof.
+            return Ok(Self {
+                file_hash: *tx_block_hash,
+                mc_proof: None,
+                shard_proofs: Vec::new(),
+                path_shard: shard,
+                vset_utime_since,
+                signatures,
+            });
+        }
+
+        // Get pivot mc block.
+        let mc_block_key = make_block_key(&ShardIdent::MASTERCHAIN, ref_by_mc_seqno);
+        let (mc_block_hash, mc_block) = snapshot
+            .get_pinned_cf_opt(
+                pivot_blocks_cf,
+                mc_block_key.as_slice(),
+                db.pivot_blocks.new_read_config(),
+            )?
+            .context("ref mc block not found")
+            .and_then(decode_block)?;
+
+        let mc = block::make_mc_proof::<TychoModels>(mc_block, shard.workchain(), account)?;
+
+        // Collect intermediate shard blocks from the latest one (possibly
+        // from other shards in case of splits or merges).
+        let target_block_id = BlockIdShort {
+            shard,
+            seqno: tx_block_seqno,
+        };
+        let mut path_shard = shard;
+        let shard_proofs = block::collect_pivot_chain::<TychoModels, _, anyhow::Error>(
+            &mc.latest_shard_block_id,
+            &target_block_id,
+            account,
+            |block_id| {
+                check(cancelled)?;
+
+                if block_id.shard.prefix_len() > path_shard.prefix_len() {
+                    path_shard = block_id.shard;
+                }
+
+                let block_key = make_block_key(&block_id.shard, block_id.seqno);
+                let (_, sc_block) = snapshot
+                    .get_pinned_cf_opt(
+                        pivot_blocks_cf,
+                        block_key.as_slice(),
+                        db.pivot_blocks.new_read_config(),
+                    )?
+                    .context("pivot shard block not found")
+                    .and_then(decode_block)?;
+
+                Ok(sc_block)
+            },
+        )
+        .context("stored masterchain block has some strange shard description")?;
+
+        Ok(Self {
+            file_hash: mc_block_hash,
+            mc_proof: Some(mc.root),
+            shard_proofs,
+            path_shard,
+            vset_utime_since,
+            signatures,
+        })
+    }
+
+    /// Checks whether the same chain can be used for another account
+    /// from the same block (paths can differ after splits or merges).
+    ///
+    /// NOTE: All blocks on the path contain the account, so their shards
+    /// are nested and it is enough to check the smallest one.
+    fn is_suitable_for(&self, account: &HashBytes) -> bool {
+        self.path_shard.contains_account(account)
+    }
+
+    fn finish(self, target_proof: Cell, format: ProofChainFormat) -> Result<Cell> {
+        let mut shard_proofs = self.shard_proofs;
+        let mc_proof = match self.mc_proof {
+            Some(mc_proof) => {
+                shard_proofs.push(target_proof);
+                mc_proof
+            }
+            None => target_proof,
+        };
+
+        block::make_proof_chain_ext::<TychoModels>(
+            format,
+            &self.file_hash,
+            mc_proof,
+            &shard_proofs,
+            self.vset_utime_since,
+            self.signatures,
+        )
+        .map_err(Into::into)
+    }
+}
+
 fn find_outdated_bound(db: &ProofDb, remove_until: u32) -> Result<Option<OutdatedBound>> {
     let until_mc_seqno = {
         let mut iter = db.timings.raw_iterator();
@@ -624,6 +1727,46 @@ fn find_outdated_bound(db: &ProofDb, remove_until: u32) -> Result<Option<Outdate
     }))
 }
 
+/// Removes secondary index entries of all transactions up to
+/// the specified lt (inclusive) in bounded batches.
+fn remove_outdated_tx_indices(db: &ProofDb, until_lt: u64) -> Result<()> {
+    const BATCH_LEN: usize = 10000;
+
+    let tx_hashes_cf = &db.tx_hashes.cf();
+    let in_msgs_cf = &db.in_msgs.cf();
+    let account_transactions_cf = &db.account_transactions.cf();
+
+    let mut batch = rocksdb::WriteBatch::new();
+    let mut iter = db.transactions.raw_iterator();
+    iter.seek_to_first();
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        let lt = u64::from_be_bytes(key[..8].try_into().unwrap());
+        if lt > until_lt {
+            break;
+        }
+
+        batch.delete_cf(
+            account_transactions_cf,
+            make_account_tx_key(key.try_into().unwrap()),
+        );
+        if let Some(tx_hash) = parse_tx_hash(value) {
+            batch.delete_cf(tx_hashes_cf, tx_hash.as_slice());
+        }
+        if let Some(msg_hash) = parse_in_msg_hash(value) {
+            batch.delete_cf(in_msgs_cf, msg_hash.as_slice());
+        }
+
+        if batch.len() >= BATCH_LEN {
+            db.rocksdb().write(std::mem::take(&mut batch))?;
+        }
+        iter.next();
+    }
+    iter.status()?;
+
+    db.rocksdb().write(batch)?;
+    Ok(())
+}
+
 async fn trigger_compaction(db: &ProofDb) -> Result<()> {
     let cancelled = CancellationFlag::new();
     scopeguard::defer! {
@@ -681,6 +1824,25 @@ fn decode_block(data: rocksdb::DBPinnableSlice<'_>) -> anyhow::Result<(HashBytes
     Ok((file_hash, cell))
 }
 
+struct CurrentVset {
+    vset: ValidatorSet,
+    /// Signature id of the network if it signs blocks with `global_id`.
+    signature_id: Option<i32>,
+}
+
+fn warn_signatures_outside_main_set(signatures: &Cell, vset: &ValidatorSet) {
+    // NOTE: Epoch data contains only main validators.
+    match block::find_signatures_outside_main_set(signatures, vset.main.get()) {
+        Ok(indices) if indices.is_empty() => {}
+        Ok(indices) => tracing::warn!(
+            main_validator_count = vset.main.get(),
+            ?indices,
+            "block signatures reference validators outside the main set"
+        ),
+        Err(e) => tracing::warn!("failed to check block signatures: {e:?}"),
+    }
+}
+
 fn encode_signatures(vset_utime_since: u32, cell: Cell) -> Vec<u8> {
     use tycho_types::boc::ser::BocHeader;
 
@@ -713,13 +1875,23 @@ trait ProofDbExt: Sized {
 
 impl ProofDbExt for ProofDb {
     const NAME: &'static str = "proofs";
-    const VERSION: Semver = [0, 0, 1];
+    const VERSION: Semver = [0, 0, 3];
 
     fn register_migrations(
-        _migrations: &mut Migrations<Self>,
-        _cancelled: CancellationFlag,
+        migrations: &mut Migrations<Self>,
+        cancelled: CancellationFlag,
     ) -> Result<(), MigrationError> {
-        // TODO: Add migrations here.
+        migrations.register([0, 0, 1], [0, 0, 2], {
+            let cancelled = cancelled.clone();
+            move |db| fill_tx_hashes(db, &cancelled).map_err(|e| MigrationError::Custom(e.into()))
+        })?;
+
+        migrations.register([0, 0, 2], [0, 0, 3], move |db| {
+            fill_account_transactions(db, &cancelled)
+                .and_then(|_| warn_unindexed_in_msgs(db))
+                .map_err(|e| MigrationError::Custom(e.into()))
+        })?;
+
         Ok(())
     }
 
@@ -769,6 +1941,9 @@ weedb::tables! {
         pruned_blocks: tables::PrunedBlocks,
         pivot_blocks: tables::PivotBlocks,
         transactions: tables::Transactions,
+        tx_hashes: tables::TxHashes,
+        in_msgs: tables::InMsgs,
+        account_transactions: tables::AccountTransactions,
         signatures: tables::Signatures,
         timings: tables::Timings,
     }
@@ -824,6 +1999,103 @@ impl VersionProvider for StateVersionProvider {
     }
 }
 
+/// Adds hashes to transactions stored before the [`tables::TxHashes`] index
+/// and fills the index (hashes are taken from pruned blocks).
+fn fill_tx_hashes(db: &ProofDb, cancelled: &CancellationFlag) -> Result<()> {
+    const BATCH_LEN: usize = 10000;
+    const OLD_VALUE_LEN: usize = tables::PrunedBlocks::KEY_LEN + 4;
+
+    tracing::info!("filling transaction hashes");
+
+    let transactions_cf = &db.transactions.cf();
+    let tx_hashes_cf = &db.tx_hashes.cf();
+
+    let mut tx_key = [0; tables::Transactions::KEY_LEN];
+    let mut tx_value = [0; OLD_VALUE_LEN + 32];
+
+    let mut batch = rocksdb::WriteBatch::new();
+    let mut iter = db.pruned_blocks.raw_iterator();
+    iter.seek_to_first();
+    while let (Some(block_key), Some(value)) = (iter.key(), iter.value()) {
+        check(cancelled)?;
+
+        let block = Cell::virtualize(Boc::decode(&value[32..])?);
+        for (account, lt, tx_hash) in block::collect_tx_hashes::<TychoModels>(&block)? {
+            tx_key[0..8].copy_from_slice(&lt.to_be_bytes());
+            tx_key[8] = block_key[0];
+            tx_key[9..41].copy_from_slice(account.as_slice());
+
+            // NOTE: Outdated transactions could be already removed.
+            let Some(value) = db.transactions.get(tx_key)? else {
+                continue;
+            };
+            if value.len() != OLD_VALUE_LEN {
+                continue;
+            }
+
+            tx_value[..OLD_VALUE_LEN].copy_from_slice(&value);
+            tx_value[OLD_VALUE_LEN..].copy_from_slice(tx_hash.as_slice());
+            batch.put_cf(transactions_cf, tx_key.as_slice(), tx_value.as_slice());
+            batch.put_cf(tx_hashes_cf, tx_hash.as_slice(), tx_key.as_slice());
+        }
+
+        if batch.len() >= BATCH_LEN {
+            db.rocksdb().write(std::mem::take(&mut batch))?;
+        }
+        iter.next();
+    }
+    iter.status()?;
+
+    db.rocksdb().write(batch)?;
+    Ok(())
+}
+
+/// Fills the [`tables::AccountTransactions`] index from stored transactions.
+fn fill_account_transactions(db: &ProofDb, cancelled: &CancellationFlag) -> Result<()> {
+    const BATCH_LEN: usize = 10000;
+
+    tracing::info!("filling account transactions");
+
+    let account_transactions_cf = &db.account_transactions.cf();
+
+    let mut batch = rocksdb::WriteBatch::new();
+    let mut iter = db.transactions.raw_iterator();
+    iter.seek_to_first();
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        batch.put_cf(
+            account_transactions_cf,
+            make_account_tx_key(key.try_into().unwrap()),
+            value,
+        );
+
+        if batch.len() >= BATCH_LEN {
+            check(cancelled)?;
+            db.rocksdb().write(std::mem::take(&mut batch))?;
+        }
+        iter.next();
+    }
+    iter.status()?;
+
+    db.rocksdb().write(batch)?;
+    Ok(())
+}
+
+/// NOTE: Pruned blocks don't keep inbound message hashes,
+/// so the index can't be filled for already stored transactions.
+fn warn_unindexed_in_msgs(db: &ProofDb) -> Result<()> {
+    let mut iter = db.transactions.raw_iterator();
+    iter.seek_to_first();
+    iter.status()?;
+
+    if iter.valid() {
+        tracing::warn!(
+            "inbound messages of already stored transactions are not indexed, \
+            only transactions ingested after the upgrade can be found by inbound message"
+        );
+    }
+    Ok(())
+}
+
 fn check(cancelled: &CancellationFlag) -> Result<()> {
     if cancelled.check() {
         Err(Error::Cancelled.into())
@@ -831,3 +2103,695 @@ fn check(cancelled: &CancellationFlag) -> Result<()> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proof_api_util::block::fixtures::{
+        BlockBuilder, BuiltBlock, Network, Validators, make_full_tx, make_msg, make_shard_state,
+        make_signatures_dict,
+    };
+    use proof_api_util::block::{
+        BlockchainShardState, ProofChainV2, ProofKind, ShardState, TrustedVset, verify_proof_chain,
+        verify_proof_chain_v2,
+    };
+    use tycho_types::merkle::MerkleProof;
+    use tycho_types::models::{Block, Transaction};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn store_blocks_and_build_proofs() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+
+        let TestBlocks {
+            vset,
+            address,
+            lt,
+            in_msg,
+            txs: [tx1, _],
+            shard_block,
+            mc_seqno,
+        } = store_test_blocks(&storage).await?;
+
+        // Single transaction.
+        let proof_chain = storage
+            .build_proof(&address, lt + 1, TxContent::default(), ProofChainFormat::V1)
+            .await?
+            .context("proof not found")?;
+        let tx_hash = verify_proof_chain::<TychoModels>(
+            proof_chain.as_ref(),
+            TrustedVset::Full(&vset),
+            &address,
+            lt + 1,
+            None,
+        )?;
+        assert_eq!(tx_hash, *tx1.repr_hash());
+
+        let find_proof_tx = |proof_chain: &Cell| -> Result<Cell> {
+            let chain = block::ProofChain::load_from_proof(proof_chain.as_ref())?;
+            block::find_transaction::<TychoModels>(chain.target_block(), &address.address, lt + 1)?
+                .context("no tx")
+        };
+
+        // Only the transaction hash by default.
+        let proof_tx = find_proof_tx(&proof_chain)?;
+        assert!(proof_tx.descriptor().is_pruned_branch());
+
+        // Full transaction with the inbound message body.
+        let tx_block = storage
+            .find_tx_block(&address, lt + 1)?
+            .context("tx block not found")?;
+        let full_block = (shard_block.id.file_hash, shard_block.root.clone());
+        let proof_chain = storage
+            .build_proof_ext(
+                &address,
+                lt + 1,
+                tx_block,
+                Some(full_block),
+                TxContent {
+                    full_tx: true,
+                    in_msg_body: true,
+                    out_msg_bodies: false,
+                },
+                ProofChainFormat::V1,
+            )
+            .await?;
+        let tx_hash = verify_proof_chain::<TychoModels>(
+            proof_chain.as_ref(),
+            TrustedVset::Full(&vset),
+            &address,
+            lt + 1,
+            None,
+        )?;
+        assert_eq!(tx_hash, *tx1.repr_hash());
+
+        let proof_tx = find_proof_tx(&proof_chain)?;
+        assert!(!proof_tx.descriptor().is_pruned_branch());
+        let parsed = proof_tx.parse::<Transaction>()?;
+        let proof_in_msg = parsed.in_msg.context("no in msg")?;
+        assert_eq!(proof_in_msg.repr_hash(), in_msg.repr_hash());
+
+        // Transactions can be found by hash.
+        assert_eq!(
+            storage.find_tx_by_hash(&tx_hash)?,
+            Some((address.clone(), lt + 1))
+        );
+        assert!(storage.find_tx_by_hash(&HashBytes([0x44; 32]))?.is_none());
+
+        // Transactions can be found by inbound message hash.
+        assert_eq!(
+            storage.find_tx_by_in_msg(in_msg.repr_hash())?,
+            Some((address.clone(), lt + 1))
+        );
+        assert!(storage.find_tx_by_in_msg(&HashBytes([0x44; 32]))?.is_none());
+
+        // Account transactions are listed with their blocks.
+        let txs = storage.list_account_transactions(&address, 0, 1).await?;
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].lt, lt + 1);
+        assert_eq!(txs[0].tx_hash, Some(tx_hash));
+        assert_eq!(txs[0].block_id, shard_block.id);
+        assert_eq!(txs[0].mc_seqno, mc_seqno);
+
+        let txs = storage
+            .list_account_transactions(&address, lt + 3, 10)
+            .await?;
+        assert!(txs.is_empty());
+
+        assert!(
+            storage
+                .build_proof(&address, lt + 3, TxContent::default(), ProofChainFormat::V1)
+                .await?
+                .is_none()
+        );
+
+        // Transactions from the same block share the proof chain.
+        let batch = storage
+            .build_proofs(&[(address.clone(), lt + 1), (address.clone(), lt + 2)])
+            .await?;
+        assert_eq!(batch.len(), 1);
+        for (address, lt) in &batch[0].transactions {
+            verify_proof_chain::<TychoModels>(
+                batch[0].proof_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                address,
+                *lt,
+                None,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn build_state_proof_from_latest_tx_block() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+
+        let TestBlocks {
+            address,
+            lt,
+            shard_block,
+            mc_seqno,
+            ..
+        } = store_test_blocks(&storage).await?;
+
+        // State is taken from the block of the latest account transaction.
+        let tx_block = storage
+            .find_latest_tx_block(&address)?
+            .context("tx block not found")?;
+        assert_eq!(
+            tx_block.block_key,
+            make_block_key(&shard_block.id.shard, shard_block.id.seqno)
+        );
+        assert_eq!(tx_block.ref_by_mc_seqno, mc_seqno);
+
+        let missing = StdAddr::new(0, HashBytes([0x22; 32]));
+        assert!(storage.find_latest_tx_block(&missing)?.is_none());
+
+        let full_block = (shard_block.id.file_hash, shard_block.root.clone());
+        let proof = storage
+            .build_state_proof_ext(
+                &address,
+                tx_block,
+                full_block,
+                ProofChainFormat::V2(ProofKind::State),
+            )
+            .await?;
+
+        let chain = ProofChainV2::load_from_proof(proof.proof_chain.as_ref())?;
+        assert_eq!(chain.kind, ProofKind::State);
+        assert_eq!(chain.mc_seqno, mc_seqno);
+        assert_eq!(
+            chain.chain.target_block().hash(0),
+            shard_block.root.repr_hash()
+        );
+        block::verify_shard_chain::<TychoModels>(
+            &chain.chain.mc_block,
+            &chain.chain.shard_blocks,
+            &address.address,
+        )?;
+
+        let state_proof = proof.state_proof.parse_exotic::<MerkleProof>()?;
+        let accounts = state_proof.cell.parse::<ShardState>()?.load_accounts()?;
+        let found = accounts
+            .get(address.address)?
+            .map(|(_, item)| item.last_trans_lt);
+        assert_eq!(found, Some(lt + 2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn build_config_param_proof_from_stored_key_block() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+
+        let now = now_sec();
+        let next_vset = Validators::new(2, 4).make_vset(2000, 4);
+
+        let mc_block4 = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 4)
+            .with_gen_utime(now)
+            .build()?;
+        let key_block5 = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+            .with_gen_utime(now)
+            .with_key_block_config(next_vset, None)
+            .build()?;
+        let mc_block6 = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 6)
+            .with_gen_utime(now)
+            .with_prev_key_block_seqno(5)
+            .build()?;
+
+        // There are no key blocks except the zerostate.
+        store_signed_blocks(&storage, &[], &[&mc_block4]).await?;
+        assert!(storage.find_latest_key_block_id().await?.is_none());
+
+        // Key block is found through the latest masterchain block.
+        store_signed_blocks(&storage, &[], &[&key_block5, &mc_block6]).await?;
+        assert_eq!(
+            storage.find_latest_key_block_id().await?,
+            Some(key_block5.id)
+        );
+
+        // Key block is resolved from the storage after a restart.
+        drop(storage);
+        // NOTE: Let the aborted compaction task release the database.
+        tokio::task::yield_now().await;
+
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+        let key_block_id = storage
+            .find_latest_key_block_id()
+            .await?
+            .context("key block not found")?;
+        assert_eq!(key_block_id, key_block5.id);
+
+        let proof_chain = storage
+            .build_config_param_proof_ext(&key_block_id, key_block5.root.clone(), 34)
+            .await?
+            .context("config param not found")?;
+        let chain = block::ProofChain::load_from_proof(proof_chain.as_ref())?;
+        assert_eq!(chain.mc_block.hash(0), key_block5.root.repr_hash());
+        assert!(chain.shard_blocks.is_empty());
+
+        assert!(
+            storage
+                .build_config_param_proof_ext(&key_block_id, key_block5.root, 12345)
+                .await?
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn build_absence_proofs_for_committed_blocks() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+
+        let now = now_sec();
+        let account1 = HashBytes([0x11; 32]);
+        let account2 = HashBytes([0x22; 32]);
+
+        // Shard blocks 10 and 11 are committed by the same masterchain block.
+        let block9 = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 9)
+            .with_gen_utime(now)
+            .build()?;
+        let block10 = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10)
+            .with_gen_utime(now)
+            .with_prev_block(&block9.id);
+        let lt10 = block10.start_lt();
+        let block10 = block10.with_transaction(&account1, lt10 + 1).build()?;
+        let block11 = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 11)
+            .with_gen_utime(now)
+            .with_prev_block(&block10.id);
+        let lt11 = block11.start_lt();
+        let block11 = block11.with_transaction(&account2, lt11 + 1).build()?;
+
+        let mc_block4 = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 4)
+            .with_gen_utime(now)
+            .with_shard_block(&block9.id)
+            .build()?;
+        let mc_block5 = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+            .with_gen_utime(now)
+            .with_shard_block(&block11.id)
+            .build()?;
+        let mc_block6 = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 6)
+            .with_gen_utime(now)
+            .with_shard_block(&block11.id)
+            .build()?;
+
+        store_signed_blocks(
+            &storage,
+            &[(&block9, 4), (&block10, 5), (&block11, 5)],
+            &[&mc_block4, &mc_block5, &mc_block6],
+        )
+        .await?;
+
+        // There is a proof chain for each committed block.
+        let missing = StdAddr::new(0, HashBytes([0x33; 32]));
+        let proof_chains = storage
+            .build_tx_absence_proofs(&missing, 5, None, ProofChainFormat::V1)
+            .await?
+            .context("account exists")?;
+        assert_eq!(proof_chains.len(), 2);
+        for (proof_chain, shard_block) in proof_chains.iter().zip([&block11, &block10]) {
+            let chain = block::ProofChain::load_from_proof(proof_chain.as_ref())?;
+            assert_eq!(chain.target_block().hash(0), shard_block.root.repr_hash());
+            block::verify_shard_chain::<TychoModels>(
+                &chain.mc_block,
+                &chain.shard_blocks,
+                &missing.address,
+            )?;
+        }
+
+        // Proof chains are built in the requested format.
+        let proof_chains = storage
+            .build_tx_absence_proofs(&missing, 5, None, ProofChainFormat::V2(ProofKind::Tx))
+            .await?
+            .context("account exists")?;
+        assert_eq!(proof_chains.len(), 2);
+        for proof_chain in &proof_chains {
+            let chain = ProofChainV2::load_from_proof(proof_chain.as_ref())?;
+            assert_eq!(chain.mc_seqno, 5);
+            assert_eq!(chain.kind, ProofKind::Tx);
+        }
+
+        let address1 = StdAddr::new(0, account1);
+        let proof_chains = storage
+            .build_tx_absence_proofs(&address1, 5, Some(lt10 + 2), ProofChainFormat::V1)
+            .await?
+            .context("tx exists")?;
+        assert_eq!(proof_chains.len(), 2);
+
+        // Transactions from intermediate blocks are also checked.
+        assert!(
+            storage
+                .build_tx_absence_proofs(&address1, 5, None, ProofChainFormat::V1)
+                .await?
+                .is_none()
+        );
+        assert!(
+            storage
+                .build_tx_absence_proofs(&address1, 5, Some(lt10 + 1), ProofChainFormat::V1)
+                .await?
+                .is_none()
+        );
+
+        let address2 = StdAddr::new(0, account2);
+        assert!(
+            storage
+                .build_tx_absence_proofs(&address2, 5, None, ProofChainFormat::V1)
+                .await?
+                .is_none()
+        );
+
+        // There are no new blocks to prove.
+        assert!(
+            storage
+                .build_tx_absence_proofs(&missing, 6, None, ProofChainFormat::V1)
+                .await?
+                .is_none()
+        );
+
+        // The previous masterchain block is unknown.
+        assert!(
+            storage
+                .build_tx_absence_proofs(&missing, 4, None, ProofChainFormat::V1)
+                .await?
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn build_proofs_across_split() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+
+        let now = now_sec();
+        let parent = ShardIdent::BASECHAIN;
+        let (left, right) = parent.split().unwrap();
+
+        let left1 = HashBytes([0x11; 32]);
+        let left2 = HashBytes([0x22; 32]);
+        let right1 = HashBytes([0xcc; 32]);
+        assert!(left.contains_account(&left1) && left.contains_account(&left2));
+        assert!(right.contains_account(&right1));
+
+        // All transactions are in the block before the split.
+        let b10 = BlockBuilder::new(Network::Tycho, parent, 10).with_gen_utime(now);
+        let lt = b10.start_lt();
+        let b10 = b10
+            .with_transaction(&left1, lt + 1)
+            .with_transaction(&left2, lt + 2)
+            .with_transaction(&right1, lt + 3)
+            .build()?;
+        let l11 = BlockBuilder::new(Network::Tycho, left, 11)
+            .with_gen_utime(now)
+            .with_prev_block(&b10.id)
+            .after_split()
+            .build()?;
+        let r11 = BlockBuilder::new(Network::Tycho, right, 11)
+            .with_gen_utime(now)
+            .with_prev_block(&b10.id)
+            .after_split()
+            .build()?;
+
+        let mc_block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+            .with_gen_utime(now)
+            .with_shard_block(&l11.id)
+            .with_shard_block(&r11.id)
+            .build()?;
+        let mc_seqno = mc_block.id.seqno;
+
+        let vset = store_signed_blocks(
+            &storage,
+            &[(&b10, mc_seqno), (&l11, mc_seqno), (&r11, mc_seqno)],
+            &[&mc_block],
+        )
+        .await?;
+
+        let left1 = StdAddr::new(0, left1);
+        let left2 = StdAddr::new(0, left2);
+        let right1 = StdAddr::new(0, right1);
+
+        // Paths to the masterchain block differ after the split.
+        let batch = storage
+            .build_proofs(&[
+                (left1.clone(), lt + 1),
+                (right1.clone(), lt + 3),
+                (left2.clone(), lt + 2),
+            ])
+            .await?;
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].transactions, [(left1, lt + 1), (left2, lt + 2)]);
+        assert_eq!(batch[1].transactions, [(right1, lt + 3)]);
+
+        for item in &batch {
+            for (address, lt) in &item.transactions {
+                verify_proof_chain::<TychoModels>(
+                    item.proof_chain.as_ref(),
+                    TrustedVset::Full(&vset),
+                    address,
+                    *lt,
+                    None,
+                )?;
+            }
+        }
+
+        // Single proofs are built in the requested format.
+        let proof_chain = storage
+            .build_proof(
+                &right1,
+                lt + 3,
+                TxContent::default(),
+                ProofChainFormat::V2(ProofKind::Tx),
+            )
+            .await?
+            .context("proof not found")?;
+        let tx_hash = verify_proof_chain_v2::<TychoModels>(
+            proof_chain.as_ref(),
+            TrustedVset::Full(&vset),
+            &right1,
+            lt + 3,
+            None,
+        )?;
+        assert_eq!(storage.find_tx_by_hash(&tx_hash)?, Some((right1, lt + 3)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn outdated_block_keys_across_splits_and_merges() {
+        let parent = ShardIdent::BASECHAIN;
+        let (left, right) = parent.split().unwrap();
+
+        let make_bound = |blocks: &[(ShardIdent, u32)]| OutdatedBound {
+            remove_until: 0,
+            lt: 0,
+            blocks: blocks
+                .iter()
+                .map(|&(shard, seqno)| BlockIdShort { shard, seqno })
+                .collect(),
+        };
+        let is_removed = |bound: &OutdatedBound, shard: ShardIdent, seqno: u32| {
+            let key = make_block_key(&shard, seqno);
+            bound
+                .iter_block_keys()
+                .any(|(from, to)| from <= key && key < to)
+        };
+
+        // Latest shard blocks after a split.
+        let bound = make_bound(&[(left, 20), (right, 21)]);
+        assert!(is_removed(&bound, parent, 10));
+        assert!(is_removed(&bound, left, 20));
+        assert!(!is_removed(&bound, left, 21));
+        assert!(is_removed(&bound, right, 21));
+        assert!(!is_removed(&bound, right, 22));
+
+        // Latest shard block after a merge.
+        let bound = make_bound(&[(parent, 30)]);
+        assert!(is_removed(&bound, left, 28));
+        assert!(is_removed(&bound, right, 29));
+        assert!(is_removed(&bound, parent, 30));
+        assert!(!is_removed(&bound, parent, 31));
+        assert!(!is_removed(&bound, left, 31));
+
+        // Blocks of other workchains are kept.
+        assert!(!is_removed(&bound, ShardIdent::MASTERCHAIN, 1));
+    }
+
+    #[tokio::test]
+    async fn migrate_and_remove_tx_indices() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+        let db = &storage.inner.db;
+
+        let TestBlocks {
+            address,
+            lt,
+            in_msg,
+            txs: [tx1, tx2],
+            ..
+        } = store_test_blocks(&storage).await?;
+        let account = address.address;
+
+        // Emulate transactions stored before the tx hashes index.
+        for (lt, tx) in [(lt + 1, &tx1), (lt + 2, &tx2)] {
+            let mut tx_key = [0; tables::Transactions::KEY_LEN];
+            tx_key[0..8].copy_from_slice(&lt.to_be_bytes());
+            tx_key[9..41].copy_from_slice(account.as_slice());
+
+            let value = db.transactions.get(tx_key)?.context("tx not found")?;
+            db.transactions.insert(tx_key, &value[..17])?;
+            db.tx_hashes.remove(tx.repr_hash().as_slice())?;
+        }
+        assert!(storage.find_tx_by_hash(tx1.repr_hash())?.is_none());
+
+        fill_tx_hashes(db, &CancellationFlag::new())?;
+        for (lt, tx) in [(lt + 1, &tx1), (lt + 2, &tx2)] {
+            assert_eq!(
+                storage.find_tx_by_hash(tx.repr_hash())?,
+                Some((address.clone(), lt))
+            );
+        }
+
+        // Account transactions index is rebuilt from transactions.
+        let mut iter = db.account_transactions.raw_iterator();
+        iter.seek_to_first();
+        while let Some(key) = iter.key() {
+            db.account_transactions.remove(key)?;
+            iter.next();
+        }
+        storage.update_snapshot();
+        assert!(
+            storage
+                .list_account_transactions(&address, 0, 10)
+                .await?
+                .is_empty()
+        );
+
+        fill_account_transactions(db, &CancellationFlag::new())?;
+        storage.update_snapshot();
+        let txs = storage.list_account_transactions(&address, 0, 10).await?;
+        let txs = txs.iter().map(|tx| (tx.lt, tx.tx_hash)).collect::<Vec<_>>();
+        assert_eq!(txs, [
+            (lt + 1, Some(*tx1.repr_hash())),
+            (lt + 2, Some(*tx2.repr_hash()))
+        ]);
+
+        // Only index entries of outdated transactions are removed.
+        remove_outdated_tx_indices(db, lt + 1)?;
+        assert!(storage.find_tx_by_hash(tx1.repr_hash())?.is_none());
+        assert!(storage.find_tx_by_in_msg(in_msg.repr_hash())?.is_none());
+        assert_eq!(
+            storage.find_tx_by_hash(tx2.repr_hash())?,
+            Some((address.clone(), lt + 2))
+        );
+
+        Ok(())
+    }
+
+    /// Shard block with two transactions of the same account
+    /// (the first one has an inbound message) and its masterchain block.
+    ///
+    /// The state after the shard block contains the account.
+    pub(super) struct TestBlocks {
+        pub vset: ValidatorSet,
+        pub address: StdAddr,
+        pub lt: u64,
+        pub in_msg: Cell,
+        pub txs: [Cell; 2],
+        pub shard_block: BuiltBlock,
+        pub mc_seqno: u32,
+    }
+
+    pub(super) async fn store_test_blocks(storage: &ProofStorage) -> Result<TestBlocks> {
+        let now = now_sec();
+        let account = HashBytes([0x11; 32]);
+
+        let shard_block =
+            BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10).with_gen_utime(now);
+        let lt = shard_block.start_lt();
+        let in_msg = make_msg(1)?;
+        let tx1 = make_full_tx(&account, lt + 1, Some(in_msg.clone()), &[])?;
+        let tx2 = make_full_tx(&account, lt + 2, None, &[])?;
+        let accounts = [(account, lt + 2)];
+        let state = make_shard_state(Network::Tycho, ShardIdent::BASECHAIN, 10, &accounts)?;
+        let shard_block = shard_block
+            .with_raw_transaction(&account, lt + 1, tx1.clone())
+            .with_raw_transaction(&account, lt + 2, tx2.clone())
+            .with_state(state)
+            .build()?;
+        let mc_block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+            .with_gen_utime(now)
+            .with_shard_block(&shard_block.id)
+            .build()?;
+        let mc_seqno = mc_block.id.seqno;
+
+        let vset = store_signed_blocks(storage, &[(&shard_block, mc_seqno)], &[&mc_block]).await?;
+
+        Ok(TestBlocks {
+            vset,
+            address: StdAddr::new(0, account),
+            lt,
+            in_msg,
+            txs: [tx1, tx2],
+            shard_block,
+            mc_seqno,
+        })
+    }
+
+    /// Stores shard blocks (with the seqno of the referencing masterchain block)
+    /// and masterchain blocks signed by the returned validator set.
+    pub(super) async fn store_signed_blocks(
+        storage: &ProofStorage,
+        shard_blocks: &[(&BuiltBlock, u32)],
+        mc_blocks: &[&BuiltBlock],
+    ) -> Result<ValidatorSet> {
+        let validators = Validators::new(1, 4);
+        let vset = validators.make_vset(1000, 4);
+        let zerostate_id = BlockId {
+            shard: ShardIdent::MASTERCHAIN,
+            seqno: 0,
+            root_hash: HashBytes::ZERO,
+            file_hash: HashBytes::ZERO,
+        };
+        storage.init_key_blocks(zerostate_id, vset.clone(), None);
+
+        // Shard blocks are stored without signatures.
+        for (shard_block, mc_seqno) in shard_blocks {
+            storage
+                .store_block(make_block_stuff(shard_block)?, Dict::new(), *mc_seqno)
+                .await?;
+        }
+        for mc_block in mc_blocks {
+            let signatures = make_signatures_dict(&validators.sign_all(&mc_block.id, None))?;
+            storage
+                .store_block(make_block_stuff(mc_block)?, signatures, mc_block.id.seqno)
+                .await?;
+        }
+        storage.update_snapshot();
+
+        Ok(vset)
+    }
+
+    pub(super) fn make_block_stuff(block: &BuiltBlock) -> Result<BlockStuff> {
+        let parsed = block.root.parse::<Block>()?;
+        Ok(BlockStuff::from_block_and_root(
+            &block.id,
+            parsed,
+            block.root.clone(),
+            0,
+        ))
+    }
+}
@@ -0,0 +1,477 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use bytesize::ByteSize;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use tycho_types::cell::HashBytes;
+use tycho_util::time::now_sec;
+use weedb::{ColumnFamily, Semver, rocksdb};
+
+use super::{ProofDb, ProofDbExt, ProofStorage, tables};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const IMPORT_BATCH_SIZE: usize = 64 << 20;
+
+/// Present in the [`tables::State`] while the snapshot is being imported.
+const IMPORT_MARKER_KEY: &[u8] = b"__snapshot_import";
+
+/// Describes an exported proofs storage snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Name of the exported storage.
+    pub db_name: String,
+    /// Schema version of the exported storage.
+    pub schema_version: Semver,
+    /// Unix timestamp of the export.
+    pub created_at: u32,
+    /// The lowest signed masterchain block seqno.
+    pub min_mc_seqno: Option<u32>,
+    /// The highest signed masterchain block seqno.
+    pub max_mc_seqno: Option<u32>,
+    /// Exported column families.
+    pub tables: Vec<SnapshotTable>,
+}
+
+/// Describes a single exported column family.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotTable {
+    /// Column family name.
+    pub name: String,
+    /// Number of exported entries.
+    pub entries: u64,
+    /// Data file size in bytes.
+    pub size: u64,
+    /// Hex encoded SHA-256 of the data file.
+    pub sha256: String,
+}
+
+impl SnapshotTable {
+    fn path(&self, dir: &Path) -> PathBuf {
+        table_path(dir, &self.name)
+    }
+}
+
+impl ProofStorage {
+    /// Writes all proof tables into the `dir`.
+    ///
+    /// NOTE: The export is offline only. Tables are read one by one, so
+    /// the storage must not be written by a running node meanwhile.
+    ///
+    /// Each table is written into a separate `{name}.bin` file as a sequence of
+    /// `key_len: u32 (LE), key, value_len: u32 (LE), value` entries.
+    /// The manifest is written last, so its presence marks a complete export.
+    pub async fn export_snapshot(&self, dir: &Path) -> Result<SnapshotManifest> {
+        let db = self.inner.db.clone();
+        let dir = dir.to_owned();
+        tokio::task::spawn_blocking(move || export_snapshot(&db, &dir)).await?
+    }
+
+    /// Fills an empty storage with the snapshot from the `dir`.
+    ///
+    /// NOTE: All data files are verified before the first write.
+    /// Data of an interrupted import is removed (here or on the next
+    /// storage open), so the import can be restarted.
+    pub async fn import_snapshot(&self, dir: &Path) -> Result<SnapshotManifest> {
+        let db = self.inner.db.clone();
+        let dir = dir.to_owned();
+        let manifest = tokio::task::spawn_blocking(move || import_snapshot(&db, &dir)).await??;
+
+        self.update_snapshot();
+        Ok(manifest)
+    }
+}
+
+fn export_snapshot(db: &ProofDb, dir: &Path) -> Result<SnapshotManifest> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    anyhow::ensure!(
+        !manifest_path.exists(),
+        "snapshot already exists: {}",
+        dir.display()
+    );
+    std::fs::create_dir_all(dir).context("failed to create snapshot directory")?;
+
+    let (min_mc_seqno, max_mc_seqno) = {
+        let parse_seqno = |key: &[u8]| u32::from_be_bytes(key[..4].try_into().unwrap());
+
+        let mut iter = db.signatures.raw_iterator();
+        iter.seek_to_first();
+        let min = iter.key().map(parse_seqno);
+        iter.seek_to_last();
+        let max = iter.key().map(parse_seqno);
+        iter.status()?;
+        (min, max)
+    };
+
+    let mut exported = Vec::new();
+    for table in db
+        .column_families()
+        .filter(|table| is_data_table(table.name))
+    {
+        let path = table_path(dir, table.name);
+        let file =
+            File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut writer = TableWriter::new(file);
+
+        let mut read_config = rocksdb::ReadOptions::default();
+        read_config.fill_cache(false);
+
+        let mut iter = db.rocksdb().raw_iterator_cf_opt(&table.cf, read_config);
+        iter.seek_to_first();
+        while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+            writer.write_entry(key, value)?;
+            iter.next();
+        }
+        iter.status()?;
+
+        let table = writer.finish(table.name)?;
+        tracing::info!(
+            cf = %table.name,
+            entries = table.entries,
+            size = %ByteSize(table.size),
+            "exported table"
+        );
+        exported.push(table);
+    }
+
+    let manifest = SnapshotManifest {
+        db_name: ProofDb::NAME.to_owned(),
+        schema_version: ProofDb::VERSION,
+        created_at: now_sec(),
+        min_mc_seqno,
+        max_mc_seqno,
+        tables: exported,
+    };
+    std::fs::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?)
+        .context("failed to write manifest")?;
+
+    Ok(manifest)
+}
+
+fn import_snapshot(db: &ProofDb, dir: &Path) -> Result<SnapshotManifest> {
+    let manifest = std::fs::read(dir.join(MANIFEST_FILE)).context("failed to read manifest")?;
+    let manifest = serde_json::from_slice::<SnapshotManifest>(&manifest)
+        .context("failed to parse manifest")?;
+
+    anyhow::ensure!(
+        manifest.db_name == ProofDb::NAME,
+        "expected db name: {}, got: {}",
+        ProofDb::NAME,
+        manifest.db_name
+    );
+    anyhow::ensure!(
+        manifest.schema_version == ProofDb::VERSION,
+        "expected schema version: {:?}, got: {:?}",
+        ProofDb::VERSION,
+        manifest.schema_version
+    );
+
+    // Check that the snapshot contains exactly the storage tables.
+    let mut expected = db
+        .column_families()
+        .map(|table| table.name)
+        .filter(|name| is_data_table(name))
+        .collect::<Vec<_>>();
+    let mut actual = manifest
+        .tables
+        .iter()
+        .map(|table| table.name.as_str())
+        .collect::<Vec<_>>();
+    expected.sort_unstable();
+    actual.sort_unstable();
+    anyhow::ensure!(
+        actual == expected,
+        "snapshot tables mismatch: expected {expected:?}, got {actual:?}"
+    );
+
+    // Check that all tables are empty.
+    clear_interrupted_import(db)?;
+    for table in db
+        .column_families()
+        .filter(|table| is_data_table(table.name))
+    {
+        let mut iter = db.rocksdb().raw_iterator_cf(&table.cf);
+        iter.seek_to_first();
+        iter.status()?;
+        anyhow::ensure!(iter.key().is_none(), "table is not empty: {}", table.name);
+    }
+
+    // Verify data files.
+    for item in &manifest.tables {
+        let mut reader = TableReader::open(&item.path(dir))?;
+        let mut entries = 0u64;
+        while reader.read_entry()?.is_some() {
+            entries += 1;
+        }
+        reader.verify(item, entries)?;
+        tracing::info!(cf = %item.name, entries, "verified table");
+    }
+
+    // Import data.
+    db.state.insert(IMPORT_MARKER_KEY, b"")?;
+    for item in &manifest.tables {
+        let table = db
+            .column_families()
+            .find(|table| table.name == item.name)
+            .with_context(|| format!("unknown table: {}", item.name))?;
+
+        let mut reader = TableReader::open(&item.path(dir))?;
+        let mut batch = rocksdb::WriteBatch::new();
+        while let Some((key, value)) = reader.read_entry()? {
+            batch.put_cf(&table.cf, key, value);
+            if batch.size_in_bytes() >= IMPORT_BATCH_SIZE {
+                db.rocksdb()
+                    .write(std::mem::take(&mut batch))
+                    .context("failed to write snapshot batch")?;
+            }
+        }
+        db.rocksdb()
+            .write(batch)
+            .context("failed to write snapshot batch")?;
+
+        tracing::info!(
+            cf = %item.name,
+            entries = item.entries,
+            size = %ByteSize(item.size),
+            "imported table"
+        );
+    }
+
+    // NOTE: Marker is removed only after all tables are written.
+    db.rocksdb().flush_wal(true)?;
+    db.state.remove(IMPORT_MARKER_KEY)?;
+
+    Ok(manifest)
+}
+
+/// Removes all data if the previous snapshot import was interrupted.
+pub(super) fn clear_interrupted_import(db: &ProofDb) -> Result<()> {
+    if db.state.get(IMPORT_MARKER_KEY)?.is_none() {
+        return Ok(());
+    }
+
+    tracing::warn!("removing data of the interrupted snapshot import");
+    for table in db
+        .column_families()
+        .filter(|table| is_data_table(table.name))
+    {
+        let mut iter = db.rocksdb().raw_iterator_cf(&table.cf);
+        iter.seek_to_first();
+        let first = iter.key().map(<[u8]>::to_vec);
+        iter.seek_to_last();
+        let last = iter.key().map(<[u8]>::to_vec);
+        iter.status()?;
+
+        if let (Some(first), Some(last)) = (first, last) {
+            let mut batch = rocksdb::WriteBatch::new();
+            batch.delete_range_cf(&table.cf, &first, &last);
+            batch.delete_cf(&table.cf, &last);
+            db.rocksdb().write(batch)?;
+        }
+    }
+
+    db.state.remove(IMPORT_MARKER_KEY)?;
+    Ok(())
+}
+
+/// Returns whether the column family is a part of the snapshot.
+fn is_data_table(name: &str) -> bool {
+    // NOTE: State contains only the db name and version which are
+    // stored in the manifest.
+    name != tables::State::NAME
+}
+
+fn table_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.bin"))
+}
+
+struct TableWriter {
+    writer: BufWriter<File>,
+    hasher: sha2::Sha256,
+    entries: u64,
+    size: u64,
+}
+
+impl TableWriter {
+    fn new(file: File) -> Self {
+        Self {
+            writer: BufWriter::new(file),
+            hasher: sha2::Sha256::new(),
+            entries: 0,
+            size: 0,
+        }
+    }
+
+    fn write_entry(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.write_chunk(key)?;
+        self.write_chunk(value)?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, data: &[u8]) -> Result<()> {
+        let len = u32::try_from(data.len()).context("entry is too large")?;
+        for part in [&len.to_le_bytes()[..], data] {
+            self.writer.write_all(part)?;
+            self.hasher.update(part);
+        }
+        self.size += 4 + data.len() as u64;
+        Ok(())
+    }
+
+    fn finish(self, name: &str) -> Result<SnapshotTable> {
+        let file = self.writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+
+        Ok(SnapshotTable {
+            name: name.to_owned(),
+            entries: self.entries,
+            size: self.size,
+            sha256: HashBytes(self.hasher.finalize().into()).to_string(),
+        })
+    }
+}
+
+struct TableReader {
+    reader: BufReader<File>,
+    hasher: sha2::Sha256,
+    size: u64,
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+impl TableReader {
+    fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+        Ok(Self {
+            reader: BufReader::new(file),
+            hasher: sha2::Sha256::new(),
+            size: 0,
+            key: Vec::new(),
+            value: Vec::new(),
+        })
+    }
+
+    fn read_entry(&mut self) -> Result<Option<(&[u8], &[u8])>> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len)?;
+
+        let mut key = std::mem::take(&mut self.key);
+        let mut value = std::mem::take(&mut self.value);
+        self.read_chunk(len, &mut key)?;
+        self.reader.read_exact(&mut len)?;
+        self.read_chunk(len, &mut value)?;
+        self.key = key;
+        self.value = value;
+
+        Ok(Some((&self.key, &self.value)))
+    }
+
+    fn read_chunk(&mut self, len: [u8; 4], target: &mut Vec<u8>) -> Result<()> {
+        target.resize(u32::from_le_bytes(len) as usize, 0);
+        self.reader.read_exact(target)?;
+
+        self.hasher.update(len);
+        self.hasher.update(target.as_slice());
+        self.size += 4 + target.len() as u64;
+        Ok(())
+    }
+
+    fn verify(self, table: &SnapshotTable, entries: u64) -> Result<()> {
+        let sha256 = HashBytes(self.hasher.finalize().into()).to_string();
+        anyhow::ensure!(
+            entries == table.entries && self.size == table.size && sha256 == table.sha256,
+            "data file mismatch for table {}",
+            table.name
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proof_api_util::block::{
+        ProofChainFormat, TrustedVset, TxContent, TychoModels, verify_proof_chain,
+    };
+    use tycho_storage::fs::Dir;
+
+    use super::*;
+    use crate::storage::ProofStorageConfig;
+    use crate::storage::tests::{TestBlocks, store_test_blocks};
+
+    #[tokio::test]
+    async fn export_and_import_snapshot() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+        let TestBlocks {
+            vset,
+            address,
+            lt,
+            in_msg,
+            mc_seqno,
+            ..
+        } = store_test_blocks(&storage).await?;
+
+        // Snapshot can be imported only into an empty storage.
+        let snapshot_dir = tmp.path().join("snapshot");
+        let manifest = storage.export_snapshot(&snapshot_dir).await?;
+        assert_eq!(manifest.min_mc_seqno, Some(mc_seqno));
+        assert_eq!(manifest.max_mc_seqno, Some(mc_seqno));
+        assert!(storage.export_snapshot(&snapshot_dir).await.is_err());
+        assert!(storage.import_snapshot(&snapshot_dir).await.is_err());
+
+        let imported_tmp = tempfile::tempdir()?;
+        let imported = ProofStorage::new(
+            &Dir::new(imported_tmp.path())?,
+            ProofStorageConfig::default(),
+        )
+        .await?;
+        let db = &imported.inner.db;
+
+        // Snapshot must contain all storage tables.
+        let partial_dir = tmp.path().join("partial");
+        std::fs::create_dir_all(&partial_dir)?;
+        let mut partial = manifest.clone();
+        partial.tables.pop();
+        std::fs::write(
+            partial_dir.join(MANIFEST_FILE),
+            serde_json::to_vec(&partial)?,
+        )?;
+        assert!(imported.import_snapshot(&partial_dir).await.is_err());
+
+        // Data of an interrupted import is removed.
+        let junk_key = [0u8; tables::PrunedBlocks::KEY_LEN];
+        db.state.insert(IMPORT_MARKER_KEY, b"")?;
+        db.pruned_blocks.insert(junk_key, b"junk")?;
+        imported.import_snapshot(&snapshot_dir).await?;
+        assert!(db.state.get(IMPORT_MARKER_KEY)?.is_none());
+        assert!(db.pruned_blocks.get(junk_key)?.is_none());
+
+        let proof_chain = imported
+            .build_proof(&address, lt + 1, TxContent::default(), ProofChainFormat::V1)
+            .await?
+            .context("proof not found")?;
+        verify_proof_chain::<TychoModels>(
+            proof_chain.as_ref(),
+            TrustedVset::Full(&vset),
+            &address,
+            lt + 1,
+            None,
+        )?;
+        assert_eq!(
+            imported.find_tx_by_in_msg(in_msg.repr_hash())?,
+            Some((address.clone(), lt + 1))
+        );
+
+        Ok(())
+    }
+}
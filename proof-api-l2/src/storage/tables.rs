@@ -68,12 +68,15 @@ impl ColumnFamilyOptions<Caches> for PrunedBlocks {
 
 /// Stores transactions index.
 /// - Key: `lt: u64 (BE), workchain: i8, account: [u8; 32]`
-/// - Value: `workchain: i8, shard: u64 (BE), seqno: u32 (BE), ref_by_mc_seqno: u32 (LE)`
+/// - Value: `workchain: i8, shard: u64 (BE), seqno: u32 (BE), ref_by_mc_seqno: u32 (LE),
+///   tx_hash: [u8; 32], in_msg_hash: [u8; 32] (optional)`
+///
+/// NOTE: `in_msg_hash` is absent for transactions without inbound messages.
 pub struct Transactions;
 
 impl Transactions {
     pub const KEY_LEN: usize = 8 + 1 + 32;
-    pub const VALUE_LEN: usize = PrunedBlocks::KEY_LEN + 4;
+    pub const VALUE_LEN: usize = PrunedBlocks::KEY_LEN + 4 + 32 + 32;
 }
 
 impl ColumnFamily for Transactions {
@@ -88,6 +91,70 @@ impl ColumnFamilyOptions<Caches> for Transactions {
     }
 }
 
+/// Stores account transactions index.
+/// - Key: `workchain: i8, account: [u8; 32], lt: u64 (BE)`
+/// - Value: the same as in [`Transactions`]
+pub struct AccountTransactions;
+
+impl AccountTransactions {
+    pub const KEY_LEN: usize = 1 + 32 + 8;
+}
+
+impl ColumnFamily for AccountTransactions {
+    const NAME: &'static str = "account_transactions";
+}
+
+impl ColumnFamilyOptions<Caches> for AccountTransactions {
+    fn options(opts: &mut Options, ctx: &mut Caches) {
+        zstd_block_based_table_factory(opts, ctx);
+        opts.set_compression_type(DBCompressionType::Zstd);
+    }
+}
+
+/// Stores transaction hashes index.
+/// - Key: `tx_hash: [u8; 32]`
+/// - Value: `lt: u64 (BE), workchain: i8, account: [u8; 32]` (key in [`Transactions`])
+pub struct TxHashes;
+
+impl TxHashes {
+    pub const KEY_LEN: usize = 32;
+}
+
+impl ColumnFamily for TxHashes {
+    const NAME: &'static str = "tx_hashes";
+}
+
+impl ColumnFamilyOptions<Caches> for TxHashes {
+    fn options(opts: &mut Options, caches: &mut Caches) {
+        default_block_based_table_factory(opts, caches);
+
+        opts.set_optimize_filters_for_hits(true);
+        optimize_for_point_lookup(opts, caches);
+    }
+}
+
+/// Stores inbound message hashes index.
+/// - Key: `msg_hash: [u8; 32]`
+/// - Value: `lt: u64 (BE), workchain: i8, account: [u8; 32]` (key in [`Transactions`])
+pub struct InMsgs;
+
+impl InMsgs {
+    pub const KEY_LEN: usize = 32;
+}
+
+impl ColumnFamily for InMsgs {
+    const NAME: &'static str = "in_msgs";
+}
+
+impl ColumnFamilyOptions<Caches> for InMsgs {
+    fn options(opts: &mut Options, caches: &mut Caches) {
+        default_block_based_table_factory(opts, caches);
+
+        opts.set_optimize_filters_for_hits(true);
+        optimize_for_point_lookup(opts, caches);
+    }
+}
+
 /// Stores info for the start bound of the GC.
 ///
 /// - Key: `created_at: u32 (BE)`
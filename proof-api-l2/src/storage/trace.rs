@@ -0,0 +1,483 @@
+use anyhow::{Context, Result};
+use proof_api_util::block::{self, ProofChainFormat, TxContent, TychoModels};
+use tycho_types::models::{IntAddr, MsgInfo, StdAddr, Transaction};
+use tycho_types::prelude::*;
+use tycho_util::FastHashMap;
+use tycho_util::sync::CancellationFlag;
+use weedb::OwnedSnapshot;
+
+use super::{BlockKey, ProofChainParts, ProofDb, ProofStorage, TxBlockRef, check, tables};
+
+/// Max number of transactions in a single trace.
+const MAX_TRACE_LEN: usize = 64;
+/// Max number of destination account transactions to check
+/// while searching for the destination transaction of a single message.
+const MAX_DST_CANDIDATES: usize = 16;
+
+/// Transaction trace with proof chains for each transaction.
+pub struct TxTrace {
+    pub account: StdAddr,
+    pub lt: u64,
+    pub tx_hash: HashBytes,
+    /// Proof chain with the full transaction.
+    pub proof_chain: Cell,
+    /// Internal outbound messages of the transaction.
+    pub out_msgs: Vec<TraceMsg>,
+}
+
+/// Internal outbound message of the traced transaction.
+pub struct TraceMsg {
+    pub msg_hash: HashBytes,
+    pub dst: StdAddr,
+    /// `None` if the message was not processed yet (or the trace is too long).
+    pub tx: Option<TxTrace>,
+}
+
+impl ProofStorage {
+    /// Builds proof chains for the transaction and all transactions
+    /// which were caused by its internal outbound messages.
+    ///
+    /// Destination transactions are searched through the inbound messages
+    /// index (or through the account transactions index for older entries).
+    /// Proof chains always contain full transactions (message hashes
+    /// are required to link transactions).
+    ///
+    /// NOTE: Requires the full block data to be present in the core storage.
+    pub async fn build_trace(
+        &self,
+        account: &StdAddr,
+        lt: u64,
+        content: TxContent,
+    ) -> Result<Option<TxTrace>> {
+        self.build_trace_ext(FastHashMap::default(), account, lt, content)
+            .await
+    }
+
+    /// Builds a trace using (and filling) the cache of full blocks.
+    async fn build_trace_ext(
+        &self,
+        mut blocks: FullBlocks,
+        account: &StdAddr,
+        lt: u64,
+        content: TxContent,
+    ) -> Result<Option<TxTrace>> {
+        let content = TxContent {
+            full_tx: true,
+            ..content
+        };
+
+        let Some((tx_block, tx)) = self.load_tx(&mut blocks, account, lt).await? else {
+            return Ok(None);
+        };
+
+        // Collect trace transactions in BFS order (children always
+        // have greater indices than their parents).
+        let mut nodes = vec![TraceNode {
+            account: account.clone(),
+            lt,
+            tx_block,
+            tx,
+            out_msgs: Vec::new(),
+        }];
+
+        let mut i = 0;
+        while i < nodes.len() {
+            for (msg_hash, dst, created_lt) in parse_internal_out_msgs(&nodes[i].tx)? {
+                let mut child = None;
+                if nodes.len() < MAX_TRACE_LEN
+                    && let Some((lt, tx_block, tx)) = self
+                        .find_dst_tx(&mut blocks, &dst, created_lt, &msg_hash)
+                        .await?
+                {
+                    child = Some(nodes.len());
+                    nodes.push(TraceNode {
+                        account: dst.clone(),
+                        lt,
+                        tx_block,
+                        tx,
+                        out_msgs: Vec::new(),
+                    });
+                }
+
+                nodes[i].out_msgs.push((msg_hash, dst, child));
+            }
+            i += 1;
+        }
+
+        let proof_chains = self.build_trace_proofs(&blocks, &nodes, content).await?;
+
+        // Assemble the tree from leaves.
+        let mut traces = Vec::<Option<TxTrace>>::with_capacity(nodes.len());
+        traces.resize_with(nodes.len(), || None);
+
+        for ((i, node), proof_chain) in nodes.into_iter().enumerate().zip(proof_chains).rev() {
+            let out_msgs = node
+                .out_msgs
+                .into_iter()
+                .map(|(msg_hash, dst, child)| TraceMsg {
+                    msg_hash,
+                    dst,
+                    tx: child.and_then(|child| traces[child].take()),
+                })
+                .collect();
+
+            traces[i] = Some(TxTrace {
+                account: node.account,
+                lt: node.lt,
+                tx_hash: *node.tx.repr_hash(),
+                proof_chain,
+                out_msgs,
+            });
+        }
+
+        Ok(traces[0].take())
+    }
+
+    /// Builds proof chains for all trace transactions.
+    ///
+    /// Chain parts are shared by transactions from the same block
+    /// (unless their paths to the masterchain block differ).
+    async fn build_trace_proofs(
+        &self,
+        blocks: &FullBlocks,
+        nodes: &[TraceNode],
+        content: TxContent,
+    ) -> Result<Vec<Cell>> {
+        let this = self.inner.as_ref();
+
+        let mut items = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let (file_hash, block_root) = blocks
+                .get(&node.tx_block.block_key)
+                .cloned()
+                .flatten()
+                .context("trace tx block not found")?;
+            items.push((
+                node.account.clone(),
+                node.lt,
+                node.tx_block,
+                file_hash,
+                block_root,
+            ));
+        }
+
+        let cancelled = CancellationFlag::new();
+        scopeguard::defer! {
+            cancelled.cancel();
+        }
+
+        let db = this.db.clone();
+        let snapshot = this.snapshot.load_full();
+        let cancelled = cancelled.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut chains = FastHashMap::<BlockKey, Vec<ProofChainParts>>::default();
+
+            let mut result = Vec::with_capacity(items.len());
+            for (account, lt, tx_block, file_hash, block_root) in items {
+                check(&cancelled)?;
+
+                let chains = chains.entry(tx_block.block_key).or_default();
+                let cached = chains
+                    .iter()
+                    .find(|parts| parts.is_suitable_for(&account.address))
+                    .cloned();
+                let parts = match cached {
+                    Some(parts) => parts,
+                    None => {
+                        let parts = ProofChainParts::load(
+                            &db,
+                            &snapshot,
+                            &cancelled,
+                            tx_block,
+                            &file_hash,
+                            &account.address,
+                        )?;
+                        chains.push(parts.clone());
+                        parts
+                    }
+                };
+
+                check(&cancelled)?;
+
+                let tx_proof = block::make_tx_proof_ext::<TychoModels>(
+                    block_root,
+                    &account.address,
+                    lt,
+                    account.is_masterchain(),
+                    content,
+                )?
+                .context("trace tx not found in block")?;
+
+                result.push(parts.finish(tx_proof, ProofChainFormat::V1)?);
+            }
+
+            Ok::<_, anyhow::Error>(result)
+        })
+        .await?
+    }
+
+    /// Finds a transaction of the `dst` account with the specified inbound message.
+    async fn find_dst_tx(
+        &self,
+        blocks: &mut FullBlocks,
+        dst: &StdAddr,
+        created_lt: u64,
+        msg_hash: &HashBytes,
+    ) -> Result<Option<(u64, TxBlockRef, Cell)>> {
+        if let Some((account, lt)) = self.find_tx_by_in_msg(msg_hash)?
+            && account == *dst
+        {
+            return Ok(self
+                .load_tx(blocks, &account, lt)
+                .await?
+                .map(|(tx_block, tx)| (lt, tx_block, tx)));
+        }
+
+        let candidates = {
+            let db = self.inner.db.clone();
+            let snapshot = self.inner.snapshot.load_full();
+            let dst = dst.clone();
+            tokio::task::spawn_blocking(move || {
+                find_account_txs_after(&db, &snapshot, &dst, created_lt)
+            })
+            .await??
+        };
+
+        for (lt, tx_block) in candidates {
+            let Some((_, block_root)) = self.load_block_root(blocks, &tx_block).await? else {
+                continue;
+            };
+            let Some(tx) = block::find_transaction::<TychoModels>(&block_root, &dst.address, lt)?
+            else {
+                continue;
+            };
+
+            let in_msg = tx.parse::<Transaction>()?.in_msg;
+            if in_msg.is_some_and(|msg| msg.repr_hash() == msg_hash) {
+                return Ok(Some((lt, tx_block, tx)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn load_tx(
+        &self,
+        blocks: &mut FullBlocks,
+        account: &StdAddr,
+        lt: u64,
+    ) -> Result<Option<(TxBlockRef, Cell)>> {
+        let Some(tx_block) = self.find_tx_block(account, lt)? else {
+            return Ok(None);
+        };
+        let Some((_, block_root)) = self.load_block_root(blocks, &tx_block).await? else {
+            return Ok(None);
+        };
+
+        let tx = block::find_transaction::<TychoModels>(&block_root, &account.address, lt)?;
+        Ok(tx.map(|tx| (tx_block, tx)))
+    }
+
+    async fn load_block_root(
+        &self,
+        blocks: &mut FullBlocks,
+        tx_block: &TxBlockRef,
+    ) -> Result<Option<(HashBytes, Cell)>> {
+        if let Some(block) = blocks.get(&tx_block.block_key) {
+            return Ok(block.clone());
+        }
+
+        let block = self
+            .load_full_block(tx_block)
+            .await?
+            .map(|(block_id, block)| (block_id.file_hash, block.root_cell().clone()));
+
+        blocks.insert(tx_block.block_key, block.clone());
+        Ok(block)
+    }
+}
+
+/// Full blocks (file hash and root) which were loaded while building a trace.
+type FullBlocks = FastHashMap<BlockKey, Option<(HashBytes, Cell)>>;
+
+struct TraceNode {
+    account: StdAddr,
+    lt: u64,
+    tx_block: TxBlockRef,
+    tx: Cell,
+    out_msgs: Vec<(HashBytes, StdAddr, Option<usize>)>,
+}
+
+/// Returns hashes, destinations and lt of all internal outbound messages.
+fn parse_internal_out_msgs(tx: &Cell) -> Result<Vec<(HashBytes, StdAddr, u64)>> {
+    let tx = tx.parse::<Transaction>()?;
+
+    let mut result = Vec::new();
+    for msg in tx.out_msgs.values() {
+        let msg = msg?;
+        let MsgInfo::Int(info) = msg.parse::<MsgInfo>()? else {
+            continue;
+        };
+        let IntAddr::Std(dst) = info.dst else {
+            continue;
+        };
+        result.push((*msg.repr_hash(), dst, info.created_lt));
+    }
+
+    Ok(result)
+}
+
+/// Returns the first account transactions after the specified lt.
+fn find_account_txs_after(
+    db: &ProofDb,
+    snapshot: &OwnedSnapshot,
+    account: &StdAddr,
+    after_lt: u64,
+) -> Result<Vec<(u64, TxBlockRef)>> {
+    let mut from_key = [0; tables::AccountTransactions::KEY_LEN];
+    from_key[0] = account.workchain as u8;
+    from_key[1..33].copy_from_slice(account.address.as_slice());
+    from_key[33..41].copy_from_slice(&after_lt.saturating_add(1).to_be_bytes());
+
+    let mut iter = snapshot.raw_iterator_cf_opt(
+        &db.account_transactions.cf(),
+        db.account_transactions.new_read_config(),
+    );
+    iter.seek(from_key);
+
+    let mut result = Vec::new();
+    while result.len() < MAX_DST_CANDIDATES {
+        let (Some(key), Some(value)) = (iter.key(), iter.value()) else {
+            break;
+        };
+        if key[..33] != from_key[..33] {
+            break;
+        }
+
+        let lt = u64::from_be_bytes(key[33..41].try_into().unwrap());
+        result.push((lt, TxBlockRef::from_tx_value(value)));
+        iter.next();
+    }
+    iter.status()?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use proof_api_util::block::fixtures::{
+        BlockBuilder, Network, make_full_tx, make_int_msg, make_msg,
+    };
+    use proof_api_util::block::{TrustedVset, verify_proof_chain};
+    use tycho_storage::fs::Dir;
+    use tycho_types::models::ShardIdent;
+    use tycho_util::time::now_sec;
+
+    use super::*;
+    use crate::storage::tests::store_signed_blocks;
+    use crate::storage::{ProofStorageConfig, make_block_key};
+
+    #[tokio::test]
+    async fn build_trace_from_indices() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let storage =
+            ProofStorage::new(&Dir::new(tmp.path())?, ProofStorageConfig::default()).await?;
+
+        let address1 = StdAddr::new(0, HashBytes([0x11; 32]));
+        let address2 = StdAddr::new(0, HashBytes([0x22; 32]));
+        let address3 = StdAddr::new(0, HashBytes([0x33; 32]));
+
+        // tx1 -> tx2 -> (not processed)
+        //     -> tx3
+        let shard_block =
+            BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10).with_gen_utime(now_sec());
+        let lt = shard_block.start_lt();
+        let msg12 = make_int_msg(&address2, lt + 1)?;
+        let msg13 = make_int_msg(&address3, lt + 1)?;
+        let msg21 = make_int_msg(&address1, lt + 2)?;
+        let tx1 = make_full_tx(&address1.address, lt + 1, Some(make_msg(1)?), &[
+            msg12.clone(),
+            msg13.clone(),
+        ])?;
+        let tx2 = make_full_tx(&address2.address, lt + 2, Some(msg12.clone()), &[
+            msg21.clone()
+        ])?;
+        let tx3 = make_full_tx(&address3.address, lt + 3, Some(msg13.clone()), &[])?;
+        let shard_block = shard_block
+            .with_raw_transaction(&address1.address, lt + 1, tx1.clone())
+            .with_raw_transaction(&address2.address, lt + 2, tx2.clone())
+            .with_raw_transaction(&address3.address, lt + 3, tx3.clone())
+            .build()?;
+
+        let mc_block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+            .with_gen_utime(now_sec())
+            .with_shard_block(&shard_block.id)
+            .build()?;
+        let mc_seqno = mc_block.id.seqno;
+        let vset = store_signed_blocks(&storage, &[(&shard_block, mc_seqno)], &[&mc_block]).await?;
+
+        // NOTE: Only the account transactions index can be used for tx3.
+        storage
+            .inner
+            .db
+            .in_msgs
+            .remove(msg13.repr_hash().as_slice())?;
+        assert!(storage.find_tx_by_in_msg(msg13.repr_hash())?.is_none());
+
+        // Full blocks are taken from the cache instead of the core storage.
+        let blocks = FullBlocks::from_iter([(
+            make_block_key(&shard_block.id.shard, shard_block.id.seqno),
+            Some((shard_block.id.file_hash, shard_block.root.clone())),
+        )]);
+
+        let trace = storage
+            .build_trace_ext(blocks.clone(), &address1, lt + 1, TxContent::default())
+            .await?
+            .context("trace not found")?;
+
+        let check_trace = |trace: &TxTrace, tx: &Cell| -> Result<()> {
+            assert_eq!(trace.tx_hash, *tx.repr_hash());
+            let tx_hash = verify_proof_chain::<TychoModels>(
+                trace.proof_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                &trace.account,
+                trace.lt,
+                None,
+            )?;
+            assert_eq!(tx_hash, trace.tx_hash);
+            Ok(())
+        };
+
+        check_trace(&trace, &tx1)?;
+        assert_eq!(trace.out_msgs.len(), 2);
+
+        // Destination transaction found through the inbound messages index.
+        assert_eq!(trace.out_msgs[0].msg_hash, *msg12.repr_hash());
+        assert_eq!(trace.out_msgs[0].dst, address2);
+        let trace2 = trace.out_msgs[0].tx.as_ref().context("tx2 not found")?;
+        assert_eq!(trace2.lt, lt + 2);
+        check_trace(trace2, &tx2)?;
+
+        // Message which was not processed yet.
+        assert_eq!(trace2.out_msgs.len(), 1);
+        assert_eq!(trace2.out_msgs[0].msg_hash, *msg21.repr_hash());
+        assert!(trace2.out_msgs[0].tx.is_none());
+
+        // Destination transaction found through the account transactions index.
+        assert_eq!(trace.out_msgs[1].msg_hash, *msg13.repr_hash());
+        let trace3 = trace.out_msgs[1].tx.as_ref().context("tx3 not found")?;
+        assert_eq!(trace3.lt, lt + 3);
+        check_trace(trace3, &tx3)?;
+        assert!(trace3.out_msgs.is_empty());
+
+        // Unknown transaction.
+        assert!(
+            storage
+                .build_trace_ext(blocks, &address1, lt + 2, TxContent::default())
+                .await?
+                .is_none()
+        );
+
+        Ok(())
+    }
+}
@@ -1,4 +1,3 @@
-use std::borrow::Cow;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::num::NonZeroU32;
 use std::sync::Arc;
@@ -7,7 +6,7 @@ use std::time::Duration;
 use aide::axum::ApiRouter;
 use aide::axum::routing::get_with;
 use aide::transform::TransformOperation;
-use axum::extract::{ConnectInfo, DefaultBodyLimit, Path, State};
+use axum::extract::{ConnectInfo, DefaultBodyLimit, Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::{Extension, Router};
@@ -15,17 +14,17 @@ use governor::clock::DefaultClock;
 use governor::state::keyed::DefaultKeyedStateStore;
 use governor::{Quota, RateLimiter};
 use proof_api_util::api::{
-    ApiRouterExt, JSON_HEADERS_CACHE_1W, JSON_HEADERS_DONT_CACHE, OpenApiConfig, get_version,
-    prepare_open_api,
+    ApiRouterExt, JSON_HEADERS_CACHE_1W, JSON_HEADERS_DONT_CACHE, OpenApiConfig, ProofChainQuery,
+    ProofChainResponse, TxProofChainQuery, get_version, prepare_open_api,
 };
-use proof_api_util::serde_helpers::TonAddr;
+use proof_api_util::block::{ProofChainFormat, ProofKind};
+use proof_api_util::serde_helpers::{MsgHash, TonAddr, TxHash};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tower_http::timeout::TimeoutLayer;
-use tycho_types::boc::Boc;
-use tycho_types::cell::HashBytes;
+use tycho_types::cell::Cell;
 use tycho_util::sync::rayon_run;
 use tycho_util::{FastHashSet, FastHasherState};
 
@@ -78,6 +77,18 @@ pub fn build_api(config: &ApiConfig, client: TonClient) -> Router {
             "/v1/proof_chain/{address}/{lt}/{hash}",
             get_with(get_proof_chain_v1, get_proof_chain_v1_docs),
         )
+        .api_route(
+            "/v1/out_msg_proof_chain/{address}/{lt}/{hash}/{msg_hash}",
+            get_with(get_out_msg_proof_chain_v1, get_out_msg_proof_chain_v1_docs),
+        )
+        .api_route(
+            "/v2/proof_chain/{address}/{lt}/{hash}",
+            get_with(get_proof_chain_v2, get_proof_chain_v2_docs),
+        )
+        .api_route(
+            "/v2/out_msg_proof_chain/{address}/{lt}/{hash}/{msg_hash}",
+            get_with(get_out_msg_proof_chain_v2, get_out_msg_proof_chain_v2_docs),
+        )
         .with_docs()
         .layer(
             ServiceBuilder::new()
@@ -103,72 +114,139 @@ pub fn build_api(config: &ApiConfig, client: TonClient) -> Router {
 
 // === V1 Routes ===
 
-/// Block proof chain for an existing transaction.
-#[derive(Debug, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "camelCase")]
-pub struct ProofChainResponse {
-    /// Base64 encoded BOC with the proof chain.
-    pub proof_chain: String,
+async fn get_proof_chain_v1(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(path): Path<(TonAddr, u64, TxHash)>,
+    Query(query): Query<TxProofChainQuery>,
+) -> Response {
+    tx_proof_chain_response(&state, addr, path, query, ProofChainFormat::V1).await
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TxHash(pub HashBytes);
+fn get_proof_chain_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chain")
+        .tag("proof-api-ton")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
 
-impl schemars::JsonSchema for TxHash {
-    fn schema_name() -> Cow<'static, str> {
-        Cow::Borrowed("Transaction hash")
-    }
+async fn get_out_msg_proof_chain_v1(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(path): Path<(TonAddr, u64, TxHash, MsgHash)>,
+    Query(query): Query<ProofChainQuery>,
+) -> Response {
+    out_msg_proof_chain_response(&state, addr, path, query, ProofChainFormat::V1).await
+}
 
-    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
-        let mut schema = generator.subschema_for::<String>();
-        let object = schema.ensure_object();
-        object.insert("description".into(), "Transaction hash as hex".into());
-        object.insert("format".into(), "[0-9a-fA-F]{64}".into());
-        object.insert(
-            "examples".into(),
-            vec![serde_json::json!(
-                "3333333333333333333333333333333333333333333333333333333333333333"
-            )]
-            .into(),
-        );
-        schema
-    }
+fn get_out_msg_proof_chain_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build proof chain for an outbound message")
+        .tag("proof-api-ton")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
 }
 
-async fn get_proof_chain_v1(
+// === V2 Routes ===
+
+async fn get_proof_chain_v2(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(path): Path<(TonAddr, u64, TxHash)>,
+    Query(query): Query<TxProofChainQuery>,
+) -> Response {
+    let format = ProofChainFormat::V2(ProofKind::Tx);
+    tx_proof_chain_response(&state, addr, path, query, format).await
+}
+
+fn get_proof_chain_v2_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build versioned proof chain")
+        .tag("proof-api-ton")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+async fn get_out_msg_proof_chain_v2(
     State(state): State<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path((TonAddr(address), lt, TxHash(tx_hash))): Path<(TonAddr, u64, TxHash)>,
+    Path(path): Path<(TonAddr, u64, TxHash, MsgHash)>,
+    Query(query): Query<ProofChainQuery>,
+) -> Response {
+    let format = ProofChainFormat::V2(ProofKind::OutMsg);
+    out_msg_proof_chain_response(&state, addr, path, query, format).await
+}
+
+fn get_out_msg_proof_chain_v2_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
+    op.description("Build versioned proof chain for an outbound message")
+        .tag("proof-api-ton")
+        .response::<200, axum::Json<ProofChainResponse>>()
+        .response::<404, ()>()
+        .response::<500, axum::Json<ErrorResponse>>()
+}
+
+/// Builds a transaction proof chain in the specified format.
+async fn tx_proof_chain_response(
+    state: &AppState,
+    addr: SocketAddr,
+    (TonAddr(address), lt, TxHash(tx_hash)): (TonAddr, u64, TxHash),
+    query: TxProofChainQuery,
+    format: ProofChainFormat,
 ) -> Response {
     let ip = addr.ip();
     if !state.whitelist.contains(&ip) && state.governor.check_key(&ip).is_err() {
         return res_error(ErrorResponse::LimitExceed);
     }
 
-    match state.client.build_proof(&address, lt, &tx_hash).await {
-        Ok(proof_chain) => {
-            rayon_run(move || {
-                let data = serde_json::to_vec(&ProofChainResponse {
-                    proof_chain: Boc::encode_base64(proof_chain),
-                })
-                .unwrap();
-
-                (JSON_HEADERS_CACHE_1W, axum::body::Bytes::from(data)).into_response()
-            })
-            .await
-        }
+    match state
+        .client
+        .build_proof(&address, lt, &tx_hash, query.tx_content(), format)
+        .await
+    {
+        Ok(proof_chain) => rayon_run(move || proof_chain_response(proof_chain, query.stats)).await,
         Err(e) => res_error(ErrorResponse::Internal {
             message: e.to_string(),
         }),
     }
 }
 
-fn get_proof_chain_v1_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
-    op.description("Build proof chain")
-        .tag("proof-api-ton")
-        .response::<200, axum::Json<ProofChainResponse>>()
-        .response::<404, ()>()
-        .response::<500, axum::Json<ErrorResponse>>()
+/// Builds an outbound message proof chain in the specified format.
+async fn out_msg_proof_chain_response(
+    state: &AppState,
+    addr: SocketAddr,
+    (TonAddr(address), lt, TxHash(tx_hash), MsgHash(msg_hash)): (TonAddr, u64, TxHash, MsgHash),
+    query: ProofChainQuery,
+    format: ProofChainFormat,
+) -> Response {
+    let ip = addr.ip();
+    if !state.whitelist.contains(&ip) && state.governor.check_key(&ip).is_err() {
+        return res_error(ErrorResponse::LimitExceed);
+    }
+
+    match state
+        .client
+        .build_out_msg_proof(&address, lt, &tx_hash, &msg_hash, format)
+        .await
+    {
+        Ok(proof_chain) => rayon_run(move || proof_chain_response(proof_chain, query.stats)).await,
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Serializes the proof chain of an existing item.
+fn proof_chain_response(proof_chain: Cell, stats: bool) -> Response {
+    match ProofChainResponse::new(proof_chain, stats) {
+        Ok(res) => {
+            let data = serde_json::to_vec(&res).unwrap();
+            (JSON_HEADERS_CACHE_1W, axum::body::Bytes::from(data)).into_response()
+        }
+        Err(e) => res_error(ErrorResponse::Internal {
+            message: format!("failed to analyze proof chain: {e}"),
+        }),
+    }
 }
 
 /// General error response.
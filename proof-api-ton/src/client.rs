@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use proof_api_util::block::{
     self, BlockchainBlock, BlockchainBlockExtra, BlockchainBlockMcExtra, BlockchainModels,
-    TonModels,
+    ProofChainFormat, TonModels, TxContent,
 };
 use ton_lite_client::{LiteClient, proto};
 use tycho_types::merkle::MerkleProof;
@@ -24,6 +24,8 @@ impl TonClient {
         account: &StdAddr,
         lt: u64,
         tx_hash: &HashBytes,
+        content: TxContent,
+        format: ProofChainFormat,
     ) -> Result<Cell> {
         let block_id = self.find_transaction_block_id(account, lt, tx_hash).await?;
         tracing::debug!(%block_id, %tx_hash, "found transaction block id");
@@ -31,20 +33,68 @@ impl TonClient {
         let is_masterchain = account.is_masterchain();
 
         let block_root = self.lite_client.get_block(&block_id).await?;
-        let tx_proof =
-            block::make_tx_proof::<TonModels>(block_root, &account.address, lt, is_masterchain)
-                .context("failed to build tx proof")?
-                .context("tx not found in block")?;
+        let tx_proof = block::make_tx_proof_ext::<TonModels>(
+            block_root,
+            &account.address,
+            lt,
+            is_masterchain,
+            content,
+        )
+        .context("failed to build tx proof")?
+        .context("tx not found in block")?;
+
+        self.build_proof_chain(account, &block_id, tx_proof, format)
+            .await
+    }
 
+    /// Builds a proof chain for an outbound message of the specified transaction.
+    pub async fn build_out_msg_proof(
+        &self,
+        account: &StdAddr,
+        lt: u64,
+        tx_hash: &HashBytes,
+        msg_hash: &HashBytes,
+        format: ProofChainFormat,
+    ) -> Result<Cell> {
+        let block_id = self.find_transaction_block_id(account, lt, tx_hash).await?;
+        tracing::debug!(%block_id, %tx_hash, %msg_hash, "found transaction block id");
+
+        let is_masterchain = account.is_masterchain();
+
+        let block_root = self.lite_client.get_block(&block_id).await?;
+        let msg_proof = block::make_out_msg_proof::<TonModels>(
+            block_root,
+            &account.address,
+            lt,
+            msg_hash,
+            is_masterchain,
+        )
+        .context("failed to build out msg proof")?
+        .context("out msg of the transaction not found in block")?;
+
+        self.build_proof_chain(account, &block_id, msg_proof, format)
+            .await
+    }
+
+    /// Wraps a proof for the item in the specified block into a proof chain.
+    ///
+    /// NOTE: Target proof for masterchain blocks must include block info.
+    async fn build_proof_chain(
+        &self,
+        account: &StdAddr,
+        block_id: &BlockId,
+        target_proof: Cell,
+        format: ProofChainFormat,
+    ) -> Result<Cell> {
         let mc_proof;
         let file_hash;
         let vset_utime_since;
         let signatures;
         let mut shard_proofs = Vec::new();
-        if account.is_masterchain() {
+        if block_id.is_masterchain() {
             // No shard blocks are required in addition to masterchain proof.
             file_hash = block_id.file_hash;
-            mc_proof = tx_proof;
+            mc_proof = target_proof;
 
             let prev_block_id = mc_proof
                 .parse::<<TonModels as BlockchainModels>::Block>()?
@@ -56,12 +106,12 @@ impl TonClient {
             // Find masterchain block proof.
             let mc_block_link = self
                 .lite_client
-                .get_block_proof(&prev_block_id, Some(&block_id), true)
+                .get_block_proof(&prev_block_id, Some(block_id), true)
                 .await
                 .context("failed to get mc block proof")?;
 
             // Build signatures dict.
-            let mc = self.parse_mc_block_proof(mc_block_link, &block_id).await?;
+            let mc = self.parse_mc_block_proof(mc_block_link, block_id).await?;
 
             vset_utime_since = mc.vset_utime_since;
             signatures = mc.signatures;
@@ -69,7 +119,7 @@ impl TonClient {
             // Find masterchain block id and get all proof links until the shard block.
             let proto::ShardBlockProof { mc_block_id, links } = self
                 .lite_client
-                .get_shard_block_proof(&block_id)
+                .get_shard_block_proof(block_id)
                 .await
                 .context("failed to get shard block proof")?;
 
@@ -122,16 +172,21 @@ impl TonClient {
                 shard_proofs.push(proof);
             }
 
-            shard_proofs.push(tx_proof);
+            shard_proofs.push(target_proof);
 
             mc_proof = merge_mc_block_proof(
                 mc.header_proof,
                 mc_extra_root.context("masterchain extra root not found")?,
-                block_id.shard,
+                account,
             )?;
+
+            // NOTE: Liteserver can choose another path in case of shard splits or merges.
+            block::verify_shard_chain::<TonModels>(&mc_proof, &shard_proofs, account)
+                .context("shard block proof links don't lead to the account")?;
         }
 
-        let proof_chain = block::make_proof_chain(
+        let proof_chain = block::make_proof_chain_ext::<TonModels>(
+            format,
             &file_hash,
             mc_proof,
             &shard_proofs,
@@ -188,10 +243,10 @@ impl TonClient {
             .context("failed to parse config proof cell")?
             .cell;
 
-        let vset = 'vset: {
+        let (vset, signature_id) = 'vset: {
             for _ in 0..2 {
                 match parse_current_vset(config_proof) {
-                    Ok(vset) => break 'vset vset,
+                    Ok(res) => break 'vset res,
                     Err(ParseVsetError::Invalid(e)) => return Err(e),
                     Err(ParseVsetError::PrunedVset) => {
                         tracing::debug!("fallback to downloading a full key block");
@@ -207,6 +262,14 @@ impl TonClient {
             return Err(ParseVsetError::PrunedVset.into());
         };
 
+        block::check_signatures(
+            mc_block_id,
+            forward.signatures.signatures.iter().cloned().map(Ok),
+            &vset,
+            signature_id,
+        )
+        .context("invalid block signatures")?;
+
         let signatures =
             block::prepare_signatures(forward.signatures.signatures.into_iter().map(Ok), &vset)
                 .context("failed to prepare block signature")?;
@@ -225,7 +288,8 @@ struct McProof {
     signatures: Cell,
 }
 
-fn parse_current_vset(config_proof: Cell) -> Result<ValidatorSet, ParseVsetError> {
+/// Returns the current validator set and the signature id of the key block.
+fn parse_current_vset(config_proof: Cell) -> Result<(ValidatorSet, Option<i32>), ParseVsetError> {
     let block = config_proof.parse::<<TonModels as BlockchainModels>::Block>()?;
 
     let mc_extra = block
@@ -247,18 +311,30 @@ fn parse_current_vset(config_proof: Cell) -> Result<ValidatorSet, ParseVsetError
         return Err(ParseVsetError::PrunedVset);
     }
 
-    config
+    // NOTE: Global version is required to get the signature id.
+    let global_version_cell = config
+        .get_raw_cell(8)
+        .context("failed to get global version")?;
+    if matches!(global_version_cell, Some(cell) if cell.descriptor().is_pruned_branch()) {
+        return Err(ParseVsetError::PrunedVset);
+    }
+
+    let vset = config
         .get::<ConfigParam34>()
         .context("failed to load current vset")?
-        .context("current validator set not found")
-        .map_err(ParseVsetError::Invalid)
+        .context("current validator set not found")?;
+
+    let signature_id = block::signature_id_from_config(block.global_id(), &config.params)
+        .context("failed to get signature id")?;
+
+    Ok((vset, signature_id))
 }
 
 #[derive(thiserror::Error, Debug)]
 enum ParseVsetError {
     #[error(transparent)]
     Invalid(#[from] anyhow::Error),
-    #[error("current vset cell is pruned")]
+    #[error("current vset or global version cell is pruned")]
     PrunedVset,
 }
 
@@ -272,7 +348,7 @@ impl From<tycho_types::error::Error> for ParseVsetError {
 fn merge_mc_block_proof(
     header_proof: Vec<u8>,
     extra_proof: Cell,
-    shard_ident: ShardIdent,
+    account: &StdAddr,
 ) -> Result<Cell> {
     // Parse proof for block info.
     let header_proof = Boc::decode(header_proof)
@@ -300,7 +376,12 @@ fn merge_mc_block_proof(
         CellBuilder::build_from((info, extra_cs)).context("failed to build mc block proof")?;
 
     // Minimize the proof.
-    let proof = block::make_mc_proof::<TonModels>(proof.clone(), shard_ident)?.root;
+    let proof = block::make_mc_proof::<TonModels>(
+        proof.clone(),
+        account.workchain as i32,
+        &account.address,
+    )?
+    .root;
 
     // Done.
     Ok(proof)
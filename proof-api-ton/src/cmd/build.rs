@@ -3,6 +3,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use clap::Parser;
 use proof_api_ton::client::TonClient;
+use proof_api_util::block::{ProofChainFormat, TxContent};
 use ton_lite_client::{LiteClient, LiteClientConfig, TonGlobalConfig};
 use tycho_types::boc::Boc;
 use tycho_types::cell::HashBytes;
@@ -25,6 +26,18 @@ pub struct Cmd {
     // Path to the TON global config.
     #[clap(long)]
     global_config: PathBuf,
+
+    /// Include the full transaction cell instead of only its hash.
+    #[clap(long)]
+    full_tx: bool,
+
+    /// Include the full inbound message (implies `--full-tx`).
+    #[clap(long)]
+    in_msg_body: bool,
+
+    /// Include all full outbound messages (implies `--full-tx`).
+    #[clap(long)]
+    out_msg_bodies: bool,
 }
 
 impl Cmd {
@@ -37,7 +50,17 @@ impl Cmd {
         let client = TonClient::new(lite_client);
 
         let proof_chain = client
-            .build_proof(&self.address, self.lt, &self.hash)
+            .build_proof(
+                &self.address,
+                self.lt,
+                &self.hash,
+                TxContent {
+                    full_tx: self.full_tx,
+                    in_msg_body: self.in_msg_body,
+                    out_msg_bodies: self.out_msg_bodies,
+                },
+                ProofChainFormat::V1,
+            )
             .await
             .context("failed to build proof")?;
 
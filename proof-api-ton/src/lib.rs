@@ -2,6 +2,7 @@ use std::sync::OnceLock;
 
 pub mod api;
 pub mod client;
+pub mod signatures;
 
 pub static BIN_VERSION: &str = env!("PROOFS_API_VERSION");
 pub static BIN_BUILD: &str = env!("PROOFS_API_BUILD");
@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use proof_api_util::block;
+use proof_api_util::block::ton::TonBlockSignatures;
+use ton_lite_client::proto;
+use tycho_types::models::{ValidatorBaseInfo, ValidatorSet};
+
+/// Converts lite-server block signatures into the `#11` block signatures.
+///
+/// Fails if some signer is not in the `vset` or their weight is not enough.
+pub fn from_signature_set(
+    set: &proto::SignatureSet,
+    vset: &ValidatorSet,
+) -> Result<TonBlockSignatures> {
+    let validator_info = ValidatorBaseInfo {
+        validator_list_hash_short: set.validator_set_hash,
+        catchain_seqno: set.catchain_seqno,
+    };
+
+    TonBlockSignatures::from_list(validator_info, &set.signatures, vset)
+        .context("invalid signature set")
+}
+
+/// Converts `#11` block signatures into lite-server block signatures.
+///
+/// Fails if stored signature count or weight are not consistent with the `vset`.
+pub fn to_signature_set(
+    signatures: &TonBlockSignatures,
+    vset: &ValidatorSet,
+) -> Result<proto::SignatureSet> {
+    let list =
+        block::collect_block_signatures(signatures, vset).context("invalid block signatures")?;
+
+    Ok(proto::SignatureSet {
+        validator_set_hash: signatures.validator_info.validator_list_hash_short,
+        catchain_seqno: signatures.validator_info.catchain_seqno,
+        signatures: list,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proof_api_util::block::fixtures::{BlockBuilder, Network, Validators};
+    use tycho_types::models::ShardIdent;
+
+    use super::*;
+
+    #[test]
+    fn signature_set_roundtrip() -> Result<()> {
+        let validators = Validators::new(2, 5);
+        let vset = validators.make_vset(1000, 4);
+        let block = BlockBuilder::new(Network::Ton, ShardIdent::MASTERCHAIN, 5).build()?;
+
+        let set = proto::SignatureSet {
+            validator_set_hash: 123,
+            catchain_seqno: 456,
+            signatures: validators.sign(&block.id, None, [4, 2, 3]),
+        };
+        let signatures = from_signature_set(&set, &vset)?;
+        assert_eq!(signatures.validator_info.validator_list_hash_short, 123);
+        assert_eq!(signatures.validator_info.catchain_seqno, 456);
+        assert_eq!(signatures.signature_count, 3);
+
+        let converted = to_signature_set(&signatures, &vset)?;
+        assert_eq!(converted.validator_set_hash, set.validator_set_hash);
+        assert_eq!(converted.catchain_seqno, set.catchain_seqno);
+        assert_eq!(converted.signatures, set.signatures);
+
+        // Not enough weight (exactly 2/3).
+        let set = proto::SignatureSet {
+            signatures: validators.sign(&block.id, None, [0, 1, 2, 3]),
+            ..set
+        };
+        assert!(from_signature_set(&set, &vset).is_err());
+
+        Ok(())
+    }
+}
@@ -202,6 +202,8 @@ pub struct KeyBlockData {
     pub prev_key_block_seqno: u32,
     pub current_vset: ValidatorSet,
     pub prev_vset: Option<ValidatorSet>,
+    /// Signature id from the config of this key block.
+    pub signature_id: Option<i32>,
     pub signatures: Vec<BlockSignature>,
 }
 
@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use proof_api_util::block::{
     BlockchainBlock, BlockchainBlockExtra, BlockchainBlockMcExtra, BlockchainModels, TonModels,
-    make_key_block_proof,
+    make_key_block_proof, signature_id_from_config,
 };
 use ton_lite_client::{LiteClient, proto};
 use tycho_types::cell::Lazy;
@@ -126,6 +126,7 @@ impl NetworkClient for TonClient {
             signatures: proof.signatures.signatures,
             current_vset: config.get_current_validator_set()?,
             prev_vset: config.get_previous_validator_set()?,
+            signature_id: signature_id_from_config(block.global_id(), &config.params)?,
         })
     }
 
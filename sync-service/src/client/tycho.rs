@@ -2,13 +2,11 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use proof_api_util::block::{
     BaseBlockProof, BlockchainBlock, BlockchainBlockExtra, BlockchainBlockMcExtra,
-    BlockchainModels, TychoModels, make_key_block_proof,
+    BlockchainModels, TychoModels, make_key_block_proof, signature_id_from_config,
 };
 use tycho_types::cell::Lazy;
 use tycho_types::merkle::MerkleProof;
-use tycho_types::models::{
-    BlockSignatures, BlockchainConfig, GlobalCapability, StdAddr, Transaction,
-};
+use tycho_types::models::{BlockSignatures, BlockchainConfig, StdAddr, Transaction};
 use tycho_types::prelude::*;
 
 use crate::client::{KeyBlockData, NetworkClient};
@@ -37,11 +35,10 @@ impl NetworkClient for TychoClient {
 
     async fn get_signature_id(&self) -> Result<Option<i32>> {
         let current = self.rpc.get_latest_config().await?;
-        let global = current.config.get_global_version()?;
-        Ok(global
-            .capabilities
-            .contains(GlobalCapability::CapSignatureWithId)
-            .then_some(current.global_id))
+        Ok(signature_id_from_config(
+            current.global_id,
+            &current.config,
+        )?)
     }
 
     async fn get_latest_key_block_seqno(&self) -> Result<u32> {
@@ -65,7 +62,7 @@ impl NetworkClient for TychoClient {
         let proof = BocRepr::decode_base64::<BaseBlockProof<BlockSignatures>, _>(proof)
             .context("failed to deserialize key block proof")?;
 
-        // TODO: Check signatures.
+        // NOTE: Signatures are checked by the uploader with the previous vset.
         let signatures = match proof.signatures {
             Some(data) => {
                 let mut signatures = Vec::new();
@@ -95,6 +92,7 @@ impl NetworkClient for TychoClient {
             signatures,
             current_vset: config.get_current_validator_set()?,
             prev_vset: config.get_previous_validator_set()?,
+            signature_id: signature_id_from_config(block.global_id(), &config.params)?,
         })
     }
 
@@ -4,12 +4,17 @@ use std::time::Duration;
 
 use anyhow::{Context, Result};
 use num_traits::ToPrimitive;
-use proof_api_util::block::{make_epoch_data, prepare_signatures};
+use proof_api_util::block::{
+    AnyModels, KeyBlockChainVerifier, SignaturesMode, find_signatures_outside_main_set,
+    make_epoch_data, prepare_signatures_ext,
+};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use tycho_types::cell::{CellBuilder, HashBytes};
+use tycho_types::cell::{Cell, CellBuilder, HashBytes};
 use tycho_types::merkle::MerkleProof;
-use tycho_types::models::{Account, AccountState, BlockchainConfig, ComputePhase, StdAddr, TxInfo};
+use tycho_types::models::{
+    Account, AccountState, BlockchainConfig, ComputePhase, StdAddr, TxInfo, ValidatorSet,
+};
 use tycho_types::num::Tokens;
 use tycho_util::serde_helpers;
 
@@ -42,6 +47,10 @@ pub struct UploaderConfig {
 
     #[serde(default = "default_retry_interval", with = "serde_helpers::humantime")]
     pub retry_interval: Duration,
+
+    /// Which key block signatures to send to the bridge.
+    #[serde(default)]
+    pub signatures_mode: SignaturesMode,
 }
 
 fn default_poll_interval() -> Duration {
@@ -60,6 +69,8 @@ pub struct Uploader {
     blockchain_config: BlockchainConfig,
     /// Cache of key blocks from the `src` network.
     key_blocks_cache: BTreeMap<u32, Arc<KeyBlockData>>,
+    /// Verifier of key blocks from the `src` network.
+    trusted_key_blocks: TrustedKeyBlocks,
     wallet: Wallet,
     min_bridge_state_lt: u64,
     last_checked_vset: u32,
@@ -99,6 +110,7 @@ impl Uploader {
             config,
             blockchain_config,
             key_blocks_cache: Default::default(),
+            trusted_key_blocks: Default::default(),
             wallet,
             min_bridge_state_lt: 0,
             last_checked_vset: 0,
@@ -129,13 +141,15 @@ impl Uploader {
             self.last_checked_vset = current_vset_utime_since;
         }
 
-        let Some(key_block) = self.find_next_key_block(current_vset_utime_since).await? else {
+        let Some((prev_key_block, key_block)) =
+            self.find_next_key_block(current_vset_utime_since).await?
+        else {
             tracing::debug!(current_vset_utime_since, "no new key blocks found");
             return Ok(());
         };
 
         tracing::info!(block_id = %key_block.block_id, "sending key block");
-        self.send_key_block(key_block.clone())
+        self.send_key_block(&prev_key_block, key_block.clone())
             .await
             .context("failed to send key block")?;
 
@@ -147,7 +161,11 @@ impl Uploader {
         Ok(())
     }
 
-    async fn send_key_block(&mut self, key_block: Arc<KeyBlockData>) -> Result<()> {
+    async fn send_key_block(
+        &mut self,
+        prev_key_block: &KeyBlockData,
+        key_block: Arc<KeyBlockData>,
+    ) -> Result<()> {
         let key_block_proof = self.src.make_key_block_proof_to_sync(&key_block)?;
         let key_block_proof = CellBuilder::build_from(MerkleProof {
             hash: *key_block_proof.hash(0),
@@ -155,11 +173,42 @@ impl Uploader {
             cell: key_block_proof,
         })?;
 
-        let Some(prev_vset) = key_block.prev_vset.as_ref() else {
-            anyhow::bail!("no prev_vset found");
-        };
-        let signatures =
-            prepare_signatures(key_block.signatures.iter().cloned().map(Ok), prev_vset)?;
+        // NOTE: Vset of the previous key block is the one used by the bridge.
+        if self.trusted_key_blocks.needs_seed(prev_key_block) {
+            let epoch_data = make_epoch_data(&prev_key_block.current_vset)
+                .context("failed to build epoch data")?;
+            let committed = self
+                .dst
+                .get_library_cell(epoch_data.repr_hash())
+                .await
+                .context("failed to get the committed epoch data")?;
+
+            self.trusted_key_blocks
+                .seed(prev_key_block, &epoch_data, committed.as_ref())?;
+            tracing::info!(
+                seqno = %prev_key_block.block_id.seqno,
+                vset_utime_since = prev_key_block.current_vset.utime_since,
+                "using the vset committed to the bridge as trusted"
+            );
+        }
+        let prev_vset = &self.trusted_key_blocks.accept(prev_key_block, &key_block)?;
+
+        let signatures = prepare_signatures_ext(
+            key_block.signatures.iter().cloned().map(Ok),
+            prev_vset,
+            self.config.signatures_mode,
+        )?;
+
+        // NOTE: Epoch data contains only main validators.
+        let outside_main_set = find_signatures_outside_main_set(&signatures, prev_vset.main.get())?;
+        if !outside_main_set.is_empty() {
+            tracing::warn!(
+                seqno = %key_block.block_id.seqno,
+                main_validator_count = prev_vset.main.get(),
+                indices = ?outside_main_set,
+                "key block signatures reference validators outside the main set"
+            );
+        }
 
         // Deploy library with the next epoch data.
         let epoch_data =
@@ -243,7 +292,7 @@ impl Uploader {
     async fn find_next_key_block(
         &mut self,
         current_vset_utime_since: u32,
-    ) -> Result<Option<Arc<KeyBlockData>>> {
+    ) -> Result<Option<(Arc<KeyBlockData>, Arc<KeyBlockData>)>> {
         // TODO: Add retries.
         let mut latest_seqno = self.src.get_latest_key_block_seqno().await?;
 
@@ -267,8 +316,8 @@ impl Uploader {
                     );
                     return Ok(None);
                 }
-                // Stop on the same vset.
-                std::cmp::Ordering::Equal => break Ok(result),
+                // Stop on the same vset (the previous key block is trusted).
+                std::cmp::Ordering::Equal => break Ok(result.map(|next| (key_block, next))),
             }
         }
     }
@@ -349,3 +398,172 @@ impl Uploader {
         }
     }
 }
+
+/// Verifier of key blocks from the `src` network.
+///
+/// Starts from the vset committed to the bridge on the `dst` network
+/// (its epoch data library must be deployed there) and follows key blocks.
+#[derive(Default)]
+struct TrustedKeyBlocks {
+    verifier: Option<KeyBlockChainVerifier<AnyModels>>,
+}
+
+impl TrustedKeyBlocks {
+    /// Returns whether there is no trusted vset of the `prev_key_block`.
+    fn needs_seed(&self, prev_key_block: &KeyBlockData) -> bool {
+        !matches!(
+            &self.verifier,
+            Some(verifier)
+                if verifier.current_vset().utime_since == prev_key_block.current_vset.utime_since
+        )
+    }
+
+    /// Starts following key blocks from the vset of the `prev_key_block`.
+    ///
+    /// Fails if the `committed` epoch data differs from the `epoch_data`
+    /// built for this vset.
+    fn seed(
+        &mut self,
+        prev_key_block: &KeyBlockData,
+        epoch_data: &Cell,
+        committed: Option<&Cell>,
+    ) -> Result<()> {
+        anyhow::ensure!(
+            committed.is_some_and(|lib| lib.repr_hash() == epoch_data.repr_hash()),
+            "vset of the key block {} is not committed to the bridge",
+            prev_key_block.block_id
+        );
+
+        // NOTE: Next key block is signed with the signature id
+        // from the config of the previous key block.
+        self.verifier = Some(KeyBlockChainVerifier::new(
+            prev_key_block.current_vset.clone(),
+            prev_key_block.signature_id,
+        ));
+        Ok(())
+    }
+
+    /// Verifies the key block which follows the `prev_key_block`.
+    ///
+    /// Returns the trusted vset which signed the key block.
+    fn accept(
+        &mut self,
+        prev_key_block: &KeyBlockData,
+        key_block: &KeyBlockData,
+    ) -> Result<ValidatorSet> {
+        let verifier = self.verifier.as_mut().context("no trusted vset")?;
+        anyhow::ensure!(
+            verifier.current_vset() == &prev_key_block.current_vset,
+            "untrusted vset of the key block {}",
+            prev_key_block.block_id
+        );
+
+        // NOTE: Full block ids are compared to detect forks.
+        if let Some(last) = verifier.last_key_block_id() {
+            anyhow::ensure!(
+                last == &prev_key_block.block_id,
+                "key block fork detected: trusted={last}, got={}",
+                prev_key_block.block_id
+            );
+        }
+
+        let vset = verifier.current_vset().clone();
+        verifier
+            .accept(
+                &key_block.block_id,
+                &key_block.root,
+                key_block.signatures.iter().cloned().map(Ok),
+            )
+            .context("invalid key block")?;
+        verifier.set_signature_id(key_block.signature_id);
+        Ok(vset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proof_api_util::block::fixtures::{BlockBuilder, Network, Validators};
+    use proof_api_util::block::{TychoModels, make_key_block_proof};
+    use tycho_types::models::ShardIdent;
+
+    use super::*;
+
+    fn make_key_block(
+        validators: &Validators,
+        seqno: u32,
+        prev_key_block_seqno: u32,
+        gen_utime: u32,
+        vset: &ValidatorSet,
+    ) -> Result<KeyBlockData> {
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, seqno)
+            .with_gen_utime(gen_utime)
+            .with_prev_key_block_seqno(prev_key_block_seqno)
+            .with_key_block_config(vset.clone(), None)
+            .build()?;
+        let root = make_key_block_proof::<TychoModels>(block.root, false)?;
+
+        Ok(KeyBlockData {
+            block_id: block.id,
+            root,
+            prev_key_block_seqno,
+            current_vset: vset.clone(),
+            prev_vset: None,
+            signature_id: None,
+            signatures: validators.sign_all(&block.id, None),
+        })
+    }
+
+    #[test]
+    fn trusted_key_blocks() -> Result<()> {
+        let validators1 = Validators::new(1, 4);
+        let vset1 = validators1.make_vset(1000, 4);
+        let validators2 = Validators::new(2, 4);
+        let vset2 = validators2.make_vset(2000, 4);
+        let validators3 = Validators::new(3, 4);
+        let vset3 = validators3.make_vset(3000, 4);
+        let vset4 = Validators::new(4, 4).make_vset(4000, 4);
+
+        let key_block10 = make_key_block(&validators1, 10, 0, 100, &vset2)?;
+        let mut key_block20 = make_key_block(&validators2, 20, 10, 200, &vset3)?;
+        key_block20.signature_id = Some(42);
+
+        let mut trusted = TrustedKeyBlocks::default();
+        assert!(trusted.needs_seed(&key_block10));
+
+        // Vset must be committed to the bridge.
+        let epoch_data = make_epoch_data(&vset2)?;
+        let other_epoch_data = make_epoch_data(&vset1)?;
+        for committed in [None, Some(&other_epoch_data)] {
+            assert!(trusted.seed(&key_block10, &epoch_data, committed).is_err());
+        }
+        trusted.seed(&key_block10, &epoch_data, Some(&epoch_data))?;
+        assert!(!trusted.needs_seed(&key_block10));
+
+        // Vset of the previous key block must be the trusted one.
+        let mut forged = make_key_block(&validators1, 10, 0, 100, &vset2)?;
+        forged.current_vset = vset1.clone();
+        assert!(trusted.accept(&forged, &key_block20).is_err());
+
+        // Key block must be signed by the trusted vset.
+        let mut unsigned = make_key_block(&validators1, 20, 10, 200, &vset3)?;
+        unsigned.signatures = validators3.sign_all(&unsigned.block_id, None);
+        assert!(trusted.accept(&key_block10, &unsigned).is_err());
+
+        let signed_by = trusted.accept(&key_block10, &key_block20)?;
+        assert_eq!(signed_by, vset2);
+        assert!(!trusted.needs_seed(&key_block20));
+
+        // Key block with the same seqno but different hash is a fork.
+        let fork20 = make_key_block(&validators2, 20, 10, 201, &vset3)?;
+        assert_ne!(fork20.block_id, key_block20.block_id);
+        let mut key_block30 = make_key_block(&validators3, 30, 20, 300, &vset4)?;
+        assert!(trusted.accept(&fork20, &key_block30).is_err());
+
+        // Signature id is taken from the config of the previous key block.
+        assert!(trusted.accept(&key_block20, &key_block30).is_err());
+        key_block30.signatures = validators3.sign_all(&key_block30.block_id, Some(42));
+        assert_eq!(trusted.accept(&key_block20, &key_block30)?, vset3);
+
+        Ok(())
+    }
+}
@@ -92,7 +92,7 @@ async fn main() -> Result<()> {
 
         let signatures = key_block_proof.signatures.signatures;
 
-        check_signatures(&id, signatures.into_iter().map(Ok), &v_set)?;
+        check_signatures(&id, signatures.into_iter().map(Ok), &v_set, None)?;
     }
 
     // Get blockchain config
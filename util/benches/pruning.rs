@@ -0,0 +1,66 @@
+//! Compares the block pruning on the global rayon pool with a single thread.
+//!
+//! Run with `cargo bench -p proof-api-util --features test-utils --bench pruning`.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use proof_api_util::block::fixtures::{BlockBuilder, Network};
+use proof_api_util::block::{TychoModels, make_pruned_block};
+use tycho_types::models::ShardIdent;
+use tycho_types::prelude::*;
+
+const TXS_PER_ACCOUNT: u64 = 8;
+
+/// Builds a shard block with the specified number of accounts.
+fn make_large_block(accounts: u32) -> Cell {
+    let mut block = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10);
+    let lt = block.start_lt();
+    for i in 0..accounts {
+        let mut account = HashBytes::ZERO;
+        account.0[..4].copy_from_slice(&i.to_be_bytes());
+        account.0[4..8].copy_from_slice(&i.wrapping_mul(0x9e3779b9).to_be_bytes());
+
+        for j in 0..TXS_PER_ACCOUNT {
+            block = block.with_transaction(&account, lt + i as u64 * TXS_PER_ACCOUNT + j + 1);
+        }
+    }
+    block.build().unwrap().root
+}
+
+fn pruning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("make_pruned_block");
+    group.sample_size(20);
+
+    let single_thread = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    let prune = |block_root: &Cell| {
+        make_pruned_block::<TychoModels, _, _>(block_root.clone(), || false, |_, _, _| Ok(()))
+            .unwrap()
+    };
+
+    for accounts in [100, 1000, 10000] {
+        let block_root = make_large_block(accounts);
+
+        group.bench_with_input(
+            BenchmarkId::new("single_thread", accounts),
+            &block_root,
+            |b, block_root| {
+                b.iter(|| single_thread.install(|| prune(block_root)));
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("parallel", accounts),
+            &block_root,
+            |b, block_root| {
+                b.iter(|| prune(block_root));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, pruning);
+criterion_main!(benches);
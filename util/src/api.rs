@@ -18,6 +18,11 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
 use tower_service::Service;
+use tycho_types::boc::Boc;
+use tycho_types::cell::Cell;
+use tycho_types::error::Error;
+
+use crate::block::{ProofChainStats, TxContent, analyze_proof_chain};
 
 pub struct Api {
     serve_fn: Box<dyn FnOnce() -> BoxFuture<'static, std::io::Result<()>> + Send>,
@@ -149,6 +154,69 @@ fn get_version_docs(op: TransformOperation<'_>) -> TransformOperation<'_> {
         .response::<200, axum::Json<ApiInfoResponse>>()
 }
 
+/// Proof chain request params.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofChainQuery {
+    /// Whether to include proof chain size info into the response.
+    #[serde(default)]
+    pub stats: bool,
+}
+
+/// Transaction proof chain request params.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TxProofChainQuery {
+    /// Whether to include proof chain size info into the response.
+    #[serde(default)]
+    pub stats: bool,
+    /// Whether to include the full transaction cell (with its description
+    /// and message headers) instead of only its hash.
+    #[serde(default)]
+    pub full_tx: bool,
+    /// Whether to include the full inbound message (implies `fullTx`).
+    #[serde(default)]
+    pub in_msg_body: bool,
+    /// Whether to include all full outbound messages (implies `fullTx`).
+    #[serde(default)]
+    pub out_msg_bodies: bool,
+}
+
+impl TxProofChainQuery {
+    pub fn tx_content(&self) -> TxContent {
+        TxContent {
+            full_tx: self.full_tx,
+            in_msg_body: self.in_msg_body,
+            out_msg_bodies: self.out_msg_bodies,
+        }
+    }
+}
+
+/// Block proof chain for an existing transaction.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofChainResponse {
+    /// Base64 encoded BOC with the proof chain.
+    pub proof_chain: String,
+    /// Proof chain size info.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ProofChainStats>,
+}
+
+impl ProofChainResponse {
+    /// Encodes the proof chain and analyzes it if `stats` is set.
+    pub fn new(proof_chain: Cell, stats: bool) -> Result<Self, Error> {
+        let stats = stats
+            .then(|| analyze_proof_chain(proof_chain.as_ref()))
+            .transpose()?;
+
+        Ok(Self {
+            proof_chain: Boc::encode_base64(proof_chain),
+            stats,
+        })
+    }
+}
+
 pub const JSON_HEADERS_DONT_CACHE: [(HeaderName, HeaderValue); 2] = [
     make_json_content_type(),
     (
@@ -0,0 +1,222 @@
+use tycho_types::error::Error;
+use tycho_types::models::{BlockSignature, ShardIdent, ValidatorBaseInfo};
+use tycho_types::prelude::*;
+
+use crate::block::ton::{TonBlock, TonBlockExtra, TonBlockInfo, TonBlockSignatures};
+use crate::block::tycho::{TychoBlock, TychoBlockExtra, TychoBlockInfo, TychoBlockSignatures};
+use crate::block::{
+    AccountBlocksShort, BlockchainBlock, BlockchainBlockExtra, BlockchainBlockInfo,
+    BlockchainBlockSignatures, BlockchainModels, McExtra, OutMsgDescrShort, ShardState,
+};
+
+/// Models which are selected at runtime by cell tags.
+pub struct AnyModels;
+
+impl BlockchainModels for AnyModels {
+    type Block = AnyBlock;
+    type BlockSignatures = AnyBlockSignatures;
+    type ShardState = ShardState;
+}
+
+macro_rules! dispatch {
+    ($self:expr, $x:ident => $expr:expr) => {
+        match $self {
+            Self::Ton($x) => $expr,
+            Self::Tycho($x) => $expr,
+        }
+    };
+}
+
+pub enum AnyBlock {
+    Ton(TonBlock),
+    Tycho(TychoBlock),
+}
+
+impl AnyBlock {
+    const TON_TAG: u32 = 0x11ef55aa;
+    const TYCHO_TAG: u32 = 0x11ef55bb;
+}
+
+impl<'a> Load<'a> for AnyBlock {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.get_u32(0)? {
+            Self::TON_TAG => TonBlock::load_from(slice).map(Self::Ton),
+            Self::TYCHO_TAG => TychoBlock::load_from(slice).map(Self::Tycho),
+            _ => Err(Error::InvalidTag),
+        }
+    }
+}
+
+impl BlockchainBlock for AnyBlock {
+    type Info = AnyBlockInfo;
+    type Extra = AnyBlockExtra;
+
+    fn global_id(&self) -> i32 {
+        dispatch!(self, block => block.global_id())
+    }
+
+    fn load_info(&self) -> Result<Self::Info, Error> {
+        match self {
+            Self::Ton(block) => block.load_info().map(AnyBlockInfo::Ton),
+            Self::Tycho(block) => block.load_info().map(AnyBlockInfo::Tycho),
+        }
+    }
+
+    fn load_info_raw(&self) -> Result<Cell, Error> {
+        dispatch!(self, block => block.load_info_raw())
+    }
+
+    fn load_state_update_raw(&self) -> Result<Cell, Error> {
+        dispatch!(self, block => block.load_state_update_raw())
+    }
+
+    fn load_extra(&self) -> Result<Self::Extra, Error> {
+        match self {
+            Self::Ton(block) => block.load_extra().map(AnyBlockExtra::Ton),
+            Self::Tycho(block) => block.load_extra().map(AnyBlockExtra::Tycho),
+        }
+    }
+}
+
+pub enum AnyBlockInfo {
+    Ton(TonBlockInfo),
+    Tycho(TychoBlockInfo),
+}
+
+impl AnyBlockInfo {
+    const TON_TAG: u32 = 0x9bc7a987;
+    const TYCHO_TAG: u32 = 0x9bc7a988;
+}
+
+impl<'a> Load<'a> for AnyBlockInfo {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.get_u32(0)? {
+            Self::TON_TAG => TonBlockInfo::load_from(slice).map(Self::Ton),
+            Self::TYCHO_TAG => TychoBlockInfo::load_from(slice).map(Self::Tycho),
+            _ => Err(Error::InvalidTag),
+        }
+    }
+}
+
+impl BlockchainBlockInfo for AnyBlockInfo {
+    fn is_key_block(&self) -> bool {
+        dispatch!(self, info => info.is_key_block())
+    }
+
+    fn seqno(&self) -> u32 {
+        dispatch!(self, info => info.seqno())
+    }
+
+    fn gen_utime(&self) -> u32 {
+        dispatch!(self, info => info.gen_utime())
+    }
+
+    fn shard(&self) -> &ShardIdent {
+        dispatch!(self, info => info.shard())
+    }
+
+    fn after_merge(&self) -> bool {
+        dispatch!(self, info => info.after_merge())
+    }
+
+    fn after_split(&self) -> bool {
+        dispatch!(self, info => info.after_split())
+    }
+
+    fn end_lt(&self) -> u64 {
+        dispatch!(self, info => info.end_lt())
+    }
+
+    fn prev_key_block_seqno(&self) -> u32 {
+        dispatch!(self, info => info.prev_key_block_seqno())
+    }
+
+    fn prev_ref(&self) -> &Cell {
+        dispatch!(self, info => info.prev_ref())
+    }
+
+    fn prev_vert_ref(&self) -> Option<&Cell> {
+        dispatch!(self, info => info.prev_vert_ref())
+    }
+}
+
+pub enum AnyBlockExtra {
+    Ton(TonBlockExtra),
+    Tycho(TychoBlockExtra),
+}
+
+impl AnyBlockExtra {
+    const TON_TAG: u32 = 0x4a33f6fd;
+    const TYCHO_TAG: u32 = 0x4a33f6fc;
+}
+
+impl<'a> Load<'a> for AnyBlockExtra {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.get_u32(0)? {
+            Self::TON_TAG => TonBlockExtra::load_from(slice).map(Self::Ton),
+            Self::TYCHO_TAG => TychoBlockExtra::load_from(slice).map(Self::Tycho),
+            _ => Err(Error::InvalidTag),
+        }
+    }
+}
+
+impl BlockchainBlockExtra for AnyBlockExtra {
+    type McExtra = McExtra;
+
+    fn load_account_blocks(&self) -> Result<AccountBlocksShort, Error> {
+        dispatch!(self, extra => extra.load_account_blocks())
+    }
+
+    fn load_out_msg_description(&self) -> Result<OutMsgDescrShort, Error> {
+        dispatch!(self, extra => extra.load_out_msg_description())
+    }
+
+    fn has_custom(&self) -> bool {
+        dispatch!(self, extra => extra.has_custom())
+    }
+
+    fn load_custom(&self) -> Result<Option<Self::McExtra>, Error> {
+        let Some(custom) = dispatch!(self, extra => extra.custom.as_ref()) else {
+            return Ok(None);
+        };
+        custom.parse::<Self::McExtra>().map(Some)
+    }
+}
+
+pub enum AnyBlockSignatures {
+    Ton(TonBlockSignatures),
+    Tycho(TychoBlockSignatures),
+}
+
+impl AnyBlockSignatures {
+    const TON_TAG: u8 = 0x11;
+    const TYCHO_TAG: u8 = 0x12;
+}
+
+impl<'a> Load<'a> for AnyBlockSignatures {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.get_u8(0)? {
+            Self::TON_TAG => TonBlockSignatures::load_from(slice).map(Self::Ton),
+            Self::TYCHO_TAG => TychoBlockSignatures::load_from(slice).map(Self::Tycho),
+            _ => Err(Error::InvalidTag),
+        }
+    }
+}
+
+impl BlockchainBlockSignatures for AnyBlockSignatures {
+    fn validator_info(&self) -> ValidatorBaseInfo {
+        dispatch!(self, signatures => signatures.validator_info())
+    }
+
+    fn signature_count(&self) -> u32 {
+        dispatch!(self, signatures => signatures.signature_count())
+    }
+
+    fn total_weight(&self) -> u64 {
+        dispatch!(self, signatures => signatures.total_weight())
+    }
+
+    fn signatures(&self) -> Dict<u16, BlockSignature> {
+        dispatch!(self, signatures => signatures.signatures())
+    }
+}
@@ -0,0 +1,771 @@
+//! Deterministic synthetic blocks for offline tests.
+//!
+//! Blocks are built with the same layouts as the real ones but contain
+//! only the parts which are used by the short models.
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU16;
+
+use tycho_crypto::ed25519;
+use tycho_types::cell::Lazy;
+use tycho_types::error::Error;
+use tycho_types::merkle::MerkleUpdate;
+use tycho_types::models::{
+    AccountStatus, BlockId, BlockRef, BlockSignature, ComputePhase, ComputePhaseSkipReason,
+    CurrencyCollection, DepthBalanceInfo, GlobalCapability, HashUpdate, IntMsgInfo, Message,
+    MsgInfo, OptionalAccount, OrdinaryTxInfo, ShardAccount, ShardAccounts, ShardIdent, Signature,
+    SkippedComputePhase, StdAddr, Transaction, TxInfo, ValidatorDescription, ValidatorSet,
+};
+use tycho_types::num::Uint15;
+use tycho_types::prelude::*;
+
+use crate::block::build_data_for_sign;
+
+/// Network which defines block layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Ton,
+    Tycho,
+}
+
+impl Network {
+    fn block_tag(self) -> u32 {
+        match self {
+            Self::Ton => 0x11ef55aa,
+            Self::Tycho => 0x11ef55bb,
+        }
+    }
+
+    fn block_info_tag(self) -> u32 {
+        match self {
+            Self::Ton => 0x9bc7a987,
+            Self::Tycho => 0x9bc7a988,
+        }
+    }
+
+    fn block_extra_tag(self) -> u32 {
+        match self {
+            Self::Ton => 0x4a33f6fd,
+            Self::Tycho => 0x4a33f6fc,
+        }
+    }
+
+    fn shard_state_tag(self) -> u32 {
+        match self {
+            Self::Ton => 0x9023afe2,
+            Self::Tycho => 0x9023aeee,
+        }
+    }
+}
+
+/// Validators with deterministic ed25519 keys.
+pub struct Validators {
+    keys: Vec<ed25519::KeyPair>,
+}
+
+impl Validators {
+    /// Generates keys for `count` validators from the specified seed.
+    pub fn new(seed: u8, count: u16) -> Self {
+        let keys = (0..count)
+            .map(|i| {
+                let mut secret = [seed; 32];
+                secret[..2].copy_from_slice(&i.to_be_bytes());
+                ed25519::KeyPair::from(&ed25519::SecretKey::from_bytes(secret))
+            })
+            .collect();
+
+        Self { keys }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the weight of the validator with the specified index.
+    pub fn weight(&self, index: usize) -> u64 {
+        (index as u64 + 1) * 10
+    }
+
+    /// Returns the short id of the validator with the specified index.
+    pub fn node_id_short(&self, index: usize) -> HashBytes {
+        let public_key = self.keys[index].public_key.to_bytes();
+        HashBytes(tl_proto::hash(tycho_crypto::tl::PublicKey::Ed25519 {
+            key: &public_key,
+        }))
+    }
+
+    /// Builds a validator set with all validators.
+    pub fn make_vset(&self, utime_since: u32, main: u16) -> ValidatorSet {
+        let mut total_weight = 0;
+        let mut list = Vec::with_capacity(self.keys.len());
+        for (i, key) in self.keys.iter().enumerate() {
+            let weight = self.weight(i);
+            list.push(ValidatorDescription {
+                public_key: HashBytes(key.public_key.to_bytes()),
+                weight,
+                adnl_addr: None,
+                mc_seqno_since: 0,
+                prev_total_weight: total_weight,
+            });
+            total_weight += weight;
+        }
+
+        ValidatorSet {
+            utime_since,
+            utime_until: utime_since + 86400,
+            main: NonZeroU16::new(main).expect("main validator count must not be zero"),
+            total_weight,
+            list,
+        }
+    }
+
+    /// Signs the block by validators with the specified indices.
+    pub fn sign<I>(
+        &self,
+        block_id: &BlockId,
+        signature_id: Option<i32>,
+        indices: I,
+    ) -> Vec<BlockSignature>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let data = build_data_for_sign(block_id, signature_id);
+        indices
+            .into_iter()
+            .map(|i| BlockSignature {
+                node_id_short: self.node_id_short(i),
+                signature: Signature(self.keys[i].sign_raw(&data)),
+            })
+            .collect()
+    }
+
+    /// Signs the block by all validators.
+    pub fn sign_all(&self, block_id: &BlockId, signature_id: Option<i32>) -> Vec<BlockSignature> {
+        self.sign(block_id, signature_id, 0..self.keys.len())
+    }
+}
+
+/// Builds a signatures dict as it is stored in the block proof.
+pub fn make_signatures_dict(
+    signatures: &[BlockSignature],
+) -> Result<Dict<u16, BlockSignature>, Error> {
+    let mut dict = Dict::new();
+    for (i, signature) in signatures.iter().enumerate() {
+        dict.set(i as u16, signature)?;
+    }
+    Ok(dict)
+}
+
+/// Deterministic transaction cell (only its hash and the root prefix are used by proofs).
+pub fn make_tx(account: &HashBytes, lt: u64) -> Result<Cell, Error> {
+    let mut b = CellBuilder::new();
+    b.store_small_uint(0b0111, 4)?;
+    b.store_u256(account)?;
+    b.store_u64(lt)?;
+    b.build()
+}
+
+/// Builds a transaction with a valid layout and the specified messages.
+///
+/// Transaction description is an ordinary transaction with a skipped compute phase.
+pub fn make_full_tx(
+    account: &HashBytes,
+    lt: u64,
+    in_msg: Option<Cell>,
+    out_msgs: &[Cell],
+) -> Result<Cell, Error> {
+    let mut out_msgs_dict = Dict::<Uint15, Cell>::new();
+    for (i, msg) in out_msgs.iter().enumerate() {
+        out_msgs_dict.set(Uint15::new(i as u16), msg.clone())?;
+    }
+
+    let info = TxInfo::Ordinary(OrdinaryTxInfo {
+        credit_first: true,
+        storage_phase: None,
+        credit_phase: None,
+        compute_phase: ComputePhase::Skipped(SkippedComputePhase {
+            reason: ComputePhaseSkipReason::NoState,
+        }),
+        action_phase: None,
+        aborted: true,
+        bounce_phase: None,
+        destroyed: false,
+    });
+
+    CellBuilder::build_from(Transaction {
+        account: *account,
+        lt,
+        prev_trans_hash: HashBytes::ZERO,
+        prev_trans_lt: 0,
+        now: 0,
+        out_msg_count: Uint15::new(out_msgs.len() as u16),
+        orig_status: AccountStatus::NotExists,
+        end_status: AccountStatus::NotExists,
+        in_msg,
+        out_msgs: out_msgs_dict,
+        total_fees: CurrencyCollection::ZERO,
+        state_update: Lazy::new(&HashUpdate {
+            old: HashBytes::ZERO,
+            new: HashBytes::ZERO,
+        })?,
+        info: Lazy::new(&info)?,
+    })
+}
+
+/// Deterministic message cell.
+pub fn make_msg(seed: u64) -> Result<Cell, Error> {
+    CellBuilder::build_from(seed)
+}
+
+/// Deterministic message cell with a body in a separate cell.
+pub fn make_msg_with_body(seed: u64) -> Result<Cell, Error> {
+    let body = CellBuilder::build_from((seed, seed))?;
+
+    let mut b = CellBuilder::new();
+    b.store_u64(seed)?;
+    b.store_reference(body)?;
+    b.build()
+}
+
+/// Internal message with an empty body.
+pub fn make_int_msg(dst: &StdAddr, created_lt: u64) -> Result<Cell, Error> {
+    CellBuilder::build_from(Message {
+        info: MsgInfo::Int(IntMsgInfo {
+            dst: dst.clone().into(),
+            created_lt,
+            ..Default::default()
+        }),
+        init: None,
+        body: Cell::empty_cell_ref().as_slice()?,
+        layout: None,
+    })
+}
+
+/// Builds a shard state with the specified accounts.
+///
+/// Accounts are empty, only the last transaction lt is set.
+pub fn make_shard_state(
+    network: Network,
+    shard: ShardIdent,
+    seqno: u32,
+    accounts: &[(HashBytes, u64)],
+) -> Result<Cell, Error> {
+    let mut shard_accounts = ShardAccounts::new();
+    for (address, last_trans_lt) in accounts {
+        shard_accounts.set(
+            address,
+            DepthBalanceInfo {
+                split_depth: 0,
+                balance: CurrencyCollection::ZERO,
+            },
+            ShardAccount {
+                account: Lazy::new(&OptionalAccount(None))?,
+                last_trans_hash: HashBytes::ZERO,
+                last_trans_lt: *last_trans_lt,
+            },
+        )?;
+    }
+
+    let mut b = CellBuilder::new();
+    b.store_u32(network.shard_state_tag())?;
+    b.store_u32(0)?; // global_id
+    shard.store_into(&mut b, Cell::empty_context())?;
+    b.store_u32(seqno)?;
+    b.store_u32(0)?; // vert_seqno
+    b.store_u32(0)?; // gen_utime
+    b.store_u64(0)?; // gen_lt
+    b.store_u32(0)?; // min_ref_mc_seqno
+    b.store_reference(Cell::empty_cell())?; // out_msg_queue_info
+    b.store_bit_zero()?; // before_split
+    b.store_reference(CellBuilder::build_from(&shard_accounts)?)?;
+    b.store_reference(Cell::empty_cell())?; // overload history, etc.
+    b.store_bit_zero()?; // custom
+    b.build()
+}
+
+/// Synthetic block.
+pub struct BuiltBlock {
+    pub id: BlockId,
+    pub root: Cell,
+}
+
+/// Builder for synthetic blocks.
+pub struct BlockBuilder {
+    network: Network,
+    global_id: i32,
+    shard: ShardIdent,
+    seqno: u32,
+    gen_utime: u32,
+    start_lt: u64,
+    end_lt: u64,
+    prev_key_block_seqno: u32,
+    prev_blocks: Vec<BlockRef>,
+    after_split: bool,
+    transactions: BTreeMap<HashBytes, BTreeMap<u64, Cell>>,
+    out_msgs: BTreeMap<HashBytes, (Cell, Cell)>,
+    shard_blocks: Vec<BlockId>,
+    config: Option<KeyBlockConfig>,
+    state_root: Option<Cell>,
+}
+
+struct KeyBlockConfig {
+    current_vset: ValidatorSet,
+    prev_vset: Option<ValidatorSet>,
+    with_signature_id: bool,
+}
+
+impl BlockBuilder {
+    /// Logical time range of each block.
+    pub const LT_STEP: u64 = 1_000_000;
+
+    pub fn new(network: Network, shard: ShardIdent, seqno: u32) -> Self {
+        let start_lt = seqno as u64 * Self::LT_STEP;
+        Self {
+            network,
+            global_id: 0,
+            shard,
+            seqno,
+            gen_utime: 1_700_000_000 + seqno,
+            start_lt,
+            end_lt: start_lt + Self::LT_STEP - 1,
+            prev_key_block_seqno: 0,
+            prev_blocks: Vec::new(),
+            after_split: false,
+            transactions: BTreeMap::new(),
+            out_msgs: BTreeMap::new(),
+            shard_blocks: Vec::new(),
+            config: None,
+            state_root: None,
+        }
+    }
+
+    /// Returns the logical time of the first transaction in the block.
+    pub fn start_lt(&self) -> u64 {
+        self.start_lt
+    }
+
+    pub fn with_global_id(mut self, global_id: i32) -> Self {
+        self.global_id = global_id;
+        self
+    }
+
+    pub fn with_gen_utime(mut self, gen_utime: u32) -> Self {
+        self.gen_utime = gen_utime;
+        self
+    }
+
+    pub fn with_prev_key_block_seqno(mut self, seqno: u32) -> Self {
+        self.prev_key_block_seqno = seqno;
+        self
+    }
+
+    /// Adds a reference to the previous block (two references mean a merge).
+    pub fn with_prev_block(mut self, block_id: &BlockId) -> Self {
+        self.prev_blocks.push(BlockRef {
+            end_lt: block_id.seqno as u64 * Self::LT_STEP + Self::LT_STEP - 1,
+            seqno: block_id.seqno,
+            root_hash: block_id.root_hash,
+            file_hash: block_id.file_hash,
+        });
+        self
+    }
+
+    /// Marks the block as the first block after the shard split.
+    pub fn after_split(mut self) -> Self {
+        self.after_split = true;
+        self
+    }
+
+    /// Adds a transaction built by [`make_tx`].
+    ///
+    /// NOTE: Logical time must be in the block lt range (see [`Self::start_lt`]).
+    pub fn with_transaction(mut self, account: &HashBytes, lt: u64) -> Self {
+        let tx = make_tx(account, lt).unwrap();
+        self.transactions
+            .entry(*account)
+            .or_default()
+            .insert(lt, tx);
+        self
+    }
+
+    /// Adds a prebuilt transaction cell (e.g. from [`make_full_tx`]).
+    ///
+    /// NOTE: Logical time must be in the block lt range (see [`Self::start_lt`]).
+    pub fn with_raw_transaction(mut self, account: &HashBytes, lt: u64, tx: Cell) -> Self {
+        self.transactions
+            .entry(*account)
+            .or_default()
+            .insert(lt, tx);
+        self
+    }
+
+    /// Adds an external outbound message of the transaction.
+    ///
+    /// The transaction must be added with [`with_transaction`] first.
+    ///
+    /// [`with_transaction`]: Self::with_transaction
+    pub fn with_out_msg(mut self, account: &HashBytes, lt: u64, msg: Cell) -> Self {
+        let tx = self.transactions[account][&lt].clone();
+        self.out_msgs.insert(*msg.repr_hash(), (msg, tx));
+        self
+    }
+
+    /// Adds a shard description to the masterchain block.
+    pub fn with_shard_block(mut self, block_id: &BlockId) -> Self {
+        self.shard_blocks.push(*block_id);
+        self
+    }
+
+    /// Makes a key block with the config params 32 and 34.
+    pub fn with_key_block_config(
+        mut self,
+        current_vset: ValidatorSet,
+        prev_vset: Option<ValidatorSet>,
+    ) -> Self {
+        self.config = Some(KeyBlockConfig {
+            current_vset,
+            prev_vset,
+            with_signature_id: false,
+        });
+        self
+    }
+
+    /// Enables `CapSignatureWithId` in the key block config.
+    pub fn with_signature_id_capability(mut self) -> Self {
+        if let Some(config) = &mut self.config {
+            config.with_signature_id = true;
+        }
+        self
+    }
+
+    /// Sets the shard state after this block.
+    pub fn with_state(mut self, state_root: Cell) -> Self {
+        self.state_root = Some(state_root);
+        self
+    }
+
+    pub fn build(self) -> Result<BuiltBlock, Error> {
+        let network = self.network;
+        let is_masterchain = self.shard.is_masterchain();
+
+        let info = self.build_info()?;
+
+        let state_update = {
+            let state_root = match &self.state_root {
+                Some(state_root) => state_root.clone(),
+                None => Cell::empty_cell(),
+            };
+            let update = CellBuilder::build_from(MerkleUpdate {
+                old_hash: *state_root.hash(0),
+                new_hash: *state_root.hash(0),
+                old_depth: state_root.depth(0),
+                new_depth: state_root.depth(0),
+                old: state_root.clone(),
+                new: state_root,
+            })?;
+
+            match network {
+                Network::Ton => update,
+                Network::Tycho => {
+                    // NOTE: Tycho blocks store the state update with the queue diff info.
+                    let mut b = CellBuilder::new();
+                    b.store_reference(update)?;
+                    b.store_u256(&HashBytes::ZERO)?; // out_msg_queue_updates.diff_hash
+                    b.store_u32(0)?; // out_msg_queue_updates.tail_len
+                    b.build()?
+                }
+            }
+        };
+
+        let custom = if is_masterchain {
+            Some(self.build_mc_extra()?)
+        } else {
+            None
+        };
+
+        let extra = {
+            let mut b = CellBuilder::new();
+            b.store_u32(network.block_extra_tag())?;
+            b.store_reference(make_empty_aug_dict(2)?)?; // in_msg_descr
+            b.store_reference(self.build_out_msg_descr()?)?;
+            b.store_reference(self.build_account_blocks()?)?;
+            b.store_u256(&HashBytes::ZERO)?; // rand_seed
+            b.store_u256(&HashBytes::ZERO)?; // created_by
+            match custom {
+                Some(custom) => {
+                    b.store_bit_one()?;
+                    b.store_reference(custom)?;
+                }
+                None => b.store_bit_zero()?,
+            }
+            b.build()?
+        };
+
+        let mut b = CellBuilder::new();
+        b.store_u32(network.block_tag())?;
+        b.store_u32(self.global_id as u32)?;
+        b.store_reference(info)?;
+        b.store_reference(Cell::empty_cell())?; // value_flow
+        b.store_reference(state_update)?;
+        b.store_reference(extra)?;
+        let root = b.build()?;
+
+        Ok(BuiltBlock {
+            id: BlockId {
+                shard: self.shard,
+                seqno: self.seqno,
+                root_hash: *root.repr_hash(),
+                // NOTE: File hash is not checked by proofs.
+                file_hash: *root.repr_hash(),
+            },
+            root,
+        })
+    }
+
+    fn build_info(&self) -> Result<Cell, Error> {
+        let is_masterchain = self.shard.is_masterchain();
+        let after_merge = self.prev_blocks.len() == 2;
+
+        let mut packed_flags = 0u8;
+        if !is_masterchain {
+            packed_flags |= 0b10000000;
+        }
+        if after_merge {
+            packed_flags |= 0b01000000;
+        }
+        if self.after_split {
+            packed_flags |= 0b00010000;
+        }
+        if self.config.is_some() {
+            packed_flags |= 0b00000010;
+        }
+
+        let prev_ref = match self.prev_blocks.as_slice() {
+            [] => CellBuilder::build_from(BlockRef {
+                end_lt: 0,
+                seqno: self.seqno.saturating_sub(1),
+                root_hash: HashBytes::ZERO,
+                file_hash: HashBytes::ZERO,
+            })?,
+            [prev] => CellBuilder::build_from(prev)?,
+            [left, right] => {
+                let mut b = CellBuilder::new();
+                b.store_reference(CellBuilder::build_from(left)?)?;
+                b.store_reference(CellBuilder::build_from(right)?)?;
+                b.build()?
+            }
+            _ => return Err(Error::InvalidData),
+        };
+
+        let mut b = CellBuilder::new();
+        b.store_u32(self.network.block_info_tag())?;
+        b.store_u32(0)?; // version
+        b.store_u8(packed_flags)?;
+        b.store_u8(0)?; // flags
+        b.store_u32(self.seqno)?;
+        b.store_u32(0)?; // vert_seqno
+        self.shard.store_into(&mut b, Cell::empty_context())?;
+        b.store_u32(self.gen_utime)?;
+        if self.network == Network::Tycho {
+            b.store_u16(0)?; // gen_utime_ms
+        }
+        b.store_u64(self.start_lt)?;
+        b.store_u64(self.end_lt)?;
+        b.store_u32(0)?; // gen_validator_list_hash_short
+        b.store_u32(0)?; // gen_catchain_seqno
+        b.store_u32(0)?; // min_ref_mc_seqno
+        b.store_u32(self.prev_key_block_seqno)?;
+        if !is_masterchain {
+            // master_ref
+            b.store_reference(CellBuilder::build_from(BlockRef {
+                end_lt: 0,
+                seqno: 0,
+                root_hash: HashBytes::ZERO,
+                file_hash: HashBytes::ZERO,
+            })?)?;
+        }
+        b.store_reference(prev_ref)?;
+        b.build()
+    }
+
+    fn build_account_blocks(&self) -> Result<Cell, Error> {
+        let mut account_blocks = AugDict::<HashBytes, CurrencyCollection, RawAccountBlock>::new();
+        for (account, transactions) in &self.transactions {
+            let mut dict = AugDict::<u64, CurrencyCollection, Cell>::new();
+            for (lt, tx) in transactions {
+                dict.set(lt, CurrencyCollection::ZERO, tx)?;
+            }
+            let (dict, _) = dict.into_parts();
+
+            account_blocks.set(account, CurrencyCollection::ZERO, RawAccountBlock {
+                account: *account,
+                transactions: dict.into_root().ok_or(Error::EmptyProof)?,
+            })?;
+        }
+        CellBuilder::build_from(&account_blocks)
+    }
+
+    fn build_out_msg_descr(&self) -> Result<Cell, Error> {
+        let mut out_msgs = AugDict::<HashBytes, CurrencyCollection, RawOutMsgExt>::new();
+        for (msg_hash, (msg, tx)) in &self.out_msgs {
+            out_msgs.set(msg_hash, CurrencyCollection::ZERO, RawOutMsgExt {
+                msg: msg.clone(),
+                transaction: tx.clone(),
+            })?;
+        }
+        CellBuilder::build_from(&out_msgs)
+    }
+
+    fn build_mc_extra(&self) -> Result<Cell, Error> {
+        // Build shard hashes.
+        let mut workchains = BTreeMap::<i32, Vec<&BlockId>>::new();
+        for block_id in &self.shard_blocks {
+            workchains
+                .entry(block_id.shard.workchain())
+                .or_default()
+                .push(block_id);
+        }
+
+        let mut shard_hashes = Dict::<i32, Cell>::new();
+        for (workchain, blocks) in workchains {
+            let full = ShardIdent::new(workchain, 1 << 63).ok_or(Error::InvalidData)?;
+            shard_hashes.set(workchain, make_shard_descr_tree(full, &blocks)?)?;
+        }
+
+        let mut b = CellBuilder::new();
+        b.store_u16(0xcca5)?;
+        b.store_bit(self.config.is_some())?;
+        shard_hashes.store_into(&mut b, Cell::empty_context())?;
+
+        // Empty shard fees.
+        b.store_bit_zero()?;
+        CurrencyCollection::ZERO.store_into(&mut b, Cell::empty_context())?;
+        CurrencyCollection::ZERO.store_into(&mut b, Cell::empty_context())?;
+
+        // `prev_blk_signatures`, `recover_create_msg`, `mint_msg`.
+        let mut misc = CellBuilder::new();
+        misc.store_small_uint(0, 3)?;
+        b.store_reference(misc.build()?)?;
+
+        if let Some(config) = &self.config {
+            b.store_u256(&HashBytes([0x55; 32]))?; // config address
+            b.store_reference(config.build_params()?)?;
+        }
+
+        b.build()
+    }
+}
+
+impl KeyBlockConfig {
+    fn build_params(&self) -> Result<Cell, Error> {
+        let mut capabilities = 0u64;
+        if self.with_signature_id {
+            capabilities |= GlobalCapability::CapSignatureWithId as u64;
+        }
+
+        let mut global_version = CellBuilder::new();
+        global_version.store_u8(0xc4)?;
+        global_version.store_u32(0)?;
+        global_version.store_u64(capabilities)?;
+
+        let mut params = Dict::<u32, Cell>::new();
+        params.set(8, global_version.build()?)?;
+        params.set(34, CellBuilder::build_from(&self.current_vset)?)?;
+        if let Some(prev_vset) = &self.prev_vset {
+            params.set(32, CellBuilder::build_from(prev_vset)?)?;
+        }
+
+        params.into_root().ok_or(Error::EmptyProof)
+    }
+}
+
+/// `acc_trans#5 account_addr:bits256 transactions:(HashmapAug 64 ^Transaction CurrencyCollection)
+/// state_update:^(HASH_UPDATE Account)`
+struct RawAccountBlock {
+    account: HashBytes,
+    transactions: Cell,
+}
+
+impl Store for RawAccountBlock {
+    fn store_into(&self, b: &mut CellBuilder, _: &dyn CellContext) -> Result<(), Error> {
+        let mut state_update = CellBuilder::new();
+        state_update.store_u8(0x72)?;
+        state_update.store_u256(&HashBytes::ZERO)?;
+        state_update.store_u256(&HashBytes::ZERO)?;
+
+        b.store_small_uint(5, 4)?;
+        b.store_u256(&self.account)?;
+        b.store_slice(self.transactions.as_slice()?)?;
+        b.store_reference(state_update.build()?)
+    }
+}
+
+/// `msg_export_ext$000 msg:^(Message Any) transaction:^Transaction`
+struct RawOutMsgExt {
+    msg: Cell,
+    transaction: Cell,
+}
+
+impl Store for RawOutMsgExt {
+    fn store_into(&self, b: &mut CellBuilder, _: &dyn CellContext) -> Result<(), Error> {
+        b.store_small_uint(0b000, 3)?;
+        b.store_reference(self.msg.clone())?;
+        b.store_reference(self.transaction.clone())
+    }
+}
+
+/// Empty `HashmapAugE` with `count` empty currency collections as extra.
+fn make_empty_aug_dict(count: usize) -> Result<Cell, Error> {
+    let mut b = CellBuilder::new();
+    b.store_bit_zero()?;
+    for _ in 0..count {
+        CurrencyCollection::ZERO.store_into(&mut b, Cell::empty_context())?;
+    }
+    b.build()
+}
+
+/// Builds a `BinTree ShardDescr` for the specified shard blocks.
+fn make_shard_descr_tree(shard: ShardIdent, blocks: &[&BlockId]) -> Result<Cell, Error> {
+    let mut b = CellBuilder::new();
+    if let Some(block_id) = blocks.iter().find(|id| id.shard == shard) {
+        // `bt_leaf$0 leaf:ShardDescr`
+        b.store_bit_zero()?;
+        store_shard_descr(&mut b, block_id)?;
+    } else {
+        // `bt_fork$1 left:^(BinTree X) right:^(BinTree X)`
+        let (left, right) = shard.split().ok_or(Error::InvalidData)?;
+        b.store_bit_one()?;
+        b.store_reference(make_shard_descr_tree(left, blocks)?)?;
+        b.store_reference(make_shard_descr_tree(right, blocks)?)?;
+    }
+    b.build()
+}
+
+/// `shard_descr_new#a`
+fn store_shard_descr(b: &mut CellBuilder, block_id: &BlockId) -> Result<(), Error> {
+    let start_lt = block_id.seqno as u64 * BlockBuilder::LT_STEP;
+
+    b.store_small_uint(0xa, 4)?;
+    b.store_u32(block_id.seqno)?;
+    b.store_u32(0)?; // reg_mc_seqno
+    b.store_u64(start_lt)?;
+    b.store_u64(start_lt + BlockBuilder::LT_STEP - 1)?;
+    b.store_u256(&block_id.root_hash)?;
+    b.store_u256(&block_id.file_hash)?;
+    b.store_u8(0)?; // before_split, before_merge, want_split, want_merge, nx_cc_updated, flags
+    b.store_u32(0)?; // next_catchain_seqno
+    b.store_u64(0)?; // next_validator_shard
+    b.store_u32(0)?; // min_ref_mc_seqno
+    b.store_u32(0)?; // gen_utime
+    b.store_bit_zero()?; // split_merge_at
+
+    // `fees_collected` and `funds_created`.
+    let mut fees = CellBuilder::new();
+    CurrencyCollection::ZERO.store_into(&mut fees, Cell::empty_context())?;
+    CurrencyCollection::ZERO.store_into(&mut fees, Cell::empty_context())?;
+    b.store_reference(fees.build()?)
+}
@@ -1,47 +1,102 @@
-use ahash::HashMap;
-use tycho_types::cell::Lazy;
+use ahash::{HashMap, HashSet};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use tycho_types::cell::{CellType, Lazy};
 use tycho_types::error::Error;
-use tycho_types::merkle::MerkleProof;
+use tycho_types::merkle::{MerkleProof, MerkleUpdate};
 use tycho_types::models::{
-    Block, BlockId, BlockIdShort, BlockSignature, BlockchainConfig, CurrencyCollection, ShardIdent,
-    ValidatorBaseInfo, ValidatorSet,
+    Block, BlockId, BlockIdShort, BlockRef, BlockSignature, BlockchainConfig,
+    BlockchainConfigParams, CurrencyCollection, GlobalCapability, ShardAccounts, ShardHashes,
+    ShardIdent, Transaction, ValidatorBaseInfo, ValidatorSet,
 };
 use tycho_types::num::Tokens;
 use tycho_types::prelude::*;
 
+pub use self::any::AnyModels;
 pub use self::ton::TonModels;
 pub use self::tycho::TychoModels;
+pub use self::verify::{
+    KeyBlockChainVerifier, TrustedVset, check_signatures_with_epoch_data, verify_proof_chain,
+    verify_proof_chain_v2, verify_shard_chain,
+};
 
+pub mod any;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod fixtures;
 pub mod ton;
 pub mod tycho;
+pub mod verify;
 
 // === Traits ===
 
 pub trait BlockchainModels {
     type Block: BlockchainBlock;
     type BlockSignatures: BlockchainBlockSignatures;
+    type ShardState: BlockchainShardState;
 }
 
 pub trait BlockchainBlock: for<'a> Load<'a> {
     type Info: BlockchainBlockInfo;
     type Extra: BlockchainBlockExtra;
 
+    fn global_id(&self) -> i32;
+
     fn load_info(&self) -> Result<Self::Info, Error>;
     fn load_info_raw(&self) -> Result<Cell, Error>;
 
+    fn load_state_update_raw(&self) -> Result<Cell, Error>;
+
     fn load_extra(&self) -> Result<Self::Extra, Error>;
 }
 
 pub trait BlockchainBlockInfo: for<'a> Load<'a> {
     fn is_key_block(&self) -> bool;
+    fn seqno(&self) -> u32;
+    fn gen_utime(&self) -> u32;
+    fn shard(&self) -> &ShardIdent;
+    fn after_merge(&self) -> bool;
+    fn after_split(&self) -> bool;
     fn end_lt(&self) -> u64;
+    fn prev_key_block_seqno(&self) -> u32;
     fn prev_ref(&self) -> &Cell;
+    fn prev_vert_ref(&self) -> Option<&Cell>;
+
+    /// Loads the id of the previous block on the path to the specified account.
+    ///
+    /// Handles shard splits (the previous block is in the parent shard)
+    /// and merges (the previous block is in the child shard with the account).
+    fn load_prev_block_id(&self, account: &HashBytes) -> Result<BlockId, Error> {
+        let (shard, prev) = if self.after_merge() {
+            // `prev_blks_info$_ prev1:^ExtBlkRef prev2:^ExtBlkRef`
+            let (left, right) = self.shard().split().ok_or(Error::InvalidData)?;
+            let (shard, index) = if left.contains_account(account) {
+                (left, 0)
+            } else {
+                (right, 1)
+            };
+            let prev = self.prev_ref().as_slice()?.get_reference(index)?;
+            (shard, prev.parse::<BlockRef>()?)
+        } else if self.after_split() {
+            let parent = self.shard().merge().ok_or(Error::InvalidData)?;
+            (parent, self.prev_ref().parse::<BlockRef>()?)
+        } else {
+            (*self.shard(), self.prev_ref().parse::<BlockRef>()?)
+        };
+
+        Ok(BlockId {
+            shard,
+            seqno: prev.seqno,
+            root_hash: prev.root_hash,
+            file_hash: prev.file_hash,
+        })
+    }
 }
 
 pub trait BlockchainBlockExtra: for<'a> Load<'a> {
     type McExtra: BlockchainBlockMcExtra;
 
     fn load_account_blocks(&self) -> Result<AccountBlocksShort, Error>;
+    fn load_out_msg_description(&self) -> Result<OutMsgDescrShort, Error>;
 
     fn has_custom(&self) -> bool;
     fn load_custom(&self) -> Result<Option<Self::McExtra>, Error>;
@@ -49,7 +104,11 @@ pub trait BlockchainBlockExtra: for<'a> Load<'a> {
 
 pub trait BlockchainBlockMcExtra: for<'a> Load<'a> {
     fn load_top_shard_block_ids(&self) -> Result<Vec<BlockIdShort>, Error>;
-    fn find_shard_seqno(&self, shard_ident: ShardIdent) -> Result<u32, Error>;
+    fn find_shard_descr(
+        &self,
+        workchain: i32,
+        address: &HashBytes,
+    ) -> Result<(ShardIdent, ShardDescrShort), Error>;
     fn visit_all_shard_hashes(&self) -> Result<(), Error>;
     fn config(&self) -> Option<&BlockchainConfig>;
 }
@@ -61,6 +120,10 @@ pub trait BlockchainBlockSignatures: for<'a> Load<'a> {
     fn signatures(&self) -> Dict<u16, BlockSignature>;
 }
 
+pub trait BlockchainShardState: for<'a> Load<'a> {
+    fn load_accounts(&self) -> Result<ShardAccounts, Error>;
+}
+
 pub struct BaseBlockProof<S> {
     pub proof_for: BlockId,
     pub root: Cell,
@@ -102,6 +165,227 @@ impl<'a, S> Load<'a> for BaseBlockProof<S> {
     }
 }
 
+/// Proof chain root (without the merkle proof wrapper).
+///
+/// Layout: `mc_file_hash:uint256 vset_utime_since:uint32 mc_block:^Cell signatures:^Cell`
+/// followed by an optional first shard block reference and an optional reference
+/// to the rest of shard blocks. The rest is packed by three blocks per cell
+/// with an optional reference to the next chunk.
+#[derive(Debug, Clone)]
+pub struct ProofChain {
+    pub mc_file_hash: HashBytes,
+    pub vset_utime_since: u32,
+    /// Masterchain block proof.
+    pub mc_block: Cell,
+    /// Signatures dict with validator indices as keys.
+    pub signatures: Cell,
+    /// Shard block proofs, starting from the block which is referenced
+    /// by the masterchain block and ending with the transaction block.
+    pub shard_blocks: Vec<Cell>,
+}
+
+impl ProofChain {
+    const SHARD_BLOCKS_PER_CELL: usize = 3;
+
+    /// Loads the proof chain from the merkle proof cell.
+    pub fn load_from_proof(proof: &DynCell) -> Result<Self, Error> {
+        let proof = proof.parse_exotic::<MerkleProof>()?;
+        if proof.cell.hash(0) != &proof.hash {
+            return Err(Error::InvalidData);
+        }
+        proof.cell.parse::<Self>()
+    }
+
+    /// Iterates over all shard block proofs (from the latest to the oldest one).
+    pub fn shard_blocks(&self) -> std::slice::Iter<'_, Cell> {
+        self.shard_blocks.iter()
+    }
+
+    /// Returns the block which contains the proven item.
+    pub fn target_block(&self) -> &Cell {
+        self.shard_blocks.last().unwrap_or(&self.mc_block)
+    }
+}
+
+impl Store for ProofChain {
+    fn store_into(&self, b: &mut CellBuilder, context: &dyn CellContext) -> Result<(), Error> {
+        b.store_u256(&self.mc_file_hash)?;
+        b.store_u32(self.vset_utime_since)?;
+        b.store_reference(self.mc_block.clone())?;
+        b.store_reference(self.signatures.clone())?;
+
+        let mut iter = self.shard_blocks.iter();
+        if let Some(sc_block) = iter.next() {
+            b.store_reference(sc_block.clone())?;
+
+            let mut iter = iter.rev();
+
+            let remaining = iter.len();
+            let mut child = if !remaining.is_multiple_of(Self::SHARD_BLOCKS_PER_CELL) {
+                let mut b = CellBuilder::new();
+                for cell in iter
+                    .by_ref()
+                    .take(remaining % Self::SHARD_BLOCKS_PER_CELL)
+                    .rev()
+                {
+                    b.store_reference(cell.clone())?;
+                }
+                Some(b.build_ext(context)?)
+            } else {
+                None
+            };
+
+            for _ in 0..(remaining / Self::SHARD_BLOCKS_PER_CELL) {
+                let sc1 = iter.next().unwrap();
+                let sc2 = iter.next().unwrap();
+                let sc3 = iter.next().unwrap();
+
+                let mut b = CellBuilder::new();
+                b.store_reference(sc3.clone())?;
+                b.store_reference(sc2.clone())?;
+                b.store_reference(sc1.clone())?;
+                if let Some(child) = child.take() {
+                    b.store_reference(child)?;
+                }
+                child = Some(b.build_ext(context)?);
+            }
+
+            if let Some(child) = child {
+                b.store_reference(child)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Load<'a> for ProofChain {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        let mc_file_hash = slice.load_u256()?;
+        let vset_utime_since = slice.load_u32()?;
+        let mc_block = slice.load_reference_cloned()?;
+        let signatures = slice.load_reference_cloned()?;
+
+        let mut shard_blocks = Vec::new();
+        if slice.size_refs() > 0 {
+            shard_blocks.push(slice.load_reference_cloned()?);
+        }
+
+        let mut next = if slice.size_refs() > 0 {
+            Some(slice.load_reference_cloned()?)
+        } else {
+            None
+        };
+        while let Some(chunk) = next.take() {
+            let mut cs = chunk.as_slice()?;
+            let has_next = cs.size_refs() as usize > Self::SHARD_BLOCKS_PER_CELL;
+            for _ in 0..cs.size_refs().min(Self::SHARD_BLOCKS_PER_CELL as u8) {
+                shard_blocks.push(cs.load_reference_cloned()?);
+            }
+            if has_next {
+                next = Some(cs.load_reference_cloned()?);
+            }
+        }
+
+        Ok(Self {
+            mc_file_hash,
+            vset_utime_since,
+            mc_block,
+            signatures,
+            shard_blocks,
+        })
+    }
+}
+
+/// Kind of the item proven by the proof chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ProofKind {
+    /// Transaction proof.
+    Tx = 0,
+    /// Outbound message proof.
+    OutMsg = 1,
+    /// Block with the account state update.
+    State = 2,
+}
+
+impl TryFrom<u8> for ProofKind {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Tx,
+            1 => Self::OutMsg,
+            2 => Self::State,
+            _ => return Err(Error::InvalidData),
+        })
+    }
+}
+
+/// Layout of the proof chain root.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProofChainFormat {
+    /// [`ProofChain`] layout.
+    #[default]
+    V1,
+    /// [`ProofChainV2`] layout with the kind of the proven item.
+    V2(ProofKind),
+}
+
+/// Versioned proof chain root (without the merkle proof wrapper).
+///
+/// Layout: `proof_chain_v2#c2a1f0d2 global_id:int32 mc_seqno:uint32 mc_gen_utime:uint32
+/// kind:uint8` followed by the [`ProofChain`] layout.
+#[derive(Debug, Clone)]
+pub struct ProofChainV2 {
+    /// Global id of the network.
+    pub global_id: i32,
+    pub mc_seqno: u32,
+    pub mc_gen_utime: u32,
+    pub kind: ProofKind,
+    pub chain: ProofChain,
+}
+
+impl ProofChainV2 {
+    pub const TAG: u32 = 0xc2a1f0d2;
+
+    /// Loads the proof chain from the merkle proof cell.
+    pub fn load_from_proof(proof: &DynCell) -> Result<Self, Error> {
+        let proof = proof.parse_exotic::<MerkleProof>()?;
+        if proof.cell.hash(0) != &proof.hash {
+            return Err(Error::InvalidData);
+        }
+        proof.cell.parse::<Self>()
+    }
+}
+
+impl Store for ProofChainV2 {
+    fn store_into(&self, b: &mut CellBuilder, context: &dyn CellContext) -> Result<(), Error> {
+        b.store_u32(Self::TAG)?;
+        b.store_u32(self.global_id as u32)?;
+        b.store_u32(self.mc_seqno)?;
+        b.store_u32(self.mc_gen_utime)?;
+        b.store_u8(self.kind as u8)?;
+        self.chain.store_into(b, context)
+    }
+}
+
+impl<'a> Load<'a> for ProofChainV2 {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        if slice.load_u32()? != Self::TAG {
+            return Err(Error::InvalidTag);
+        }
+
+        Ok(Self {
+            global_id: slice.load_u32()? as i32,
+            mc_seqno: slice.load_u32()?,
+            mc_gen_utime: slice.load_u32()?,
+            kind: ProofKind::try_from(slice.load_u8()?)?,
+            chain: ProofChain::load_from(slice)?,
+        })
+    }
+}
+
 pub struct AccountBlockShort {
     pub account: HashBytes,
     pub transactions: AugDict<u64, CurrencyCollection, Cell>,
@@ -124,6 +408,200 @@ impl<'a> Load<'a> for AccountBlockShort {
 
 pub type AccountBlocksShort = AugDict<HashBytes, CurrencyCollection, AccountBlockShort>;
 
+/// Outbound message description which was emitted by some transaction.
+pub struct OutMsgShort {
+    /// `msg_export_ext`: message itself, `msg_export_new/imm`: message envelope.
+    pub out_msg: Cell,
+    pub transaction: Cell,
+    pub is_external: bool,
+}
+
+impl OutMsgShort {
+    /// Returns the outbound message cell.
+    pub fn load_message(&self) -> Result<Cell, Error> {
+        if self.is_external {
+            Ok(self.out_msg.clone())
+        } else {
+            // NOTE: Message is the first reference of the envelope.
+            self.out_msg.as_slice()?.load_reference_cloned()
+        }
+    }
+}
+
+impl<'a> Load<'a> for OutMsgShort {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        let is_external = match slice.load_small_uint(3) {
+            // `msg_export_ext$000`
+            Ok(0b000) => true,
+            // `msg_export_new$001` or `msg_export_imm$010`
+            Ok(0b001 | 0b010) => false,
+            Ok(_) => return Err(Error::InvalidTag),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            out_msg: slice.load_reference_cloned()?,
+            transaction: slice.load_reference_cloned()?,
+            is_external,
+        })
+    }
+}
+
+pub type OutMsgDescrShort = AugDict<HashBytes, CurrencyCollection, OutMsgShort>;
+
+/// Common prefix of the shard description (same for all networks).
+pub struct ShardDescrShort {
+    pub seqno: u32,
+    pub reg_mc_seqno: u32,
+    pub start_lt: u64,
+    pub end_lt: u64,
+    pub root_hash: HashBytes,
+    pub file_hash: HashBytes,
+}
+
+impl<'a> Load<'a> for ShardDescrShort {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.load_small_uint(4) {
+            Ok(0xa | 0xb) => {}
+            Ok(_) => return Err(Error::InvalidTag),
+            Err(e) => return Err(e),
+        }
+
+        Ok(Self {
+            seqno: slice.load_u32()?,
+            reg_mc_seqno: slice.load_u32()?,
+            start_lt: slice.load_u64()?,
+            end_lt: slice.load_u64()?,
+            root_hash: slice.load_u256()?,
+            file_hash: slice.load_u256()?,
+        })
+    }
+}
+
+/// Masterchain block extra.
+///
+/// NOTE: It has the same tag and layout in all networks.
+pub struct McExtra {
+    shard_hashes: ShardHashes,
+    config: Option<BlockchainConfig>,
+}
+
+impl McExtra {
+    const TAG: u16 = 0xcca5;
+}
+
+impl<'a> Load<'a> for McExtra {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        if slice.load_u16()? != Self::TAG {
+            return Err(Error::InvalidTag);
+        }
+
+        let with_config = slice.load_bit()?;
+        let shard_hashes = ShardHashes::load_from(slice)?;
+
+        let config = if with_config {
+            slice.only_last(256, 1)?;
+            Some(BlockchainConfig::load_from(slice)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            shard_hashes,
+            config,
+        })
+    }
+}
+
+impl BlockchainBlockMcExtra for McExtra {
+    fn load_top_shard_block_ids(&self) -> Result<Vec<BlockIdShort>, Error> {
+        let mut shard_ids = Vec::new();
+        for entry in self.shard_hashes.latest_blocks() {
+            let block_id = entry?;
+            shard_ids.push(block_id.as_short_id());
+        }
+
+        Ok(shard_ids)
+    }
+
+    fn find_shard_descr(
+        &self,
+        workchain: i32,
+        address: &HashBytes,
+    ) -> Result<(ShardIdent, ShardDescrShort), Error> {
+        let shard_hashes = self
+            .shard_hashes
+            .get_workchain_shards(workchain)?
+            .ok_or(Error::CellUnderflow)?;
+
+        let (shard, mut descr_root) =
+            find_shard_descr_by_address(shard_hashes.root(), workchain, address)?;
+        Ok((shard, ShardDescrShort::load_from(&mut descr_root)?))
+    }
+
+    fn visit_all_shard_hashes(&self) -> Result<(), Error> {
+        for item in self.shard_hashes.raw_iter() {
+            item?;
+        }
+        Ok(())
+    }
+
+    fn config(&self) -> Option<&BlockchainConfig> {
+        self.config.as_ref()
+    }
+}
+
+/// Shard state (only the accounts dict is parsed).
+pub struct ShardState {
+    pub accounts: Cell,
+}
+
+impl ShardState {
+    /// TON and Tycho (before the v2 layout).
+    const TAG_V1: u32 = 0x9023afe2;
+    /// Tycho only.
+    const TAG_V2: u32 = 0x9023aeee;
+}
+
+impl<'a> Load<'a> for ShardState {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.load_u32()? {
+            Self::TAG_V1 | Self::TAG_V2 => {}
+            _ => return Err(Error::InvalidTag),
+        }
+
+        // NOTE: Accounts dict is always the second reference.
+        Ok(Self {
+            accounts: slice.get_reference_cloned(1)?,
+        })
+    }
+}
+
+impl BlockchainShardState for ShardState {
+    fn load_accounts(&self) -> Result<ShardAccounts, Error> {
+        self.accounts.parse::<ShardAccounts>()
+    }
+}
+
+/// Signature without any tags (stored in a signatures dict).
+struct PlainSignature([u8; 64]);
+
+impl Store for PlainSignature {
+    #[inline]
+    fn store_into(&self, b: &mut CellBuilder, _: &dyn CellContext) -> Result<(), Error> {
+        b.store_raw(&self.0, 512)
+    }
+}
+
+impl<'a> Load<'a> for PlainSignature {
+    #[inline]
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        let mut signature = [0u8; 64];
+        slice.load_raw(&mut signature, 512)?;
+        Ok(Self(signature))
+    }
+}
+
 // === Proff stuff ===
 
 pub struct McBlockBoundInfo {
@@ -152,6 +630,46 @@ where
     })
 }
 
+/// Validator set info which is stored as a library for the bridge contract.
+///
+/// Contains only main validators with their original indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochData {
+    pub utime_since: u32,
+    pub utime_until: u32,
+    pub main_validator_count: u16,
+    /// Minimal signatures weight required to accept the block.
+    pub cutoff_weight: Tokens,
+    /// Validator index -> (public key, weight).
+    pub validators: Dict<u16, (HashBytes, u64)>,
+}
+
+impl Store for EpochData {
+    fn store_into(&self, b: &mut CellBuilder, context: &dyn CellContext) -> Result<(), Error> {
+        let Some(validators) = self.validators.root() else {
+            return Err(Error::CellUnderflow);
+        };
+
+        b.store_u32(self.utime_since)?;
+        b.store_u32(self.utime_until)?;
+        b.store_u16(self.main_validator_count)?;
+        self.cutoff_weight.store_into(b, context)?;
+        b.store_reference(validators.clone())
+    }
+}
+
+impl<'a> Load<'a> for EpochData {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        Ok(Self {
+            utime_since: slice.load_u32()?,
+            utime_until: slice.load_u32()?,
+            main_validator_count: slice.load_u16()?,
+            cutoff_weight: Tokens::load_from(slice)?,
+            validators: Dict::from_raw(Some(slice.load_reference_cloned()?)),
+        })
+    }
+}
+
 /// Converts validator set into an epoch data to be stored as library.
 pub fn make_epoch_data(vset: &ValidatorSet) -> Result<Cell, Error> {
     let main_validator_count = vset.main.get() as usize;
@@ -159,31 +677,57 @@ pub fn make_epoch_data(vset: &ValidatorSet) -> Result<Cell, Error> {
         return Err(Error::InvalidData);
     }
 
-    let mut total_weight = 0u64;
     let mut main_validators = Vec::new();
     for (i, item) in vset.list[..main_validator_count].iter().enumerate() {
         main_validators.push((i as u16, (item.public_key, item.weight)));
-        total_weight = total_weight
-            .checked_add(item.weight)
-            .ok_or(Error::IntOverflow)?;
     }
     assert_eq!(main_validators.len(), main_validator_count);
 
-    let Some(root) =
-        Dict::<u16, (HashBytes, u64)>::try_from_sorted_slice(&main_validators)?.into_root()
-    else {
+    let validators = Dict::<u16, (HashBytes, u64)>::try_from_sorted_slice(&main_validators)?;
+    if validators.is_empty() {
         return Err(Error::CellUnderflow);
-    };
+    }
+
+    let cutoff_weight = compute_cutoff_weight(vset)?;
+
+    CellBuilder::build_from(EpochData {
+        utime_since: vset.utime_since,
+        utime_until: vset.utime_until,
+        main_validator_count: vset.main.get(),
+        cutoff_weight: Tokens::new(cutoff_weight),
+        validators,
+    })
+}
+
+/// Computes the minimal signatures weight of main validators
+/// which is required by the bridge contract.
+fn compute_cutoff_weight(vset: &ValidatorSet) -> Result<u128, Error> {
+    let main_validator_count = vset.main.get() as usize;
+    if vset.list.len() < main_validator_count {
+        return Err(Error::InvalidData);
+    }
+
+    let mut total_weight = 0u64;
+    for item in &vset.list[..main_validator_count] {
+        total_weight = total_weight
+            .checked_add(item.weight)
+            .ok_or(Error::IntOverflow)?;
+    }
 
-    let cutoff_weight = (total_weight as u128) * 2 / 3 + 1;
+    Ok((total_weight as u128) * 2 / 3 + 1)
+}
 
-    let mut b = CellBuilder::new();
-    b.store_u32(vset.utime_since)?;
-    b.store_u32(vset.utime_until)?;
-    b.store_u16(vset.main.get())?;
-    Tokens::new(cutoff_weight).store_into(&mut b, Cell::empty_context())?;
-    b.store_reference(root)?;
-    b.build()
+/// Which signatures to put into the prepared signatures dict.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignaturesMode {
+    /// All available signatures.
+    #[default]
+    All,
+    /// The smallest subset of main validator signatures (the heaviest first)
+    /// which still reaches the cutoff weight of the epoch data and more than
+    /// 2/3 of the total vset weight.
+    MinimalQuorum,
 }
 
 /// Prepares a signatures dict with validator indices as keys.
@@ -191,15 +735,22 @@ pub fn prepare_signatures<I>(signatures: I, vset: &ValidatorSet) -> Result<Cell,
 where
     I: IntoIterator<Item = Result<BlockSignature, Error>>,
 {
-    struct PlainSignature([u8; 64]);
-
-    impl Store for PlainSignature {
-        #[inline]
-        fn store_into(&self, b: &mut CellBuilder, _: &dyn CellContext) -> Result<(), Error> {
-            b.store_raw(&self.0, 512)
-        }
-    }
+    prepare_signatures_ext(signatures, vset, SignaturesMode::All)
+}
 
+/// Prepares a signatures dict with validator indices as keys
+/// and only with signatures selected by the mode.
+///
+/// NOTE: All signatures are used if the cutoff weight can't be reached
+/// by main validators.
+pub fn prepare_signatures_ext<I>(
+    signatures: I,
+    vset: &ValidatorSet,
+    mode: SignaturesMode,
+) -> Result<Cell, Error>
+where
+    I: IntoIterator<Item = Result<BlockSignature, Error>>,
+{
     let mut block_signatures = HashMap::default();
     for entry in signatures {
         let entry = entry?;
@@ -224,14 +775,108 @@ where
         return Err(Error::InvalidData);
     }
 
+    if mode == SignaturesMode::MinimalQuorum
+        && let Some(selected) = select_minimal_quorum(&result, vset)?
+    {
+        result = result
+            .into_iter()
+            .zip(selected)
+            .filter_map(|(item, selected)| selected.then_some(item))
+            .collect();
+    }
+
     let signatures = Dict::try_from_sorted_slice(&result)?;
     signatures.into_root().ok_or(Error::EmptyProof)
 }
 
+/// Marks the smallest subset of main validator signatures which reaches
+/// both the cutoff weight and more than 2/3 of the total vset weight.
+/// Returns `None` if there are not enough signatures.
+fn select_minimal_quorum(
+    signatures: &[(u16, PlainSignature)],
+    vset: &ValidatorSet,
+) -> Result<Option<Vec<bool>>, Error> {
+    // NOTE: Signatures are also checked against the full validator set
+    // (more than 2/3 of the total weight, including non-main validators).
+    let total_cutoff_weight = (vset.total_weight as u128) * 2 / 3 + 1;
+    let cutoff_weight = compute_cutoff_weight(vset)?.max(total_cutoff_weight);
+
+    // Sort main validator signatures by weight (the heaviest first).
+    let mut main = signatures
+        .iter()
+        .enumerate()
+        .filter(|(_, (idx, _))| *idx < vset.main.get())
+        .map(|(i, (idx, _))| (i, vset.list[*idx as usize].weight))
+        .collect::<Vec<_>>();
+    main.sort_by(|(a_i, a_weight), (b_i, b_weight)| b_weight.cmp(a_weight).then(a_i.cmp(b_i)));
+
+    let mut selected = vec![false; signatures.len()];
+    let mut weight = 0u128;
+    for (i, node_weight) in main {
+        if weight >= cutoff_weight {
+            break;
+        }
+        weight += node_weight as u128;
+        selected[i] = true;
+    }
+
+    Ok((weight >= cutoff_weight).then_some(selected))
+}
+
+/// Returns indices of signatures from the prepared dict which
+/// don't belong to the main validators.
+///
+/// Epoch data contains only main validators, so such signatures
+/// will be rejected by the bridge contract.
+pub fn find_signatures_outside_main_set(
+    signatures: &Cell,
+    main_validator_count: u16,
+) -> Result<Vec<u16>, Error> {
+    let signatures = Dict::<u16, PlainSignature>::from_raw(Some(signatures.clone()));
+
+    let mut result = Vec::new();
+    for key in signatures.keys() {
+        let idx = key?;
+        if idx >= main_validator_count {
+            result.push(idx);
+        }
+    }
+    Ok(result)
+}
+
+/// Returns a signature id if the network signs data with the `global_id` prefix.
+pub fn signature_id_from_config(
+    global_id: i32,
+    config: &BlockchainConfigParams,
+) -> Result<Option<i32>, Error> {
+    let global = config.get_global_version()?;
+    Ok(global
+        .capabilities
+        .contains(GlobalCapability::CapSignatureWithId)
+        .then_some(global_id))
+}
+
+/// Builds data which is signed by validators for the specified block.
+///
+/// Data is prefixed with the signature id if it is specified.
+pub fn build_data_for_sign(block_id: &BlockId, signature_id: Option<i32>) -> Vec<u8> {
+    let data = Block::build_data_for_sign(block_id);
+    match signature_id {
+        None => data.to_vec(),
+        Some(signature_id) => {
+            let mut result = Vec::with_capacity(4 + data.len());
+            result.extend_from_slice(&signature_id.to_be_bytes());
+            result.extend_from_slice(&data);
+            result
+        }
+    }
+}
+
 pub fn check_signatures<I>(
     block_id: &BlockId,
     signatures: I,
     vset: &ValidatorSet,
+    signature_id: Option<i32>,
 ) -> Result<(), Error>
 where
     I: IntoIterator<Item = Result<BlockSignature, Error>>,
@@ -242,7 +887,7 @@ where
         .map(|x| x.map(|item| (item.node_id_short, item.signature)))
         .collect::<Result<HashMap<_, _>, _>>()?;
 
-    let to_sign = Block::build_data_for_sign(block_id);
+    let to_sign = build_data_for_sign(block_id, signature_id);
 
     let mut weight = 0u64;
     for node in &vset.list {
@@ -265,71 +910,162 @@ where
         return Err(Error::InvalidData);
     }
 
-    // Check that signature weight is enough.
-    match (weight.checked_mul(3), vset.total_weight.checked_mul(2)) {
-        (Some(weight_x3), Some(total_weight_x2)) => {
-            if weight_x3 > total_weight_x2 {
-                Ok(())
-            } else {
-                Err(Error::InvalidData)
-            }
-        }
-        _ => Err(Error::IntOverflow),
-    }
+    check_signatures_weight(weight, vset)
 }
 
-/// Build merkle proof cell which contains a proof chain in its root.
-pub fn make_proof_chain(
-    mc_file_hash: &HashBytes,
-    mc_block: Cell,
-    shard_blocks: &[Cell],
-    vset_utime_since: u32,
-    signatures: Cell,
-) -> Result<Cell, Error> {
-    let mut b = CellBuilder::new();
-    b.store_u256(mc_file_hash)?;
-    b.store_u32(vset_utime_since)?;
-    b.store_reference(mc_block)?;
-    b.store_reference(signatures)?;
+/// Builds a signatures dict from a plain list (list indices are used as keys).
+///
+/// Returns the dict with the total weight of signers. All signers must be
+/// unique validators from the `vset` with enough weight.
+///
+/// NOTE: Signatures themselves are not verified (see [`check_signatures`]).
+pub fn make_block_signatures_dict(
+    signatures: &[BlockSignature],
+    vset: &ValidatorSet,
+) -> Result<(Dict<u16, BlockSignature>, u64), Error> {
+    if signatures.len() > u16::MAX as usize + 1 {
+        return Err(Error::IntOverflow);
+    }
 
-    let mut iter = shard_blocks.iter();
-    if let Some(sc_block) = iter.next() {
-        b.store_reference(sc_block.clone())?;
+    let total_weight = compute_signers_weight(signatures, vset)?;
 
-        let mut iter = iter.rev();
+    let entries = signatures
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (i as u16, item.clone()))
+        .collect::<Vec<_>>();
+    let dict = Dict::try_from_sorted_slice(&entries)?;
 
-        let remaining = iter.len();
-        let mut child = if !remaining.is_multiple_of(3) {
-            let mut b = CellBuilder::new();
-            for cell in iter.by_ref().take(remaining % 3).rev() {
-                b.store_reference(cell.clone())?;
-            }
-            Some(b.build()?)
-        } else {
-            None
-        };
+    Ok((dict, total_weight))
+}
+
+/// Collects block signatures into a plain list.
+///
+/// Checks that the signature count and the total weight are consistent
+/// with the stored values and that the weight is enough for the `vset`.
+///
+/// NOTE: Signatures themselves are not verified (see [`check_signatures`]).
+pub fn collect_block_signatures<S: BlockchainBlockSignatures>(
+    signatures: &S,
+    vset: &ValidatorSet,
+) -> Result<Vec<BlockSignature>, Error> {
+    let list = signatures
+        .signatures()
+        .values()
+        .collect::<Result<Vec<_>, _>>()?;
 
-        for _ in 0..(remaining / 3) {
-            let sc1 = iter.next().unwrap();
-            let sc2 = iter.next().unwrap();
-            let sc3 = iter.next().unwrap();
+    if list.len() != signatures.signature_count() as usize {
+        return Err(Error::InvalidData);
+    }
 
-            let mut b = CellBuilder::new();
-            b.store_reference(sc3.clone())?;
-            b.store_reference(sc2.clone())?;
-            b.store_reference(sc1.clone())?;
-            if let Some(child) = child.take() {
-                b.store_reference(child)?;
-            }
-            child = Some(b.build()?);
+    let total_weight = compute_signers_weight(&list, vset)?;
+    if total_weight != signatures.total_weight() {
+        return Err(Error::InvalidData);
+    }
+
+    Ok(list)
+}
+
+/// Computes the total weight of unique signers and checks that it is enough.
+fn compute_signers_weight(
+    signatures: &[BlockSignature],
+    vset: &ValidatorSet,
+) -> Result<u64, Error> {
+    let mut signers = HashSet::with_capacity_and_hasher(signatures.len(), Default::default());
+    for item in signatures {
+        if !signers.insert(item.node_id_short) {
+            return Err(Error::InvalidData);
+        }
+    }
+
+    let mut weight = 0u64;
+    for node in &vset.list {
+        let node_id_short = tl_proto::hash(tycho_crypto::tl::PublicKey::Ed25519 {
+            key: node.public_key.as_array(),
+        });
+        if signers.remove(HashBytes::wrap(&node_id_short)) {
+            weight = weight.checked_add(node.weight).ok_or(Error::IntOverflow)?;
         }
+    }
+
+    // All signers must be from the vset.
+    if !signers.is_empty() {
+        return Err(Error::InvalidData);
+    }
+
+    check_signatures_weight(weight, vset)?;
+    Ok(weight)
+}
 
-        if let Some(child) = child {
-            b.store_reference(child)?;
+/// Checks that signature weight is more than 2/3 of the total vset weight.
+fn check_signatures_weight(weight: u64, vset: &ValidatorSet) -> Result<(), Error> {
+    match (weight.checked_mul(3), vset.total_weight.checked_mul(2)) {
+        (Some(weight_x3), Some(total_weight_x2)) => {
+            if weight_x3 > total_weight_x2 {
+                Ok(())
+            } else {
+                Err(Error::InvalidData)
+            }
         }
+        _ => Err(Error::IntOverflow),
     }
+}
+
+/// Build merkle proof cell which contains a proof chain in its root.
+pub fn make_proof_chain(
+    mc_file_hash: &HashBytes,
+    mc_block: Cell,
+    shard_blocks: &[Cell],
+    vset_utime_since: u32,
+    signatures: Cell,
+) -> Result<Cell, Error> {
+    let cell = CellBuilder::build_from(ProofChain {
+        mc_file_hash: *mc_file_hash,
+        vset_utime_since,
+        mc_block,
+        signatures,
+        shard_blocks: shard_blocks.to_vec(),
+    })?;
+
+    CellBuilder::build_from(MerkleProof {
+        hash: *cell.hash(0),
+        depth: cell.depth(0),
+        cell,
+    })
+}
+
+/// Build merkle proof cell which contains a versioned proof chain in its root.
+///
+/// Header fields are taken from the masterchain block proof
+/// (it must include block info).
+pub fn make_proof_chain_v2<M>(
+    mc_file_hash: &HashBytes,
+    mc_block: Cell,
+    shard_blocks: &[Cell],
+    vset_utime_since: u32,
+    signatures: Cell,
+    kind: ProofKind,
+) -> Result<Cell, Error>
+where
+    M: BlockchainModels,
+{
+    let raw_block = mc_block.parse::<M::Block>()?;
+    let info = raw_block.load_info()?;
+
+    let cell = CellBuilder::build_from(ProofChainV2 {
+        global_id: raw_block.global_id(),
+        mc_seqno: info.seqno(),
+        mc_gen_utime: info.gen_utime(),
+        kind,
+        chain: ProofChain {
+            mc_file_hash: *mc_file_hash,
+            vset_utime_since,
+            mc_block,
+            signatures,
+            shard_blocks: shard_blocks.to_vec(),
+        },
+    })?;
 
-    let cell = b.build()?;
     CellBuilder::build_from(MerkleProof {
         hash: *cell.hash(0),
         depth: cell.depth(0),
@@ -337,13 +1073,112 @@ pub fn make_proof_chain(
     })
 }
 
+/// Build merkle proof cell which contains a proof chain in the specified format.
+///
+/// NOTE: Masterchain block proof must include block info for [`ProofChainFormat::V2`].
+pub fn make_proof_chain_ext<M>(
+    format: ProofChainFormat,
+    mc_file_hash: &HashBytes,
+    mc_block: Cell,
+    shard_blocks: &[Cell],
+    vset_utime_since: u32,
+    signatures: Cell,
+) -> Result<Cell, Error>
+where
+    M: BlockchainModels,
+{
+    match format {
+        ProofChainFormat::V1 => make_proof_chain(
+            mc_file_hash,
+            mc_block,
+            shard_blocks,
+            vset_utime_since,
+            signatures,
+        ),
+        ProofChainFormat::V2(kind) => make_proof_chain_v2::<M>(
+            mc_file_hash,
+            mc_block,
+            shard_blocks,
+            vset_utime_since,
+            signatures,
+            kind,
+        ),
+    }
+}
+
+/// Proof chain size info.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "api", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ProofChainStats {
+    /// Number of unique cells (including the merkle proof root).
+    pub cell_count: u64,
+    /// Total number of data bits in unique cells.
+    pub bit_count: u64,
+    /// Max depth of the proof chain cell tree.
+    pub max_depth: u16,
+    /// Number of unique pruned branches.
+    pub pruned_branch_count: u64,
+    /// Number of shard blocks in the proof chain.
+    pub shard_hop_count: u32,
+}
+
+/// Computes size info for the proof chain built by [`make_proof_chain`]
+/// or [`make_proof_chain_v2`].
+pub fn analyze_proof_chain(proof_chain: &DynCell) -> Result<ProofChainStats, Error> {
+    let root = proof_chain.parse_exotic::<MerkleProof>()?.cell;
+
+    // NOTE: The first layout has no tag and starts with the masterchain file hash.
+    let chain = match root.as_slice()?.get_u32(0)? {
+        ProofChainV2::TAG => ProofChainV2::load_from_proof(proof_chain)?.chain,
+        _ => ProofChain::load_from_proof(proof_chain)?,
+    };
+
+    let mut stats = ProofChainStats {
+        max_depth: proof_chain.repr_depth(),
+        shard_hop_count: chain.shard_blocks.len() as u32,
+        ..Default::default()
+    };
+
+    let mut visited = HashSet::<&HashBytes>::default();
+    let mut stack = vec![proof_chain];
+    while let Some(cell) = stack.pop() {
+        if !visited.insert(cell.repr_hash()) {
+            continue;
+        }
+
+        stats.cell_count += 1;
+        stats.bit_count += cell.bit_len() as u64;
+        if cell.cell_type() == CellType::PrunedBranch {
+            stats.pruned_branch_count += 1;
+        }
+
+        stack.extend(cell.references());
+    }
+
+    Ok(stats)
+}
+
 /// Leaves only transaction hashes in block.
 ///
+/// Transactions of account blocks are visited in parallel on the rayon
+/// thread pool. All cells are tracked by the same usage tree, so visited
+/// cells are merged into a single proof. `is_cancelled` is checked for
+/// each account block and transaction.
+///
+/// `on_tx` is called with an account, lt and hash of each transaction
+/// (in the order of account blocks).
+///
 /// Input: full block.
-pub fn make_pruned_block<M, F>(block_root: Cell, mut on_tx: F) -> Result<Cell, Error>
+pub fn make_pruned_block<M, C, F>(
+    block_root: Cell,
+    is_cancelled: C,
+    mut on_tx: F,
+) -> Result<Cell, Error>
 where
     M: BlockchainModels,
-    for<'a> F: FnMut(&'a HashBytes, u64) -> Result<(), Error>,
+    C: Fn() -> bool + Sync,
+    for<'a> F: FnMut(&'a HashBytes, u64, &'a HashBytes) -> Result<(), Error>,
 {
     let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
 
@@ -359,23 +1194,44 @@ where
         info.touch_recursive();
     }
 
-    let account_blocks = extra.load_account_blocks()?;
-
-    // Visit only items with transaction roots.
-    for item in account_blocks.values() {
+    // Include account blocks dict nodes (transactions are visited separately).
+    let mut account_transactions = Vec::new();
+    for item in extra.load_account_blocks()?.values() {
+        if is_cancelled() {
+            return Err(Error::Cancelled);
+        }
         let (_, account_block) = item?;
 
-        // NOTE: Account block `transactions` dict is a new cell.
+        // NOTE: Account block `transactions` dict is a new cell,
+        // but its children are still tracked.
         let (transactions, _) = account_block.transactions.into_parts();
-        let transactions = Dict::<u64, (CurrencyCollection, Cell)>::from_raw(
-            transactions.into_root().map(|cell| usage_tree.track(&cell)),
-        );
+        if let Some(root) = transactions.into_root() {
+            account_transactions.push((account_block.account, root));
+        }
+    }
 
-        for item in transactions.iter() {
-            let (lt, _) = item?;
+    let visited = account_transactions
+        .into_par_iter()
+        .map(|(account, root)| {
+            let transactions = Dict::<u64, (CurrencyCollection, Cell)>::from_raw(Some(root));
+
+            let mut txs = Vec::new();
+            for item in transactions.iter() {
+                if is_cancelled() {
+                    return Err(Error::Cancelled);
+                }
+                let (lt, (_, tx)) = item?;
+                txs.push((lt, *tx.repr_hash()));
+            }
 
+            Ok::<_, Error>((account, txs))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (account, txs) in visited {
+        for (lt, tx_hash) in txs {
             // Handle tx.
-            on_tx(&account_block.account, lt)?;
+            on_tx(&account, lt, &tx_hash)?;
         }
     }
 
@@ -413,9 +1269,18 @@ where
 
         custom.visit_all_shard_hashes()?;
     } else {
-        // Include only prev block ref for shard blocks.
+        // Include only prev block refs for shard blocks.
         let info = raw_block.load_info()?;
-        info.prev_ref().data();
+        if info.after_merge() {
+            // Include both refs to the previous blocks.
+            info.prev_ref().touch_recursive();
+        } else {
+            info.prev_ref().data();
+        }
+
+        if let Some(prev_vert_ref) = info.prev_vert_ref() {
+            prev_vert_ref.data();
+        }
     }
 
     // Build block proof.
@@ -467,15 +1332,63 @@ where
     Ok(pruned_block)
 }
 
+/// Creates a key block proof with a single config param.
+///
+/// Returns `None` if there is no such param in the config.
+///
+/// Input: full key block.
+pub fn make_config_param_proof<M>(block_root: Cell, param: u32) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+{
+    let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
+
+    let tracked_root = usage_tree.track(&block_root);
+    let raw_block = tracked_root.parse::<M::Block>()?;
+
+    // Block info is required for masterchain blocks to check signatures.
+    // Only block info root cell is required (prev_ref is ignored).
+    let info = raw_block.load_info()?;
+    if !info.is_key_block() {
+        return Err(Error::InvalidData);
+    }
+
+    // Access only the required config param.
+    let extra = raw_block.load_extra()?;
+    let custom = extra.load_custom()?.ok_or(Error::CellUnderflow)?;
+    let config = custom.config().ok_or(Error::CellUnderflow)?;
+
+    let Some(value) = config.get_raw_cell_ref(param)? else {
+        return Ok(None);
+    };
+    value.touch_recursive();
+
+    // Build block proof.
+    let pruned_block = MerkleProof::create(block_root.as_ref(), usage_tree)
+        .prune_big_cells(true)
+        .build_raw_ext(Cell::empty_context())?;
+
+    if pruned_block.hash(0) != block_root.hash(0) {
+        return Err(Error::InvalidData);
+    }
+
+    Ok(Some(pruned_block))
+}
+
 pub struct McProofForShard {
     pub root: Cell,
-    pub latest_shard_seqno: u32,
+    /// The latest block of the shard with the account.
+    pub latest_shard_block_id: BlockId,
 }
 
 /// Creates an mc block proof for the proof chain.
 ///
 /// Input: pivot block.
-pub fn make_mc_proof<M>(block_root: Cell, shard: ShardIdent) -> Result<McProofForShard, Error>
+pub fn make_mc_proof<M>(
+    block_root: Cell,
+    workchain: i32,
+    account: &HashBytes,
+) -> Result<McProofForShard, Error>
 where
     M: BlockchainModels,
 {
@@ -492,7 +1405,7 @@ where
     let extra = raw_block.load_extra()?;
     let custom = extra.load_custom()?.ok_or(Error::CellUnderflow)?;
 
-    let latest_shard_seqno = custom.find_shard_seqno(shard)?;
+    let (shard, descr) = custom.find_shard_descr(workchain, account)?;
 
     // Build block proof.
     let pruned_block = MerkleProof::create(block_root.as_ref(), usage_tree)
@@ -505,10 +1418,56 @@ where
 
     Ok(McProofForShard {
         root: pruned_block,
-        latest_shard_seqno,
+        latest_shard_block_id: BlockId {
+            shard,
+            seqno: descr.seqno,
+            root_hash: descr.root_hash,
+            file_hash: descr.file_hash,
+        },
     })
 }
 
+/// Collects pivot block proofs from the latest shard block down to the target
+/// block (exclusive), following shard splits and merges on the path to the account.
+///
+/// Input: `load_pivot` returns a pivot block from [`make_pivot_block_proof`].
+pub fn collect_pivot_chain<M, F, E>(
+    latest_block_id: &BlockId,
+    target_block_id: &BlockIdShort,
+    account: &HashBytes,
+    mut load_pivot: F,
+) -> Result<Vec<Cell>, E>
+where
+    M: BlockchainModels,
+    F: FnMut(&BlockIdShort) -> Result<Cell, E>,
+    E: From<Error>,
+{
+    let mut result = Vec::new();
+
+    let mut block_id = *latest_block_id;
+    while block_id.as_short_id() != *target_block_id {
+        // NOTE: Seqno strictly decreases even across splits and merges.
+        if block_id.seqno <= target_block_id.seqno {
+            return Err(Error::InvalidData.into());
+        }
+
+        let pivot = load_pivot(&block_id.as_short_id())?;
+        if pivot.hash(0) != &block_id.root_hash {
+            return Err(Error::InvalidData.into());
+        }
+
+        let info = pivot.parse::<M::Block>()?.load_info()?;
+        if info.shard() != &block_id.shard || info.seqno() != block_id.seqno {
+            return Err(Error::InvalidData.into());
+        }
+
+        block_id = info.load_prev_block_id(account)?;
+        result.push(pivot);
+    }
+
+    Ok(result)
+}
+
 /// Creates a block with a single branch of the specified transaction.
 ///
 /// Input: pruned block from [`make_pruned_block`].
@@ -520,6 +1479,72 @@ pub fn make_tx_proof<M>(
 ) -> Result<Option<Cell>, Error>
 where
     M: BlockchainModels,
+{
+    make_tx_proof_ext::<M>(block_root, account, lt, include_info, TxContent::default())
+}
+
+/// Transaction parts to include into the transaction proof.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TxContent {
+    /// Include the transaction cell with its description and message headers.
+    pub full_tx: bool,
+    /// Include the full inbound message (implies `full_tx`).
+    pub in_msg_body: bool,
+    /// Include all full outbound messages (implies `full_tx`).
+    pub out_msg_bodies: bool,
+}
+
+impl TxContent {
+    /// Returns `true` if only the transaction hash is required.
+    pub fn is_empty(&self) -> bool {
+        !self.full_tx && !self.in_msg_body && !self.out_msg_bodies
+    }
+}
+
+/// Creates a block with a single branch of the specified transaction
+/// and the selected transaction content.
+///
+/// Input: full block if `content` is not empty,
+/// pruned block from [`make_pruned_block`] otherwise.
+pub fn make_tx_proof_ext<M>(
+    block_root: Cell,
+    account: &HashBytes,
+    lt: u64,
+    include_info: bool,
+    content: TxContent,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+{
+    make_multi_tx_proof_impl::<M, _>(block_root, [(account, lt)], include_info, content)
+}
+
+/// Creates a block with branches of all specified transactions.
+///
+/// Returns `None` if at least one transaction was not found.
+///
+/// Input: pruned block from [`make_pruned_block`].
+pub fn make_multi_tx_proof<'a, M, I>(
+    block_root: Cell,
+    transactions: I,
+    include_info: bool,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+    I: IntoIterator<Item = (&'a HashBytes, u64)>,
+{
+    make_multi_tx_proof_impl::<M, _>(block_root, transactions, include_info, TxContent::default())
+}
+
+fn make_multi_tx_proof_impl<'a, M, I>(
+    block_root: Cell,
+    transactions: I,
+    include_info: bool,
+    content: TxContent,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+    I: IntoIterator<Item = (&'a HashBytes, u64)>,
 {
     let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
 
@@ -532,22 +1557,28 @@ where
         info.prev_ref().data();
     }
 
-    // Make a single branch with transaction.
+    // Make a branch for each transaction.
     let extra = raw_block.load_extra()?;
 
     let account_blocks = extra.load_account_blocks()?;
-    let Some((_, account_block)) = account_blocks.get(account).ok().flatten() else {
-        return Ok(None);
-    };
+    for (account, lt) in transactions {
+        let Some((_, account_block)) = account_blocks.get(account).ok().flatten() else {
+            return Ok(None);
+        };
 
-    let (transactions, _) = account_block.transactions.into_parts();
-    let transactions = Dict::<u64, (CurrencyCollection, Cell)>::from_raw(
-        transactions.into_root().map(|cell| usage_tree.track(&cell)),
-    );
+        let (transactions, _) = account_block.transactions.into_parts();
+        let transactions = Dict::<u64, (CurrencyCollection, Cell)>::from_raw(
+            transactions.into_root().map(|cell| usage_tree.track(&cell)),
+        );
 
-    if transactions.get(lt).ok().flatten().is_none() {
-        return Ok(None);
-    };
+        let Some((_, tx)) = transactions.get(lt).ok().flatten() else {
+            return Ok(None);
+        };
+
+        if !content.is_empty() {
+            touch_tx_content(&tx, content)?;
+        }
+    }
 
     // Build block proof.
     let pruned_block = MerkleProof::create(block_root.as_ref(), usage_tree)
@@ -561,42 +1592,1745 @@ where
     Ok(Some(pruned_block))
 }
 
-fn find_shard_descr(mut root: &'_ DynCell, mut prefix: u64) -> Result<CellSlice<'_>, Error> {
-    const HIGH_BIT: u64 = 1u64 << 63;
+/// Finds the transaction cell in the block.
+///
+/// Input: full block.
+pub fn find_transaction<M>(
+    block_root: &Cell,
+    account: &HashBytes,
+    lt: u64,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+{
+    let raw_block = block_root.parse::<M::Block>()?;
+    let account_blocks = raw_block.load_extra()?.load_account_blocks()?;
+
+    let Some((_, account_block)) = account_blocks.get(account)? else {
+        return Ok(None);
+    };
 
-    debug_assert_ne!(prefix, 0);
-    while prefix != HIGH_BIT {
-        // Expect `bt_fork$1`.
-        let mut cs = root.as_slice()?;
-        if !cs.load_bit()? {
-            return Err(Error::InvalidData);
-        }
+    let (transactions, _) = account_block.transactions.into_parts();
+    Ok(transactions.get(lt)?.map(|(_, tx)| tx))
+}
 
-        // Get left (prefix bit 0) or right (prefix bit 1) branch.
-        root = cs.get_reference((prefix & HIGH_BIT != 0) as u8)?;
+/// Returns account, lt and inbound message hash of all transactions
+/// with inbound messages.
+///
+/// Input: full block.
+pub fn collect_in_msg_hashes<M>(
+    block_root: &Cell,
+) -> Result<Vec<(HashBytes, u64, HashBytes)>, Error>
+where
+    M: BlockchainModels,
+{
+    let raw_block = block_root.parse::<M::Block>()?;
+    let account_blocks = raw_block.load_extra()?.load_account_blocks()?;
 
-        // Skip one prefix bit.
-        prefix <<= 1;
-    }
+    let mut result = Vec::new();
+    for item in account_blocks.values() {
+        let (_, account_block) = item?;
 
-    // Root is now a `bt_leaf$0`.
-    let mut cs = root.as_slice()?;
-    if cs.load_bit()? {
-        return Err(Error::InvalidTag);
+        let (transactions, _) = account_block.transactions.into_parts();
+        for item in transactions.iter() {
+            let (lt, (_, tx)) = item?;
+            if let Some(in_msg) = tx.parse::<Transaction>()?.in_msg {
+                result.push((account_block.account, lt, *in_msg.repr_hash()));
+            }
+        }
     }
 
-    Ok(cs)
+    Ok(result)
 }
 
-#[cfg(test)]
-mod tests {
-    use std::path::Path;
+/// Returns account, lt and hash of all transactions.
+///
+/// Input: full block or virtualized pruned block from [`make_pruned_block`].
+pub fn collect_tx_hashes<M>(block_root: &Cell) -> Result<Vec<(HashBytes, u64, HashBytes)>, Error>
+where
+    M: BlockchainModels,
+{
+    let raw_block = block_root.parse::<M::Block>()?;
+    let account_blocks = raw_block.load_extra()?.load_account_blocks()?;
+
+    let mut result = Vec::new();
+    for item in account_blocks.values() {
+        let (_, account_block) = item?;
+
+        let (transactions, _) = account_block.transactions.into_parts();
+        for item in transactions.iter() {
+            let (lt, (_, tx)) = item?;
+            result.push((account_block.account, lt, *tx.repr_hash()));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Includes the selected parts of the tracked transaction cell.
+fn touch_tx_content(tx_root: &Cell, content: TxContent) -> Result<(), Error> {
+    // NOTE: Parsing touches the transaction root and its messages cell.
+    let tx = tx_root.parse::<Transaction>()?;
+
+    // Include the full description (compute phase, action phase, etc.).
+    tx.info.inner().touch_recursive();
+    tx.state_update.inner().data();
+
+    let touch_msg = |msg: &Cell, with_body: bool| {
+        if with_body {
+            msg.touch_recursive();
+        } else {
+            // Include only the message root cell (with an inline body).
+            msg.data();
+        }
+    };
+
+    if let Some(in_msg) = &tx.in_msg {
+        touch_msg(in_msg, content.in_msg_body);
+    }
+
+    for msg in tx.out_msgs.values() {
+        touch_msg(&msg?, content.out_msg_bodies);
+    }
+
+    Ok(())
+}
+
+/// Creates a block which proves that the account has no transaction
+/// with the specified lt.
+///
+/// Returns `None` if there is such transaction.
+///
+/// NOTE: Only this block is covered. A masterchain block can commit
+/// several blocks of the account shard, each of them must be checked.
+///
+/// Input: pruned block from [`make_pruned_block`].
+pub fn make_tx_absence_proof<M>(
+    block_root: Cell,
+    account: &HashBytes,
+    lt: u64,
+    include_info: bool,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+{
+    make_absence_proof::<M>(block_root, account, Some(lt), include_info)
+}
+
+/// Creates a block which proves that the account has no transactions in it.
+///
+/// Returns `None` if the account has at least one transaction.
+///
+/// NOTE: Only this block is covered (see [`make_tx_absence_proof`]).
+///
+/// Input: pruned block from [`make_pruned_block`].
+pub fn make_account_absence_proof<M>(
+    block_root: Cell,
+    account: &HashBytes,
+    include_info: bool,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+{
+    make_absence_proof::<M>(block_root, account, None, include_info)
+}
+
+fn make_absence_proof<M>(
+    block_root: Cell,
+    account: &HashBytes,
+    lt: Option<u64>,
+    include_info: bool,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+{
+    let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
+
+    let tracked_root = usage_tree.track(&block_root);
+    let raw_block = tracked_root.parse::<M::Block>()?;
+
+    if include_info {
+        let info = raw_block.load_info()?;
+        // Touch `prev_ref` data to include it into the cell.
+        info.prev_ref().data();
+    }
+
+    // NOTE: Dict lookup touches all forks on the path to the missing key.
+    let extra = raw_block.load_extra()?;
+
+    let account_blocks = extra.load_account_blocks()?;
+    if let Some((_, account_block)) = account_blocks.get(account)? {
+        let Some(lt) = lt else {
+            return Ok(None);
+        };
+
+        let (transactions, _) = account_block.transactions.into_parts();
+        let transactions = Dict::<u64, (CurrencyCollection, Cell)>::from_raw(
+            transactions.into_root().map(|cell| usage_tree.track(&cell)),
+        );
+
+        if transactions.get(lt)?.is_some() {
+            return Ok(None);
+        }
+    }
+
+    // Build block proof.
+    let pruned_block = MerkleProof::create(block_root.as_ref(), usage_tree)
+        .prune_big_cells(true)
+        .build_raw_ext(Cell::empty_context())?;
+
+    if pruned_block.hash(0) != block_root.hash(0) {
+        return Err(Error::InvalidData);
+    }
+
+    Ok(Some(pruned_block))
+}
+
+/// Creates a block with a single branch of the specified outbound message.
+///
+/// The proof contains the outbound message cell and the root cell
+/// of the transaction which emitted it. Returns `None` if the message
+/// was not emitted by the transaction of `account` with the specified `lt`.
+///
+/// Input: full block.
+pub fn make_out_msg_proof<M>(
+    block_root: Cell,
+    account: &HashBytes,
+    lt: u64,
+    msg_hash: &HashBytes,
+    include_info: bool,
+) -> Result<Option<Cell>, Error>
+where
+    M: BlockchainModels,
+{
+    let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
+
+    let tracked_root = usage_tree.track(&block_root);
+    let raw_block = tracked_root.parse::<M::Block>()?;
+
+    if include_info {
+        let info = raw_block.load_info()?;
+        // Touch `prev_ref` data to include it into the cell.
+        info.prev_ref().data();
+    }
+
+    // Make a single branch with the outbound message.
+    let extra = raw_block.load_extra()?;
+
+    let out_msgs = extra.load_out_msg_description()?;
+    let Some((_, out_msg)) = out_msgs.get(msg_hash).ok().flatten() else {
+        return Ok(None);
+    };
+
+    // Check that the message was emitted by the requested transaction.
+    if load_tx_account_and_lt(out_msg.transaction.as_ref())? != (*account, lt) {
+        return Ok(None);
+    }
+
+    // Include the full message.
+    out_msg.load_message()?.touch_recursive();
+
+    // Include only the root cell of the transaction.
+    out_msg.transaction.data();
+
+    // Build block proof.
+    let pruned_block = MerkleProof::create(block_root.as_ref(), usage_tree)
+        .prune_big_cells(true)
+        .build_raw_ext(Cell::empty_context())?;
+
+    if pruned_block.hash(0) != block_root.hash(0) {
+        return Err(Error::InvalidData);
+    }
+
+    Ok(Some(pruned_block))
+}
+
+/// Reads the account and lt from the transaction root cell.
+fn load_tx_account_and_lt(tx_root: &DynCell) -> Result<(HashBytes, u64), Error> {
+    // `transaction$0111 account_addr:bits256 lt:uint64 ...`
+    let mut slice = tx_root.as_slice()?;
+    if slice.load_small_uint(4)? != 0b0111 {
+        return Err(Error::InvalidTag);
+    }
+    Ok((slice.load_u256()?, slice.load_u64()?))
+}
+
+/// Account state proof parts.
+pub struct AccountStateProof {
+    /// Block proof with the `state_update` cell.
+    pub block: Cell,
+    /// Merkle proof of the shard state with a single branch to the account.
+    pub state: Cell,
+}
+
+/// Creates a proof of the account state after the specified block.
+///
+/// The block proof contains only the `state_update` cell (its new hash is
+/// the hash of the shard state). The state proof contains the `ShardAccount`
+/// and the root cell of the account (code and data are pruned, but their
+/// hashes are preserved). If there is no such account, the state proof
+/// contains the path of its absence.
+///
+/// Input: full block and its full shard state.
+pub fn make_account_state_proof<M>(
+    block_root: Cell,
+    state_root: Cell,
+    account: &HashBytes,
+    include_info: bool,
+) -> Result<AccountStateProof, Error>
+where
+    M: BlockchainModels,
+{
+    // Check that the state is for the specified block.
+    // NOTE: State can be partial (e.g. from the block merkle update).
+    let state_update = block_root
+        .parse::<M::Block>()?
+        .load_state_update_raw()?
+        .parse_exotic::<MerkleUpdate>()?;
+    if state_update.new_hash != *state_root.hash(0) {
+        return Err(Error::InvalidData);
+    }
+
+    // Build block proof.
+    let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
+
+    let tracked_root = usage_tree.track(&block_root);
+    let raw_block = tracked_root.parse::<M::Block>()?;
+
+    if include_info {
+        let info = raw_block.load_info()?;
+        // Touch `prev_ref` data to include it into the cell.
+        info.prev_ref().data();
+    }
+
+    // NOTE: Only the root of the merkle update is required.
+    raw_block.load_state_update_raw()?.data();
+
+    let block_proof = MerkleProof::create(block_root.as_ref(), usage_tree)
+        .prune_big_cells(true)
+        .build_raw_ext(Cell::empty_context())?;
+
+    if block_proof.hash(0) != block_root.hash(0) {
+        return Err(Error::InvalidData);
+    }
+
+    // Build state proof.
+    let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
+
+    let tracked_root = usage_tree.track(&state_root);
+    let accounts = tracked_root.parse::<M::ShardState>()?.load_accounts()?;
+
+    // Make a single branch with the account.
+    if let Some((_, shard_account)) = accounts.get(account)? {
+        shard_account.account.inner().data();
+    }
+
+    let state_proof = MerkleProof::create(state_root.as_ref(), usage_tree)
+        .prune_big_cells(true)
+        .build_raw_ext(Cell::empty_context())?;
+
+    if state_proof.hash(0) != state_root.hash(0) {
+        return Err(Error::InvalidData);
+    }
+
+    let state_proof = CellBuilder::build_from(MerkleProof {
+        hash: *state_proof.hash(0),
+        depth: state_proof.depth(0),
+        cell: state_proof,
+    })?;
+
+    Ok(AccountStateProof {
+        block: block_proof,
+        state: state_proof,
+    })
+}
+
+/// Creates a proof of the account state after the block with its transaction.
+///
+/// Same as [`make_account_state_proof`], but the state is taken from the new
+/// state of the block merkle update, so only accounts changed by the block
+/// can be proven.
+///
+/// Input: full block.
+pub fn make_account_state_proof_from_update<M>(
+    block_root: Cell,
+    account: &HashBytes,
+    include_info: bool,
+) -> Result<AccountStateProof, Error>
+where
+    M: BlockchainModels,
+{
+    let state_update = block_root
+        .parse::<M::Block>()?
+        .load_state_update_raw()?
+        .parse_exotic::<MerkleUpdate>()?;
+    make_account_state_proof::<M>(block_root, state_update.new, account, include_info)
+}
+
+fn find_shard_descr_by_address<'a>(
+    mut root: &'a DynCell,
+    workchain: i32,
+    address: &HashBytes,
+) -> Result<(ShardIdent, CellSlice<'a>), Error> {
+    const MAX_SPLIT_DEPTH: usize = 60;
+
+    for i in 0..=MAX_SPLIT_DEPTH {
+        // Stop on `bt_leaf$0`.
+        let mut cs = root.as_slice()?;
+        if !cs.load_bit()? {
+            // Leave only first `i` address bits and a tag bit.
+            let tag = 1u64 << (63 - i);
+            let address_prefix = u64::from_be_bytes(address.0[..8].try_into().unwrap());
+            let prefix = (address_prefix & !(tag | (tag - 1))) | tag;
+
+            let shard = ShardIdent::new(workchain, prefix).ok_or(Error::InvalidData)?;
+            return Ok((shard, cs));
+        }
+
+        // Get left (address bit 0) or right (address bit 1) branch.
+        let bit = (address.0[i / 8] >> (7 - i % 8)) & 1;
+        root = cs.get_reference(bit)?;
+    }
+
+    Err(Error::InvalidData)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
 
     use anyhow::{Context, Result};
     use tycho_types::boc::Boc;
+    use tycho_types::models::{ConsensusInfo, StdAddr};
 
+    use super::fixtures::{
+        BlockBuilder, BuiltBlock, Network, Validators, make_full_tx, make_msg, make_msg_with_body,
+        make_shard_state, make_tx,
+    };
+    use super::ton::{TonBlock, TonBlockSignatures};
+    use super::tycho::{TychoBlock, TychoBlockSignatures};
     use super::*;
 
+    #[test]
+    fn proof_chain_roundtrip() -> Result<()> {
+        let mc_block = CellBuilder::build_from(u32::MAX)?;
+        let signatures = CellBuilder::build_from(u32::MAX - 1)?;
+
+        for shard_block_count in 0..10u32 {
+            let shard_blocks = (0..shard_block_count)
+                .map(CellBuilder::build_from)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let proof = make_proof_chain(
+                &HashBytes([0x11; 32]),
+                mc_block.clone(),
+                &shard_blocks,
+                123,
+                signatures.clone(),
+            )?;
+
+            let chain = ProofChain::load_from_proof(proof.as_ref())?;
+            assert_eq!(chain.mc_file_hash, HashBytes([0x11; 32]));
+            assert_eq!(chain.vset_utime_since, 123);
+            assert_eq!(chain.mc_block, mc_block);
+            assert_eq!(chain.signatures, signatures);
+            assert_eq!(chain.shard_blocks, shard_blocks);
+            assert_eq!(
+                chain.target_block(),
+                shard_blocks.last().unwrap_or(&mc_block)
+            );
+
+            // Store must produce exactly the same cell.
+            let cell = CellBuilder::build_from(&chain)?;
+            assert_eq!(cell, proof.parse_exotic::<MerkleProof>()?.cell);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn tx_proof_chain() -> Result<()> {
+        check_tx_proof_chain::<TonModels>(Network::Ton)?;
+        check_tx_proof_chain::<TychoModels>(Network::Tycho)?;
+        check_tx_proof_chain::<AnyModels>(Network::Ton)?;
+        check_tx_proof_chain::<AnyModels>(Network::Tycho)
+    }
+
+    fn check_tx_proof_chain<M: BlockchainModels>(network: Network) -> Result<()> {
+        let account = HashBytes([0x11; 32]);
+        let other = HashBytes([0x22; 32]);
+
+        let validators = Validators::new(1, 4);
+        let vset = validators.make_vset(1000, 4);
+
+        let shard_block = BlockBuilder::new(network, ShardIdent::BASECHAIN, 10);
+        let lt = shard_block.start_lt();
+        let shard_block = shard_block
+            .with_transaction(&account, lt + 1)
+            .with_transaction(&account, lt + 2)
+            .with_transaction(&other, lt + 3)
+            .build()?;
+
+        let mc_block = BlockBuilder::new(network, ShardIdent::MASTERCHAIN, 5)
+            .with_global_id(-239)
+            .with_gen_utime(1_700_000_123)
+            .with_shard_block(&shard_block.id)
+            .build()?;
+
+        // Pruned block must contain all transactions.
+        let mut transactions = Vec::new();
+        let pruned = make_pruned_block::<M, _, _>(
+            shard_block.root.clone(),
+            || false,
+            |account, lt, _| {
+                transactions.push((*account, lt));
+                Ok(())
+            },
+        )?;
+        assert_eq!(transactions, [
+            (account, lt + 1),
+            (account, lt + 2),
+            (other, lt + 3)
+        ]);
+
+        let pruned = Cell::virtualize(pruned);
+        let tx_proof =
+            make_tx_proof::<M>(pruned.clone(), &account, lt + 2, false)?.context("tx not found")?;
+        assert!(make_tx_proof::<M>(pruned.clone(), &account, lt + 3, false)?.is_none());
+        assert!(make_tx_proof::<M>(pruned, &HashBytes::ZERO, lt + 1, false)?.is_none());
+
+        // Masterchain block must reference the latest shard block.
+        let mc_pivot = make_pivot_block_proof::<M>(true, mc_block.root.clone())?;
+        let mc = make_mc_proof::<M>(mc_pivot, 0, &account)?;
+        assert_eq!(mc.latest_shard_block_id, shard_block.id);
+
+        // Only 3 of 4 validators sign the block.
+        let signatures = validators.sign(&mc_block.id, None, 1..4);
+        let signatures = prepare_signatures(signatures.into_iter().map(Ok), &vset)?;
+
+        let make_chain = |format| {
+            make_proof_chain_ext::<M>(
+                format,
+                &mc_block.id.file_hash,
+                mc.root.clone(),
+                std::slice::from_ref(&tx_proof),
+                vset.utime_since,
+                signatures.clone(),
+            )
+        };
+        let proof_chain = make_chain(ProofChainFormat::V1)?;
+
+        let address = StdAddr::new(0, account);
+        let tx_hash = *make_tx(&account, lt + 2)?.repr_hash();
+        assert_eq!(
+            verify_proof_chain::<M>(
+                proof_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                &address,
+                lt + 2,
+                None
+            )?,
+            tx_hash
+        );
+
+        let epoch_data = make_epoch_data(&vset)?;
+        assert_eq!(
+            verify_proof_chain::<M>(
+                proof_chain.as_ref(),
+                TrustedVset::EpochData(epoch_data.as_ref()),
+                &address,
+                lt + 2,
+                None
+            )?,
+            tx_hash
+        );
+
+        // Transaction must be in the proof.
+        assert!(
+            verify_proof_chain::<M>(
+                proof_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                &address,
+                lt + 1,
+                None
+            )
+            .is_err()
+        );
+
+        // Signatures must be for the same data.
+        assert!(
+            verify_proof_chain::<M>(
+                proof_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                &address,
+                lt + 2,
+                Some(42)
+            )
+            .is_err()
+        );
+
+        // Versioned proof chain contains the same chain with a header.
+        let proof_chain_v2 = make_chain(ProofChainFormat::V2(ProofKind::Tx))?;
+        let chain_v2 = ProofChainV2::load_from_proof(proof_chain_v2.as_ref())?;
+        assert_eq!(chain_v2.global_id, -239);
+        assert_eq!(chain_v2.mc_seqno, mc_block.id.seqno);
+        assert_eq!(chain_v2.mc_gen_utime, 1_700_000_123);
+        assert_eq!(chain_v2.kind, ProofKind::Tx);
+        assert_eq!(
+            analyze_proof_chain(proof_chain_v2.as_ref())?.shard_hop_count,
+            1
+        );
+
+        assert_eq!(
+            verify_proof_chain_v2::<M>(
+                proof_chain_v2.as_ref(),
+                TrustedVset::Full(&vset),
+                &address,
+                lt + 2,
+                None
+            )?,
+            tx_hash
+        );
+
+        // Versions must not be mixed.
+        assert!(
+            verify_proof_chain::<M>(
+                proof_chain_v2.as_ref(),
+                TrustedVset::Full(&vset),
+                &address,
+                lt + 2,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            verify_proof_chain_v2::<M>(
+                proof_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                &address,
+                lt + 2,
+                None
+            )
+            .is_err()
+        );
+
+        // Only transaction proofs are accepted.
+        let state_chain = make_chain(ProofChainFormat::V2(ProofKind::State))?;
+        assert!(
+            verify_proof_chain_v2::<M>(
+                state_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                &address,
+                lt + 2,
+                None
+            )
+            .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proof_chain_stats() -> Result<()> {
+        let account = HashBytes([0x11; 32]);
+
+        let validators = Validators::new(1, 4);
+        let vset = validators.make_vset(1000, 4);
+
+        let shard_block = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10);
+        let lt = shard_block.start_lt();
+        let shard_block = shard_block.with_transaction(&account, lt + 1).build()?;
+        let mc_block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+            .with_shard_block(&shard_block.id)
+            .build()?;
+
+        let pruned = Cell::virtualize(make_pruned_block::<TychoModels, _, _>(
+            shard_block.root.clone(),
+            || false,
+            |_, _, _| Ok(()),
+        )?);
+        let tx_proof = make_tx_proof::<TychoModels>(pruned, &account, lt + 1, false)?
+            .context("tx not found")?;
+
+        let mc_pivot = make_pivot_block_proof::<TychoModels>(true, mc_block.root.clone())?;
+        let mc = make_mc_proof::<TychoModels>(mc_pivot, 0, &account)?;
+
+        let signatures = validators.sign(&mc_block.id, None, 0..4);
+        let signatures = prepare_signatures(signatures.into_iter().map(Ok), &vset)?;
+
+        let make_chain = |format| {
+            make_proof_chain_ext::<TychoModels>(
+                format,
+                &mc_block.id.file_hash,
+                mc.root.clone(),
+                std::slice::from_ref(&tx_proof),
+                vset.utime_since,
+                signatures.clone(),
+            )
+        };
+        let proof_chain = make_chain(ProofChainFormat::V1)?;
+
+        let stats = analyze_proof_chain(proof_chain.as_ref())?;
+        assert_eq!(stats.shard_hop_count, 1);
+        assert_eq!(stats.max_depth, proof_chain.repr_depth());
+        assert!(stats.bit_count > 0);
+        assert!(stats.pruned_branch_count > 0);
+        assert!(stats.cell_count > stats.pruned_branch_count);
+
+        // Versioned proof chain only adds a header.
+        let proof_chain_v2 = make_chain(ProofChainFormat::V2(ProofKind::Tx))?;
+        let stats_v2 = analyze_proof_chain(proof_chain_v2.as_ref())?;
+        assert_eq!(stats_v2.shard_hop_count, stats.shard_hop_count);
+        assert_eq!(stats_v2.pruned_branch_count, stats.pruned_branch_count);
+        assert!(stats_v2.cell_count >= stats.cell_count);
+        assert!(stats_v2.bit_count > stats.bit_count);
+
+        // Only merkle proofs of proof chains can be analyzed.
+        assert!(analyze_proof_chain(Cell::empty_cell_ref()).is_err());
+        assert!(analyze_proof_chain(shard_block.root.as_ref()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn signatures_weight() -> Result<()> {
+        let validators = Validators::new(1, 4);
+        let vset = validators.make_vset(1000, 4);
+
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5).build()?;
+        let check = |indices: &[usize], signature_id| {
+            let signatures = validators.sign(&block.id, signature_id, indices.iter().copied());
+            check_signatures(&block.id, signatures.into_iter().map(Ok), &vset, Some(42))
+        };
+
+        // Weights are 10, 20, 30 and 40.
+        assert!(check(&[1, 2, 3], Some(42)).is_ok());
+        assert!(check(&[0, 1, 3], Some(42)).is_ok());
+        assert!(check(&[0, 2, 3], Some(42)).is_ok());
+        assert!(check(&[0, 1, 2], Some(42)).is_err());
+        assert!(check(&[0, 3], Some(42)).is_err());
+
+        // Signature id must be the same.
+        assert!(check(&[1, 2, 3], None).is_err());
+        assert!(check(&[1, 2, 3], Some(43)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn minimal_quorum_signatures() -> Result<()> {
+        let account = HashBytes([0x11; 32]);
+
+        let validators = Validators::new(2, 5);
+        let mut vset = validators.make_vset(1000, 4);
+        vset.list[4].weight = 5;
+        vset.total_weight = 105;
+
+        let mc_block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5);
+        let lt = mc_block.start_lt();
+        let mc_block = mc_block.with_transaction(&account, lt + 1).build()?;
+
+        let prepare_dict = |indices: &[usize], mode| {
+            let signatures = validators.sign(&mc_block.id, None, indices.iter().copied());
+            prepare_signatures_ext(signatures.into_iter().map(Ok), &vset, mode)
+        };
+        let prepare = |indices: &[usize], mode| {
+            find_signatures_outside_main_set(&prepare_dict(indices, mode)?, u16::MAX)
+        };
+
+        // Main weights are 10, 20, 30 and 40 (cutoff is 67), the last validator
+        // is outside the main set with weight 5 (2/3 of the total weight is 70).
+        assert_eq!(prepare(&[0, 1, 2, 3, 4], SignaturesMode::All)?, [
+            0, 1, 2, 3, 4
+        ]);
+        assert_eq!(prepare(&[0, 1, 2, 3, 4], SignaturesMode::MinimalQuorum)?, [
+            1, 2, 3
+        ]);
+        assert_eq!(prepare(&[0, 2, 3, 4], SignaturesMode::MinimalQuorum)?, [
+            0, 2, 3
+        ]);
+
+        // All signatures are used when there is no quorum.
+        assert_eq!(prepare(&[0, 1, 3, 4], SignaturesMode::MinimalQuorum)?, [
+            0, 1, 3, 4
+        ]);
+        assert_eq!(prepare(&[0, 1, 2, 4], SignaturesMode::MinimalQuorum)?, [
+            0, 1, 2, 4
+        ]);
+
+        // Selected signatures must still pass the epoch data cutoff.
+        let signatures = prepare_dict(&[0, 1, 2, 3, 4], SignaturesMode::MinimalQuorum)?;
+        let weight = Dict::<u16, PlainSignature>::from_raw(Some(signatures.clone()))
+            .keys()
+            .map(|idx| Ok(vset.list[idx? as usize].weight as u128))
+            .sum::<Result<u128, Error>>()?;
+        assert!(weight >= compute_cutoff_weight(&vset)?);
+
+        // And the full validator set check.
+        let pruned = Cell::virtualize(make_pruned_block::<TychoModels, _, _>(
+            mc_block.root.clone(),
+            || false,
+            |_, _, _| Ok(()),
+        )?);
+        let tx_proof = make_tx_proof::<TychoModels>(pruned, &account, lt + 1, true)?
+            .context("tx not found")?;
+        let verify = |signatures: Cell| {
+            let proof_chain = make_proof_chain(
+                &mc_block.id.file_hash,
+                tx_proof.clone(),
+                &[],
+                vset.utime_since,
+                signatures,
+            )?;
+            verify_proof_chain::<TychoModels>(
+                proof_chain.as_ref(),
+                TrustedVset::Full(&vset),
+                &StdAddr::new(-1, account),
+                lt + 1,
+                None,
+            )
+        };
+        assert!(verify(signatures).is_ok());
+
+        // NOTE: Two heaviest signatures reach only the main validators cutoff.
+        let signatures = prepare_dict(&[2, 3], SignaturesMode::All)?;
+        assert!(verify(signatures).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn block_signatures_from_list() -> Result<()> {
+        let validators = Validators::new(2, 5);
+        let vset = validators.make_vset(1000, 4);
+
+        let block = BlockBuilder::new(Network::Ton, ShardIdent::MASTERCHAIN, 5).build()?;
+        let validator_info = ValidatorBaseInfo {
+            validator_list_hash_short: 123,
+            catchain_seqno: 456,
+        };
+
+        // Roundtrip through the cell.
+        let list = validators.sign(&block.id, None, [4, 2, 3]);
+        let signatures = TonBlockSignatures::from_list(validator_info, &list, &vset)?;
+        assert_eq!(signatures.signature_count, 3);
+        assert_eq!(signatures.total_weight, 50 + 30 + 40);
+
+        let cell = CellBuilder::build_from(&signatures)?;
+        let parsed = cell.parse::<TonBlockSignatures>()?;
+        assert_eq!(collect_block_signatures(&parsed, &vset)?, list);
+
+        check_signatures(&block.id, parsed.signatures.values(), &vset, None)?;
+
+        // Not enough weight (exactly 2/3).
+        let list = validators.sign(&block.id, None, [0, 1, 2, 3]);
+        assert!(TonBlockSignatures::from_list(validator_info, &list, &vset).is_err());
+
+        // Duplicate signers.
+        let mut list = validators.sign(&block.id, None, [2, 3, 4]);
+        list.push(list[0].clone());
+        assert!(TonBlockSignatures::from_list(validator_info, &list, &vset).is_err());
+
+        // Unknown signer.
+        let other = Validators::new(3, 1);
+        let mut list = validators.sign(&block.id, None, [2, 3, 4]);
+        list.extend(other.sign(&block.id, None, [0]));
+        assert!(TonBlockSignatures::from_list(validator_info, &list, &vset).is_err());
+
+        // Inconsistent total weight.
+        let list = validators.sign(&block.id, None, [2, 3, 4]);
+        let mut signatures = TonBlockSignatures::from_list(validator_info, &list, &vset)?;
+        signatures.total_weight += 1;
+        assert!(collect_block_signatures(&signatures, &vset).is_err());
+
+        // Tycho signatures are built the same way.
+        let list = validators.sign(&block.id, None, [4, 2, 3]);
+        let signatures = TychoBlockSignatures::from_list(
+            validator_info,
+            ConsensusInfo::default(),
+            &list,
+            &vset,
+        )?;
+        assert_eq!(signatures.signature_count, 3);
+        assert_eq!(signatures.total_weight, 50 + 30 + 40);
+
+        let cell = CellBuilder::build_from(&signatures)?;
+        let parsed = cell.parse::<TychoBlockSignatures>()?;
+        assert_eq!(parsed.validator_info, validator_info);
+        assert_eq!(collect_block_signatures(&parsed, &vset)?, list);
+
+        let list = validators.sign(&block.id, None, [0, 1, 2, 3]);
+        assert!(
+            TychoBlockSignatures::from_list(validator_info, ConsensusInfo::default(), &list, &vset)
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn multi_tx_and_out_msg_proofs() -> Result<()> {
+        let account1 = HashBytes([0x11; 32]);
+        let account2 = HashBytes([0x22; 32]);
+        let msg = make_msg(123)?;
+
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10);
+        let lt = block.start_lt();
+        let block = block
+            .with_transaction(&account1, lt + 1)
+            .with_transaction(&account2, lt + 2)
+            .with_transaction(&account2, lt + 3)
+            .with_out_msg(&account2, lt + 3, msg.clone())
+            .build()?;
+
+        let pruned = Cell::virtualize(make_pruned_block::<TychoModels, _, _>(
+            block.root.clone(),
+            || false,
+            |_, _, _| Ok(()),
+        )?);
+
+        // All transactions must be in the same proof.
+        let proof = make_multi_tx_proof::<TychoModels, _>(
+            pruned.clone(),
+            [(&account1, lt + 1), (&account2, lt + 3)],
+            false,
+        )?
+        .context("txs not found")?;
+        let account_blocks = proof
+            .parse::<TychoBlock>()?
+            .load_extra()?
+            .load_account_blocks()?;
+        for (account, lt) in [(account1, lt + 1), (account2, lt + 3)] {
+            let (_, account_block) = account_blocks.get(account)?.context("no account")?;
+            let (transactions, _) = account_block.transactions.into_parts();
+            let (_, tx) = transactions.get(lt)?.context("no tx")?;
+            assert_eq!(tx.hash(0), make_tx(&account, lt)?.repr_hash());
+        }
+
+        // Proof is not built if any of transactions is missing.
+        let proof = make_multi_tx_proof::<TychoModels, _>(
+            pruned,
+            [(&account1, lt + 1), (&account1, lt + 2)],
+            false,
+        )?;
+        assert!(proof.is_none());
+
+        // Outbound message proof contains the message and the transaction.
+        let proof = make_out_msg_proof::<TychoModels>(
+            block.root.clone(),
+            &account2,
+            lt + 3,
+            msg.repr_hash(),
+            true,
+        )?
+        .context("msg not found")?;
+        assert_eq!(proof.hash(0), block.root.repr_hash());
+
+        let block_proof = proof.parse::<TychoBlock>()?;
+        assert_eq!(block_proof.load_info()?.seqno(), 10);
+
+        let out_msgs = block_proof.load_extra()?.load_out_msg_description()?;
+        let (_, out_msg) = out_msgs.get(msg.repr_hash())?.context("no out msg")?;
+        assert!(out_msg.is_external);
+        assert_eq!(out_msg.load_message()?, msg);
+        assert_eq!(
+            out_msg.transaction.hash(0),
+            make_tx(&account2, lt + 3)?.repr_hash()
+        );
+
+        // Message must be emitted by the requested transaction.
+        for (account, lt) in [(account1, lt + 1), (account2, lt + 2)] {
+            assert!(
+                make_out_msg_proof::<TychoModels>(
+                    block.root.clone(),
+                    &account,
+                    lt,
+                    msg.repr_hash(),
+                    false
+                )?
+                .is_none()
+            );
+        }
+
+        let unknown = make_msg(321)?;
+        assert!(
+            make_out_msg_proof::<TychoModels>(
+                block.root,
+                &account2,
+                lt + 3,
+                unknown.repr_hash(),
+                false
+            )?
+            .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_pruning() -> Result<()> {
+        let mut block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 10);
+        let lt = block.start_lt();
+        for i in 0..64u8 {
+            for j in 0..(i as u64 % 4 + 1) {
+                block = block.with_transaction(&HashBytes([i; 32]), lt + i as u64 * 10 + j);
+            }
+        }
+        let block = block.build()?;
+
+        let mut parallel_txs = Vec::new();
+        let parallel = make_pruned_block::<TychoModels, _, _>(
+            block.root.clone(),
+            || false,
+            |account, lt, tx_hash| {
+                parallel_txs.push((*account, lt, *tx_hash));
+                Ok(())
+            },
+        )?;
+
+        let mut sequential_txs = Vec::new();
+        let sequential = make_pruned_block_sequential::<TychoModels, _>(
+            block.root.clone(),
+            |account, lt, tx_hash| {
+                sequential_txs.push((*account, lt, *tx_hash));
+                Ok(())
+            },
+        )?;
+
+        assert_eq!(parallel.repr_hash(), sequential.repr_hash());
+        assert_eq!(parallel_txs, sequential_txs);
+        assert_eq!(parallel_txs.len(), 160);
+
+        // Transaction hashes are kept in the pruned block.
+        let pruned_txs = collect_tx_hashes::<TychoModels>(&Cell::virtualize(parallel))?;
+        assert_eq!(pruned_txs, parallel_txs);
+
+        // Cancellation is checked while visiting account blocks and transactions.
+        let mut cancelled_txs = 0;
+        let res = make_pruned_block::<TychoModels, _, _>(
+            block.root.clone(),
+            || true,
+            |_, _, _| {
+                cancelled_txs += 1;
+                Ok(())
+            },
+        );
+        assert!(matches!(res, Err(Error::Cancelled)));
+        assert_eq!(cancelled_txs, 0);
+
+        Ok(())
+    }
+
+    /// Single-threaded reference version of [`make_pruned_block`].
+    fn make_pruned_block_sequential<M, F>(block_root: Cell, mut on_tx: F) -> Result<Cell, Error>
+    where
+        M: BlockchainModels,
+        for<'a> F: FnMut(&'a HashBytes, u64, &'a HashBytes) -> Result<(), Error>,
+    {
+        let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
+
+        let tracked_root = usage_tree.track(&block_root);
+        let raw_block = tracked_root.parse::<M::Block>()?;
+
+        // Include block extra for account blocks only.
+        let extra = raw_block.load_extra()?;
+
+        if extra.has_custom() {
+            // Include full block info for masterchain blocks.
+            let info = raw_block.load_info_raw()?;
+            info.touch_recursive();
+        }
+
+        let account_blocks = extra.load_account_blocks()?;
+
+        // Visit only items with transaction roots.
+        for item in account_blocks.values() {
+            let (_, account_block) = item?;
+
+            // NOTE: Account block `transactions` dict is a new cell.
+            let (transactions, _) = account_block.transactions.into_parts();
+            let transactions = Dict::<u64, (CurrencyCollection, Cell)>::from_raw(
+                transactions.into_root().map(|cell| usage_tree.track(&cell)),
+            );
+
+            for item in transactions.iter() {
+                let (lt, (_, tx)) = item?;
+
+                // Handle tx.
+                on_tx(&account_block.account, lt, tx.repr_hash())?;
+            }
+        }
+
+        // Build block proof.
+        let pruned_block = MerkleProof::create(block_root.as_ref(), usage_tree)
+            .prune_big_cells(true)
+            .build_raw_ext(Cell::empty_context())?;
+
+        if pruned_block.hash(0) != block_root.hash(0) {
+            return Err(Error::InvalidData);
+        }
+
+        Ok(pruned_block)
+    }
+
+    #[test]
+    fn full_tx_proofs() -> Result<()> {
+        let account = HashBytes([0x11; 32]);
+        let in_msg = make_msg_with_body(1)?;
+        let out_msgs = [make_msg_with_body(2)?, make_msg_with_body(3)?];
+
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10);
+        let lt = block.start_lt() + 1;
+        let tx = make_full_tx(&account, lt, Some(in_msg.clone()), &out_msgs)?;
+        let block = block
+            .with_raw_transaction(&account, lt, tx.clone())
+            .build()?;
+
+        let find_tx = |proof: &Cell| -> Result<Cell> {
+            let block = proof.parse::<TychoBlock>()?;
+            let account_blocks = block.load_extra()?.load_account_blocks()?;
+            let (_, account_block) = account_blocks.get(&account)?.context("no account")?;
+            let (transactions, _) = account_block.transactions.into_parts();
+            let (_, tx) = transactions.get(lt)?.context("no tx")?;
+            Ok(tx)
+        };
+        let is_pruned_body = |msg: &Cell| msg.reference(0).unwrap().descriptor().is_pruned_branch();
+        let make_proof = |content| {
+            make_tx_proof_ext::<TychoModels>(block.root.clone(), &account, lt, false, content)?
+                .context("tx not found")
+        };
+
+        let found = find_transaction::<TychoModels>(&block.root, &account, lt)?;
+        assert_eq!(found.as_ref(), Some(&tx));
+        assert!(find_transaction::<TychoModels>(&block.root, &account, lt + 1)?.is_none());
+
+        let in_msgs = collect_in_msg_hashes::<TychoModels>(&block.root)?;
+        assert_eq!(in_msgs, [(account, lt, *in_msg.repr_hash())]);
+
+        // Only the transaction hash by default.
+        let proof = make_proof(TxContent::default())?;
+        let proof_tx = find_tx(&proof)?;
+        assert!(proof_tx.descriptor().is_pruned_branch());
+        assert_eq!(proof_tx.hash(0), tx.repr_hash());
+
+        // Full transaction without message bodies.
+        let proof = make_proof(TxContent {
+            full_tx: true,
+            ..Default::default()
+        })?;
+        let proof_tx = find_tx(&proof)?;
+        assert!(!proof_tx.descriptor().is_pruned_branch());
+        assert_eq!(proof_tx.hash(0), tx.repr_hash());
+
+        let parsed = proof_tx.parse::<Transaction>()?;
+        assert!(parsed.info.load().is_ok());
+        let proof_in_msg = parsed.in_msg.context("no in msg")?;
+        assert!(is_pruned_body(&proof_in_msg));
+        for msg in parsed.out_msgs.values() {
+            assert!(is_pruned_body(&msg?));
+        }
+
+        // Full transaction with the inbound message body.
+        let proof = make_proof(TxContent {
+            in_msg_body: true,
+            ..Default::default()
+        })?;
+        let parsed = find_tx(&proof)?.parse::<Transaction>()?;
+        let proof_in_msg = parsed.in_msg.context("no in msg")?;
+        assert!(!is_pruned_body(&proof_in_msg));
+        for msg in parsed.out_msgs.values() {
+            assert!(is_pruned_body(&msg?));
+        }
+
+        // Full transaction with outbound message bodies.
+        let proof = make_proof(TxContent {
+            out_msg_bodies: true,
+            ..Default::default()
+        })?;
+        let parsed = find_tx(&proof)?.parse::<Transaction>()?;
+        for msg in parsed.out_msgs.values() {
+            assert!(!is_pruned_body(&msg?));
+        }
+
+        // Pruned block has no transaction content.
+        let pruned =
+            make_pruned_block::<TychoModels, _, _>(block.root.clone(), || false, |_, _, _| Ok(()))?;
+        let pruned = Cell::virtualize(pruned);
+        assert!(
+            make_tx_proof_ext::<TychoModels>(pruned, &account, lt, false, TxContent {
+                full_tx: true,
+                ..Default::default()
+            })
+            .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tx_absence_proofs() -> Result<()> {
+        let account1 = HashBytes([0x11; 32]);
+        let account2 = HashBytes([0x22; 32]);
+        let account3 = HashBytes([0x33; 32]);
+
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10);
+        let lt = block.start_lt();
+        let block = block
+            .with_transaction(&account1, lt + 1)
+            .with_transaction(&account1, lt + 3)
+            .with_transaction(&account3, lt + 2)
+            .build()?;
+
+        let pruned = Cell::virtualize(make_pruned_block::<TychoModels, _, _>(
+            block.root.clone(),
+            || false,
+            |_, _, _| Ok(()),
+        )?);
+
+        let load_account_blocks = |proof: &Cell| {
+            proof
+                .parse::<TychoBlock>()?
+                .load_extra()?
+                .load_account_blocks()
+        };
+
+        // Missing transaction of an existing account.
+        let proof = make_tx_absence_proof::<TychoModels>(pruned.clone(), &account1, lt + 2, true)?
+            .context("tx exists")?;
+        assert_eq!(proof.hash(0), block.root.repr_hash());
+        assert_eq!(proof.parse::<TychoBlock>()?.load_info()?.seqno(), 10);
+
+        let (_, account_block) = load_account_blocks(&proof)?
+            .get(account1)?
+            .context("no account")?;
+        let (transactions, _) = account_block.transactions.into_parts();
+        assert!(transactions.get(lt + 2)?.is_none());
+
+        // NOTE: Keys differ only in the second lowest bit, so the lookup stops
+        // at the leaf of the next transaction and the other leaf stays pruned.
+        assert!(transactions.get(lt + 1).is_err());
+        let (_, tx) = transactions.get(lt + 3)?.context("no tx")?;
+        assert!(tx.descriptor().is_pruned_branch());
+
+        // Missing account.
+        let proof = make_account_absence_proof::<TychoModels>(pruned.clone(), &account2, false)?
+            .context("account exists")?;
+        assert_eq!(proof.hash(0), block.root.repr_hash());
+        assert!(load_account_blocks(&proof)?.get(account2)?.is_none());
+
+        let proof = make_tx_absence_proof::<TychoModels>(pruned.clone(), &account2, lt, false)?
+            .context("tx exists")?;
+        assert!(load_account_blocks(&proof)?.get(account2)?.is_none());
+
+        // Existing items can't be proven as missing.
+        assert!(
+            make_tx_absence_proof::<TychoModels>(pruned.clone(), &account1, lt + 1, false)?
+                .is_none()
+        );
+        assert!(make_account_absence_proof::<TychoModels>(pruned, &account3, false)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn mc_proof_for_split_shards() -> Result<()> {
+        let (left, right) = ShardIdent::BASECHAIN.split().unwrap();
+
+        let left_block = BlockBuilder::new(Network::Tycho, left, 10).build()?;
+        let right_block = BlockBuilder::new(Network::Tycho, right, 11).build()?;
+        let mc_block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+            .with_shard_block(&left_block.id)
+            .with_shard_block(&right_block.id)
+            .build()?;
+
+        let mc_pivot = make_pivot_block_proof::<TychoModels>(true, mc_block.root.clone())?;
+        assert_eq!(mc_pivot.hash(0), mc_block.root.repr_hash());
+
+        let mc = make_mc_proof::<TychoModels>(mc_pivot.clone(), 0, &HashBytes([0x00; 32]))?;
+        assert_eq!(mc.latest_shard_block_id, left_block.id);
+
+        let mc = make_mc_proof::<TychoModels>(mc_pivot.clone(), 0, &HashBytes([0xff; 32]))?;
+        assert_eq!(mc.latest_shard_block_id, right_block.id);
+
+        // There are no shards in other workchains.
+        assert!(make_mc_proof::<TychoModels>(mc_pivot, 1, &HashBytes::ZERO).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tycho_state_update() -> Result<()> {
+        let state = make_shard_state(Network::Tycho, ShardIdent::BASECHAIN, 10, &[])?;
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10)
+            .with_state(state.clone())
+            .build()?;
+
+        // Tycho blocks store the merkle update with the queue diff info.
+        let raw_block = block.root.parse::<TychoBlock>()?;
+        assert!(!raw_block.state_update.is_exotic());
+
+        let state_update = raw_block
+            .load_state_update_raw()?
+            .parse_exotic::<MerkleUpdate>()?;
+        assert_eq!(state_update.new_hash, *state.repr_hash());
+
+        Ok(())
+    }
+
+    #[test]
+    fn account_state_proof() -> Result<()> {
+        check_account_state_proof::<TonModels>(Network::Ton)?;
+        check_account_state_proof::<TychoModels>(Network::Tycho)?;
+        check_account_state_proof::<AnyModels>(Network::Tycho)
+    }
+
+    fn check_account_state_proof<M: BlockchainModels>(network: Network) -> Result<()> {
+        let account = HashBytes([0x11; 32]);
+        let missing = HashBytes([0x22; 32]);
+
+        let state = make_shard_state(network, ShardIdent::BASECHAIN, 10, &[(account, 123)])?;
+        let block = BlockBuilder::new(network, ShardIdent::BASECHAIN, 10)
+            .with_state(state.clone())
+            .build()?;
+
+        for (address, last_trans_lt) in [(account, Some(123)), (missing, None)] {
+            let proof =
+                make_account_state_proof::<M>(block.root.clone(), state.clone(), &address, true)?;
+            assert_eq!(proof.block.hash(0), block.root.repr_hash());
+
+            let state_proof = proof.state.parse_exotic::<MerkleProof>()?;
+            assert_eq!(state_proof.hash, *state.repr_hash());
+
+            let accounts = state_proof.cell.parse::<M::ShardState>()?.load_accounts()?;
+            let found = accounts.get(address)?.map(|(_, item)| item.last_trans_lt);
+            assert_eq!(found, last_trans_lt);
+        }
+
+        // State can be taken from the block merkle update with changed accounts only.
+        let other = HashBytes([0x33; 32]);
+        let state = make_shard_state(network, ShardIdent::BASECHAIN, 11, &[
+            (account, 123),
+            (other, 456),
+        ])?;
+        let full_block = BlockBuilder::new(network, ShardIdent::BASECHAIN, 11)
+            .with_state(state.clone())
+            .build()?;
+        let partial_state = make_account_state_proof::<M>(full_block.root, state, &account, false)?
+            .state
+            .parse_exotic::<MerkleProof>()?
+            .cell;
+        let block = BlockBuilder::new(network, ShardIdent::BASECHAIN, 11)
+            .with_state(partial_state.clone())
+            .build()?;
+
+        let proof = make_account_state_proof_from_update::<M>(block.root.clone(), &account, false)?;
+        let state_proof = proof.state.parse_exotic::<MerkleProof>()?;
+        assert_eq!(state_proof.hash, *partial_state.hash(0));
+        let accounts = state_proof.cell.parse::<M::ShardState>()?.load_accounts()?;
+        let found = accounts.get(account)?.map(|(_, item)| item.last_trans_lt);
+        assert_eq!(found, Some(123));
+
+        assert!(make_account_state_proof_from_update::<M>(block.root, &other, false).is_err());
+
+        // State must be for the same block.
+        let other_state = make_shard_state(network, ShardIdent::BASECHAIN, 10, &[])?;
+        assert!(make_account_state_proof::<M>(block.root, other_state, &account, false).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn key_block_chain() -> Result<()> {
+        let validators1 = Validators::new(1, 4);
+        let vset1 = validators1.make_vset(1000, 4);
+        let validators2 = Validators::new(2, 4);
+        let vset2 = validators2.make_vset(2000, 4);
+        let vset3 = Validators::new(3, 4).make_vset(3000, 4);
+
+        let zerostate_id = BlockId {
+            shard: ShardIdent::MASTERCHAIN,
+            seqno: 0,
+            root_hash: HashBytes::ZERO,
+            file_hash: HashBytes::ZERO,
+        };
+        let make_key_block = |seqno, prev_key_block_seqno, vset: &ValidatorSet| {
+            BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, seqno)
+                .with_global_id(42)
+                .with_prev_key_block_seqno(prev_key_block_seqno)
+                .with_key_block_config(vset.clone(), None)
+                .with_signature_id_capability()
+                .build()
+        };
+        let make_proof = |block: &BuiltBlock| {
+            make_key_block_proof::<TychoModels>(block.root.clone(), false)
+                .map(|proof| (block.id, proof))
+        };
+
+        let mut verifier =
+            KeyBlockChainVerifier::<TychoModels>::with_key_block(zerostate_id, vset1.clone(), None);
+
+        // First key block is signed by the initial vset without signature id.
+        let (id10, proof10) = make_proof(&make_key_block(10, 0, &vset2)?)?;
+        let config = proof10
+            .parse::<TychoBlock>()?
+            .load_extra()?
+            .load_custom()?
+            .context("no custom")?
+            .config()
+            .cloned()
+            .context("no config")?;
+        assert_eq!(signature_id_from_config(42, &config.params)?, Some(42));
+
+        let signatures = validators1.sign_all(&id10, None);
+        verifier.accept(&id10, &proof10, signatures.iter().cloned().map(Ok))?;
+        verifier.set_signature_id(Some(42));
+        assert_eq!(verifier.current_vset().utime_since, vset2.utime_since);
+        assert_eq!(verifier.last_key_block_id(), Some(&id10));
+
+        // Same key block is accepted again.
+        verifier.accept(&id10, &proof10, signatures.into_iter().map(Ok))?;
+
+        // Next key block must be signed by the new vset with signature id.
+        let (id20, proof20) = make_proof(&make_key_block(20, 10, &vset3)?)?;
+        for signatures in [
+            validators1.sign_all(&id20, Some(42)),
+            validators2.sign_all(&id20, None),
+            validators2.sign(&id20, Some(42), 0..2),
+        ] {
+            let res = verifier.accept(&id20, &proof20, signatures.into_iter().map(Ok));
+            assert!(res.is_err());
+        }
+        let signatures = validators2.sign_all(&id20, Some(42));
+        verifier.accept(&id20, &proof20, signatures.into_iter().map(Ok))?;
+        assert_eq!(verifier.current_vset().utime_since, vset3.utime_since);
+
+        // Older key blocks are rejected.
+        let signatures = validators1.sign_all(&id10, None);
+        assert!(
+            verifier
+                .accept(&id10, &proof10, signatures.into_iter().map(Ok))
+                .is_err()
+        );
+
+        // Key blocks must be accepted without gaps.
+        let (id30, proof30) = make_proof(&make_key_block(30, 25, &vset3)?)?;
+        let signatures = Validators::new(3, 4).sign_all(&id30, Some(42));
+        assert!(
+            verifier
+                .accept(&id30, &proof30, signatures.into_iter().map(Ok))
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn key_block_proof_with_prev_vset() -> Result<()> {
+        let vset1 = Validators::new(1, 4).make_vset(1000, 4);
+        let vset2 = Validators::new(2, 4).make_vset(2000, 4);
+
+        let block = BlockBuilder::new(Network::Ton, ShardIdent::MASTERCHAIN, 10)
+            .with_key_block_config(vset2, Some(vset1))
+            .build()?;
+
+        for with_prev_vset in [false, true] {
+            let proof = make_key_block_proof::<TonModels>(block.root.clone(), with_prev_vset)?;
+            assert_eq!(proof.hash(0), block.root.repr_hash());
+
+            let custom = proof
+                .parse::<TonBlock>()?
+                .load_extra()?
+                .load_custom()?
+                .context("no custom")?;
+            let config = custom.config().context("no config")?;
+            assert_eq!(config.get_current_validator_set()?.utime_since, 2000);
+
+            let prev_vset = config.get_raw_cell_ref(32)?.context("no prev vset")?;
+            assert_eq!(
+                prev_vset.cell_type() == CellType::PrunedBranch,
+                !with_prev_vset
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_param_proof() -> Result<()> {
+        let vset1 = Validators::new(1, 4).make_vset(1000, 4);
+        let vset2 = Validators::new(2, 4).make_vset(2000, 4);
+
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 10)
+            .with_key_block_config(vset2, Some(vset1))
+            .build()?;
+
+        let proof =
+            make_config_param_proof::<TychoModels>(block.root.clone(), 32)?.context("no param")?;
+        assert_eq!(proof.hash(0), block.root.repr_hash());
+
+        let parsed = proof.parse::<TychoBlock>()?;
+        assert_eq!(parsed.load_info()?.seqno(), 10);
+
+        let custom = parsed.load_extra()?.load_custom()?.context("no custom")?;
+        let config = custom.config().context("no config")?;
+        let prev_vset = config.get_raw_cell_ref(32)?.context("no param")?;
+        assert_eq!(prev_vset.parse::<ValidatorSet>()?.utime_since, 1000);
+
+        // Other params are pruned.
+        let current_vset = config.get_raw_cell_ref(34)?.context("no param")?;
+        assert_eq!(current_vset.cell_type(), CellType::PrunedBranch);
+
+        // Unknown param.
+        assert!(make_config_param_proof::<TychoModels>(block.root.clone(), 99)?.is_none());
+
+        // Only key blocks have config.
+        let block = BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 11).build()?;
+        assert!(make_config_param_proof::<TychoModels>(block.root, 32).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn pivot_chain_across_split() -> Result<()> {
+        let parent = ShardIdent::BASECHAIN;
+        let (left, right) = parent.split().unwrap();
+
+        let b10 = BlockBuilder::new(Network::Tycho, parent, 10).build()?;
+        let b11 = BlockBuilder::new(Network::Tycho, left, 11)
+            .with_prev_block(&b10.id)
+            .after_split()
+            .build()?;
+        let b12 = BlockBuilder::new(Network::Tycho, left, 12)
+            .with_prev_block(&b11.id)
+            .build()?;
+        let pivots = make_pivots([&b11, &b12])?;
+
+        let account = HashBytes([0x00; 32]);
+        assert!(left.contains_account(&account) && !right.contains_account(&account));
+
+        let target = b10.id.as_short_id();
+        let chain =
+            collect_pivot_chain::<TychoModels, _, Error>(&b12.id, &target, &account, |id| {
+                pivots.get(id).cloned().ok_or(Error::CellUnderflow)
+            })?;
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].hash(0), &b12.id.root_hash);
+        assert_eq!(chain[1].hash(0), &b11.id.root_hash);
+
+        // Child block must reference the parent shard.
+        let info = chain[1].parse::<TychoBlock>()?.load_info()?;
+        let prev = info.load_prev_block_id(&account)?;
+        assert_eq!(prev.as_short_id(), target);
+        assert_eq!(prev.root_hash, b10.id.root_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pivot_chain_across_merge() -> Result<()> {
+        let parent = ShardIdent::BASECHAIN;
+        let (left, right) = parent.split().unwrap();
+
+        let l20 = BlockBuilder::new(Network::Tycho, left, 20).build()?;
+        let r21 = BlockBuilder::new(Network::Tycho, right, 21).build()?;
+        let m22 = BlockBuilder::new(Network::Tycho, parent, 22)
+            .with_prev_block(&l20.id)
+            .with_prev_block(&r21.id)
+            .build()?;
+        let pivots = make_pivots([&m22])?;
+
+        let load_pivot = |id: &BlockIdShort| pivots.get(id).cloned().ok_or(Error::CellUnderflow);
+
+        // Path through the left child.
+        let left_account = HashBytes([0x00; 32]);
+        let left_target = l20.id.as_short_id();
+        let chain = collect_pivot_chain::<TychoModels, _, Error>(
+            &m22.id,
+            &left_target,
+            &left_account,
+            load_pivot,
+        )?;
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].hash(0), &m22.id.root_hash);
+
+        let info = chain[0].parse::<TychoBlock>()?.load_info()?;
+        let prev = info.load_prev_block_id(&left_account)?;
+        assert_eq!(prev.as_short_id(), left_target);
+        assert_eq!(prev.root_hash, l20.id.root_hash);
+
+        // Path through the right child.
+        let right_account = HashBytes([0xff; 32]);
+        let right_target = r21.id.as_short_id();
+        let chain = collect_pivot_chain::<TychoModels, _, Error>(
+            &m22.id,
+            &right_target,
+            &right_account,
+            load_pivot,
+        )?;
+        assert_eq!(chain.len(), 1);
+
+        let prev = info.load_prev_block_id(&right_account)?;
+        assert_eq!(prev.as_short_id(), right_target);
+        assert_eq!(prev.root_hash, r21.id.root_hash);
+
+        // Account from another child can't reach the target block.
+        let res = collect_pivot_chain::<TychoModels, _, Error>(
+            &m22.id,
+            &left_target,
+            &right_account,
+            load_pivot,
+        );
+        assert!(res.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn shard_chain_links() -> Result<()> {
+        let account = StdAddr::new(0, HashBytes([0x11; 32]));
+        let make_mc_block = |shard_block_id: &BlockId| {
+            BlockBuilder::new(Network::Tycho, ShardIdent::MASTERCHAIN, 5)
+                .with_shard_block(shard_block_id)
+                .build()
+                .map(|block| block.root)
+        };
+
+        let b10 = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 10).build()?;
+        let b11 = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 11)
+            .with_prev_block(&b10.id)
+            .build()?;
+
+        let mc_block = make_mc_block(&b11.id)?;
+        verify_shard_chain::<TychoModels>(&mc_block, &[b11.root.clone()], &account)?;
+        verify_shard_chain::<TychoModels>(
+            &mc_block,
+            &[b11.root.clone(), b10.root.clone()],
+            &account,
+        )?;
+
+        // Chain must start from the block referenced by the masterchain block.
+        assert!(verify_shard_chain::<TychoModels>(&mc_block, &[], &account).is_err());
+        assert!(
+            verify_shard_chain::<TychoModels>(&mc_block, &[b10.root.clone()], &account).is_err()
+        );
+
+        // Single block must match the shard description.
+        let mc_block = make_mc_block(&BlockId {
+            seqno: 12,
+            ..b11.id
+        })?;
+        assert!(
+            verify_shard_chain::<TychoModels>(&mc_block, &[b11.root.clone()], &account).is_err()
+        );
+
+        // The last block must match the reference from the previous one.
+        let b12 = BlockBuilder::new(Network::Tycho, ShardIdent::BASECHAIN, 12)
+            .with_prev_block(&BlockId { seqno: 9, ..b11.id })
+            .build()?;
+        let mc_block = make_mc_block(&b12.id)?;
+        verify_shard_chain::<TychoModels>(&mc_block, &[b12.root.clone()], &account)?;
+        assert!(
+            verify_shard_chain::<TychoModels>(
+                &mc_block,
+                &[b12.root.clone(), b11.root.clone()],
+                &account
+            )
+            .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn any_models_dispatch() -> Result<()> {
+        let shard = ShardIdent::BASECHAIN;
+        let block = BlockBuilder::new(Network::Tycho, shard, 10).build()?.root;
+
+        let any_block = block.parse::<any::AnyBlock>()?;
+        assert!(matches!(any_block, any::AnyBlock::Tycho(_)));
+
+        let info = any_block.load_info()?;
+        assert!(matches!(info, any::AnyBlockInfo::Tycho(_)));
+        assert_eq!(info.seqno(), 10);
+        assert_eq!(info.shard(), &shard);
+
+        // Pivot proofs are built by the same entry point.
+        let pivot = make_pivot_block_proof::<AnyModels>(false, block.clone())?;
+        assert_eq!(pivot.hash(0), block.repr_hash());
+
+        let block = BlockBuilder::new(Network::Ton, shard, 10).build()?.root;
+        let any_block = block.parse::<any::AnyBlock>()?;
+        assert!(matches!(any_block, any::AnyBlock::Ton(_)));
+        assert!(matches!(any_block.load_info()?, any::AnyBlockInfo::Ton(_)));
+
+        // Unknown block tag.
+        let invalid = CellBuilder::build_from(0xdeadbeefu32)?;
+        assert!(invalid.parse::<any::AnyBlock>().is_err());
+
+        // Shard state tags are shared between networks.
+        let accounts = CellBuilder::build_from(0xaau8)?;
+        for tag in [0x9023afe2u32, 0x9023aeee] {
+            let mut b = CellBuilder::new();
+            b.store_u32(tag)?;
+            b.store_reference(Cell::empty_cell())?;
+            b.store_reference(accounts.clone())?;
+            let state = b.build()?.parse::<ShardState>()?;
+            assert_eq!(state.accounts, accounts);
+        }
+        assert!(invalid.parse::<ShardState>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn epoch_data_signatures_outside_main_set() -> Result<()> {
+        let validators = Dict::<u16, (HashBytes, u64)>::try_from_sorted_slice(&[
+            (0, (HashBytes([1; 32]), 10)),
+            (1, (HashBytes([2; 32]), 20)),
+        ])?;
+        let epoch_data = EpochData {
+            utime_since: 100,
+            utime_until: 200,
+            main_validator_count: 2,
+            cutoff_weight: Tokens::new(21),
+            validators,
+        };
+        let cell = CellBuilder::build_from(&epoch_data)?;
+        assert_eq!(cell.parse::<EpochData>()?, epoch_data);
+
+        let signatures = Dict::<u16, PlainSignature>::try_from_sorted_slice(&[
+            (1, PlainSignature([0; 64])),
+            (2, PlainSignature([0; 64])),
+            (5, PlainSignature([0; 64])),
+        ])?
+        .into_root()
+        .unwrap();
+        let outside =
+            find_signatures_outside_main_set(&signatures, epoch_data.main_validator_count)?;
+        assert_eq!(outside, [2, 5]);
+
+        // Signatures are checked only for the same vset.
+        let block_id = BlockId::default();
+        assert!(
+            check_signatures_with_epoch_data(&block_id, &signatures, 101, &epoch_data, None)
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    fn make_pivots<'a, I>(blocks: I) -> Result<HashMap<BlockIdShort, Cell>>
+    where
+        I: IntoIterator<Item = &'a BuiltBlock>,
+    {
+        let mut pivots = HashMap::default();
+        for block in blocks {
+            let pivot = make_pivot_block_proof::<TychoModels>(false, block.root.clone())?;
+            pivots.insert(block.id.as_short_id(), pivot);
+        }
+        Ok(pivots)
+    }
+
     #[test]
     #[ignore]
     fn prune_medium_block() -> Result<()> {
@@ -615,7 +3349,8 @@ mod tests {
 
         // Remove everything except transaction hashes.
         println!("building pruned block");
-        let pruned_block = make_pruned_block::<TychoModels, _>(block_root, |_, _| Ok(()))?;
+        let pruned_block =
+            make_pruned_block::<TychoModels, _, _>(block_root, || false, |_, _, _| Ok(()))?;
 
         // Build a pruned block which contains a single branch to transaction.
         println!("building tx proof");
@@ -1,13 +1,13 @@
 use tycho_types::error::Error;
 use tycho_types::models::{
-    BlockIdShort, BlockSignature, BlockchainConfig, GlobalVersion, ShardHashes, ShardIdent,
-    ValidatorBaseInfo,
+    BlockSignature, GlobalVersion, ShardIdent, ValidatorBaseInfo, ValidatorSet,
 };
 use tycho_types::prelude::*;
 
 use crate::block::{
     AccountBlocksShort, BlockchainBlock, BlockchainBlockExtra, BlockchainBlockInfo,
-    BlockchainBlockMcExtra, BlockchainBlockSignatures, BlockchainModels, find_shard_descr,
+    BlockchainBlockSignatures, BlockchainModels, McExtra, OutMsgDescrShort, ShardState,
+    make_block_signatures_dict,
 };
 
 pub struct TonModels;
@@ -15,6 +15,7 @@ pub struct TonModels;
 impl BlockchainModels for TonModels {
     type Block = TonBlock;
     type BlockSignatures = TonBlockSignatures;
+    type ShardState = ShardState;
 }
 
 #[derive(Load)]
@@ -31,6 +32,10 @@ impl BlockchainBlock for TonBlock {
     type Info = TonBlockInfo;
     type Extra = TonBlockExtra;
 
+    fn global_id(&self) -> i32 {
+        self.global_id
+    }
+
     fn load_info(&self) -> Result<Self::Info, Error> {
         self.info.parse::<Self::Info>()
     }
@@ -39,6 +44,10 @@ impl BlockchainBlock for TonBlock {
         Ok(self.info.clone())
     }
 
+    fn load_state_update_raw(&self) -> Result<Cell, Error> {
+        Ok(self.state_update.clone())
+    }
+
     fn load_extra(&self) -> Result<Self::Extra, Error> {
         self.extra.parse::<Self::Extra>()
     }
@@ -46,6 +55,9 @@ impl BlockchainBlock for TonBlock {
 
 pub struct TonBlockInfo {
     pub is_key_block: bool,
+    pub after_merge: bool,
+    pub after_split: bool,
+    pub seqno: u32,
     pub shard: ShardIdent,
     pub gen_utime: u32,
     pub start_lt: u64,
@@ -74,6 +86,8 @@ impl<'a> Load<'a> for TonBlockInfo {
             return Err(Error::InvalidData);
         }
 
+        let after_merge = packed_flags & 0b01000000 != 0;
+        let after_split = packed_flags & 0b00010000 != 0;
         let is_key_block = packed_flags & 0b00000010 != 0;
 
         let vert_seqno = slice.load_u32()?;
@@ -112,6 +126,9 @@ impl<'a> Load<'a> for TonBlockInfo {
 
         Ok(Self {
             is_key_block,
+            after_merge,
+            after_split,
+            seqno,
             shard,
             gen_utime,
             start_lt,
@@ -129,13 +146,41 @@ impl BlockchainBlockInfo for TonBlockInfo {
         self.is_key_block
     }
 
+    fn seqno(&self) -> u32 {
+        self.seqno
+    }
+
+    fn gen_utime(&self) -> u32 {
+        self.gen_utime
+    }
+
+    fn shard(&self) -> &ShardIdent {
+        &self.shard
+    }
+
+    fn after_merge(&self) -> bool {
+        self.after_merge
+    }
+
+    fn after_split(&self) -> bool {
+        self.after_split
+    }
+
     fn end_lt(&self) -> u64 {
         self.end_lt
     }
 
+    fn prev_key_block_seqno(&self) -> u32 {
+        self.prev_key_block_seqno
+    }
+
     fn prev_ref(&self) -> &Cell {
         &self.prev_ref
     }
+
+    fn prev_vert_ref(&self) -> Option<&Cell> {
+        self.prev_vert_ref.as_ref()
+    }
 }
 
 #[derive(Load)]
@@ -150,12 +195,16 @@ pub struct TonBlockExtra {
 }
 
 impl BlockchainBlockExtra for TonBlockExtra {
-    type McExtra = TonBlockMcExtra;
+    type McExtra = McExtra;
 
     fn load_account_blocks(&self) -> Result<AccountBlocksShort, Error> {
         self.account_blocks.parse::<AccountBlocksShort>()
     }
 
+    fn load_out_msg_description(&self) -> Result<OutMsgDescrShort, Error> {
+        self.out_msg_description.parse::<OutMsgDescrShort>()
+    }
+
     fn has_custom(&self) -> bool {
         self.custom.is_some()
     }
@@ -168,77 +217,7 @@ impl BlockchainBlockExtra for TonBlockExtra {
     }
 }
 
-pub struct TonBlockMcExtra {
-    shard_hashes: ShardHashes,
-    config: Option<BlockchainConfig>,
-}
-
-impl TonBlockMcExtra {
-    const TAG: u16 = 0xcca5;
-}
-
-impl<'a> Load<'a> for TonBlockMcExtra {
-    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
-        if slice.load_u16()? != Self::TAG {
-            return Err(Error::InvalidTag);
-        }
-
-        let with_config = slice.load_bit()?;
-        let shard_hashes = ShardHashes::load_from(slice)?;
-
-        let config = if with_config {
-            slice.only_last(256, 1)?;
-            Some(BlockchainConfig::load_from(slice)?)
-        } else {
-            None
-        };
-
-        Ok(Self {
-            shard_hashes,
-            config,
-        })
-    }
-}
-
-impl BlockchainBlockMcExtra for TonBlockMcExtra {
-    fn load_top_shard_block_ids(&self) -> Result<Vec<BlockIdShort>, Error> {
-        let mut shard_ids = Vec::new();
-        for entry in self.shard_hashes.latest_blocks() {
-            let block_id = entry?;
-            shard_ids.push(block_id.as_short_id());
-        }
-
-        Ok(shard_ids)
-    }
-
-    fn find_shard_seqno(&self, shard_ident: ShardIdent) -> Result<u32, Error> {
-        let shard_hashes = self
-            .shard_hashes
-            .get_workchain_shards(shard_ident.workchain())?
-            .ok_or(Error::CellUnderflow)?;
-
-        let mut descr_root = find_shard_descr(shard_hashes.root(), shard_ident.prefix())?;
-        let latest_shard_seqno = match descr_root.load_small_uint(4)? {
-            0xa | 0xb => descr_root.load_u32()?,
-            _ => return Err(Error::InvalidTag),
-        };
-
-        Ok(latest_shard_seqno)
-    }
-
-    fn visit_all_shard_hashes(&self) -> Result<(), Error> {
-        for item in self.shard_hashes.raw_iter() {
-            item?;
-        }
-        Ok(())
-    }
-
-    fn config(&self) -> Option<&BlockchainConfig> {
-        self.config.as_ref()
-    }
-}
-
-#[derive(Load)]
+#[derive(Load, Store)]
 #[tlb(tag = "#11")]
 pub struct TonBlockSignatures {
     pub validator_info: ValidatorBaseInfo,
@@ -247,6 +226,25 @@ pub struct TonBlockSignatures {
     pub signatures: Dict<u16, BlockSignature>,
 }
 
+impl TonBlockSignatures {
+    /// Builds block signatures from a plain list.
+    ///
+    /// Fails if some signer is not in the `vset` or their weight is not enough.
+    pub fn from_list(
+        validator_info: ValidatorBaseInfo,
+        signatures: &[BlockSignature],
+        vset: &ValidatorSet,
+    ) -> Result<Self, Error> {
+        let (dict, total_weight) = make_block_signatures_dict(signatures, vset)?;
+        Ok(Self {
+            validator_info,
+            signature_count: signatures.len() as u32,
+            total_weight,
+            signatures: dict,
+        })
+    }
+}
+
 impl BlockchainBlockSignatures for TonBlockSignatures {
     fn validator_info(&self) -> ValidatorBaseInfo {
         self.validator_info
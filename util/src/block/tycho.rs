@@ -1,13 +1,13 @@
 use tycho_types::error::Error;
 use tycho_types::models::{
-    BlockIdShort, BlockSignature, BlockchainConfig, ConsensusInfo, GlobalVersion, ShardHashes,
-    ShardIdent, ValidatorBaseInfo,
+    BlockSignature, ConsensusInfo, GlobalVersion, ShardIdent, ValidatorBaseInfo, ValidatorSet,
 };
 use tycho_types::prelude::*;
 
 use crate::block::{
     AccountBlocksShort, BlockchainBlock, BlockchainBlockExtra, BlockchainBlockInfo,
-    BlockchainBlockMcExtra, BlockchainBlockSignatures, BlockchainModels, find_shard_descr,
+    BlockchainBlockSignatures, BlockchainModels, McExtra, OutMsgDescrShort, ShardState,
+    make_block_signatures_dict,
 };
 
 pub struct TychoModels;
@@ -15,6 +15,7 @@ pub struct TychoModels;
 impl BlockchainModels for TychoModels {
     type Block = TychoBlock;
     type BlockSignatures = TychoBlockSignatures;
+    type ShardState = ShardState;
 }
 
 #[derive(Load)]
@@ -31,6 +32,10 @@ impl BlockchainBlock for TychoBlock {
     type Info = TychoBlockInfo;
     type Extra = TychoBlockExtra;
 
+    fn global_id(&self) -> i32 {
+        self.global_id
+    }
+
     fn load_info(&self) -> Result<Self::Info, Error> {
         self.info.parse::<Self::Info>()
     }
@@ -39,6 +44,15 @@ impl BlockchainBlock for TychoBlock {
         Ok(self.info.clone())
     }
 
+    fn load_state_update_raw(&self) -> Result<Cell, Error> {
+        // NOTE: Merkle update is wrapped into a cell with `out_msg_queue_updates`.
+        if self.state_update.is_exotic() {
+            Ok(self.state_update.clone())
+        } else {
+            self.state_update.as_slice()?.load_reference_cloned()
+        }
+    }
+
     fn load_extra(&self) -> Result<Self::Extra, Error> {
         self.extra.parse::<Self::Extra>()
     }
@@ -46,6 +60,8 @@ impl BlockchainBlock for TychoBlock {
 
 pub struct TychoBlockInfo {
     pub is_key_block: bool,
+    pub after_merge: bool,
+    pub after_split: bool,
     pub seqno: u32,
     pub shard: ShardIdent,
     pub gen_utime: u32,
@@ -75,6 +91,8 @@ impl<'a> Load<'a> for TychoBlockInfo {
             return Err(Error::InvalidData);
         }
 
+        let after_merge = packed_flags & 0b01000000 != 0;
+        let after_split = packed_flags & 0b00010000 != 0;
         let is_key_block = packed_flags & 0b00000010 != 0;
 
         let vert_seqno = slice.load_u32()?;
@@ -115,6 +133,8 @@ impl<'a> Load<'a> for TychoBlockInfo {
 
         Ok(Self {
             is_key_block,
+            after_merge,
+            after_split,
             seqno,
             shard,
             gen_utime,
@@ -133,13 +153,41 @@ impl BlockchainBlockInfo for TychoBlockInfo {
         self.is_key_block
     }
 
+    fn seqno(&self) -> u32 {
+        self.seqno
+    }
+
+    fn gen_utime(&self) -> u32 {
+        self.gen_utime
+    }
+
+    fn shard(&self) -> &ShardIdent {
+        &self.shard
+    }
+
+    fn after_merge(&self) -> bool {
+        self.after_merge
+    }
+
+    fn after_split(&self) -> bool {
+        self.after_split
+    }
+
     fn end_lt(&self) -> u64 {
         self.end_lt
     }
 
+    fn prev_key_block_seqno(&self) -> u32 {
+        self.prev_key_block_seqno
+    }
+
     fn prev_ref(&self) -> &Cell {
         &self.prev_ref
     }
+
+    fn prev_vert_ref(&self) -> Option<&Cell> {
+        self.prev_vert_ref.as_ref()
+    }
 }
 
 #[derive(Load)]
@@ -154,12 +202,16 @@ pub struct TychoBlockExtra {
 }
 
 impl BlockchainBlockExtra for TychoBlockExtra {
-    type McExtra = TychoBlockMcExtra;
+    type McExtra = McExtra;
 
     fn load_account_blocks(&self) -> Result<AccountBlocksShort, Error> {
         self.account_blocks.parse::<AccountBlocksShort>()
     }
 
+    fn load_out_msg_description(&self) -> Result<OutMsgDescrShort, Error> {
+        self.out_msg_description.parse::<OutMsgDescrShort>()
+    }
+
     fn has_custom(&self) -> bool {
         self.custom.is_some()
     }
@@ -172,77 +224,7 @@ impl BlockchainBlockExtra for TychoBlockExtra {
     }
 }
 
-pub struct TychoBlockMcExtra {
-    shard_hashes: ShardHashes,
-    config: Option<BlockchainConfig>,
-}
-
-impl TychoBlockMcExtra {
-    const TAG: u16 = 0xcca5;
-}
-
-impl<'a> Load<'a> for TychoBlockMcExtra {
-    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
-        if slice.load_u16()? != Self::TAG {
-            return Err(Error::InvalidTag);
-        }
-
-        let with_config = slice.load_bit()?;
-        let shard_hashes = ShardHashes::load_from(slice)?;
-
-        let config = if with_config {
-            slice.only_last(256, 1)?;
-            Some(BlockchainConfig::load_from(slice)?)
-        } else {
-            None
-        };
-
-        Ok(Self {
-            shard_hashes,
-            config,
-        })
-    }
-}
-
-impl BlockchainBlockMcExtra for TychoBlockMcExtra {
-    fn load_top_shard_block_ids(&self) -> Result<Vec<BlockIdShort>, Error> {
-        let mut shard_ids = Vec::new();
-        for entry in self.shard_hashes.latest_blocks() {
-            let block_id = entry?;
-            shard_ids.push(block_id.as_short_id());
-        }
-
-        Ok(shard_ids)
-    }
-
-    fn find_shard_seqno(&self, shard_ident: ShardIdent) -> Result<u32, Error> {
-        let shard_hashes = self
-            .shard_hashes
-            .get_workchain_shards(shard_ident.workchain())?
-            .ok_or(Error::CellUnderflow)?;
-
-        let mut descr_root = find_shard_descr(shard_hashes.root(), shard_ident.prefix())?;
-        let latest_shard_seqno = match descr_root.load_small_uint(4)? {
-            0xa | 0xb => descr_root.load_u32()?,
-            _ => return Err(Error::InvalidTag),
-        };
-
-        Ok(latest_shard_seqno)
-    }
-
-    fn visit_all_shard_hashes(&self) -> Result<(), Error> {
-        for item in self.shard_hashes.raw_iter() {
-            item?;
-        }
-        Ok(())
-    }
-
-    fn config(&self) -> Option<&BlockchainConfig> {
-        self.config.as_ref()
-    }
-}
-
-#[derive(Load)]
+#[derive(Load, Store)]
 #[tlb(tag = "#12")]
 pub struct TychoBlockSignatures {
     pub validator_info: ValidatorBaseInfo,
@@ -252,6 +234,27 @@ pub struct TychoBlockSignatures {
     pub signatures: Dict<u16, BlockSignature>,
 }
 
+impl TychoBlockSignatures {
+    /// Builds block signatures from a plain list.
+    ///
+    /// Fails if some signer is not in the `vset` or their weight is not enough.
+    pub fn from_list(
+        validator_info: ValidatorBaseInfo,
+        consensus_info: ConsensusInfo,
+        signatures: &[BlockSignature],
+        vset: &ValidatorSet,
+    ) -> Result<Self, Error> {
+        let (dict, total_weight) = make_block_signatures_dict(signatures, vset)?;
+        Ok(Self {
+            validator_info,
+            consensus_info,
+            signature_count: signatures.len() as u32,
+            total_weight,
+            signatures: dict,
+        })
+    }
+}
+
 impl BlockchainBlockSignatures for TychoBlockSignatures {
     fn validator_info(&self) -> ValidatorBaseInfo {
         self.validator_info
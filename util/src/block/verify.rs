@@ -0,0 +1,400 @@
+use std::marker::PhantomData;
+
+use tycho_types::error::Error;
+use tycho_types::models::{BlockId, BlockSignature, ShardIdent, StdAddr, ValidatorSet};
+use tycho_types::prelude::*;
+
+use crate::block::{
+    BlockchainBlock, BlockchainBlockExtra, BlockchainBlockInfo, BlockchainBlockMcExtra,
+    BlockchainModels, EpochData, PlainSignature, ProofChain, ProofChainV2, ProofKind,
+    build_data_for_sign, check_signatures, check_signatures_weight,
+};
+
+/// Validator set which is used as a root of trust for the proof chain.
+#[derive(Clone, Copy)]
+pub enum TrustedVset<'a> {
+    /// Full validator set (e.g. from the config param 34).
+    Full(&'a ValidatorSet),
+    /// Epoch data cell built by [`make_epoch_data`].
+    ///
+    /// [`make_epoch_data`]: crate::block::make_epoch_data
+    EpochData(&'a DynCell),
+}
+
+/// Verifies a proof chain built by [`make_proof_chain`] for the specified transaction.
+///
+/// Returns the hash of the proven transaction.
+///
+/// [`make_proof_chain`]: crate::block::make_proof_chain
+pub fn verify_proof_chain<M>(
+    proof_chain: &DynCell,
+    vset: TrustedVset<'_>,
+    account: &StdAddr,
+    lt: u64,
+    signature_id: Option<i32>,
+) -> Result<HashBytes, Error>
+where
+    M: BlockchainModels,
+{
+    let chain = ProofChain::load_from_proof(proof_chain)?;
+    verify_tx_in_chain::<M>(&chain, vset, account, lt, signature_id)
+}
+
+/// Verifies a proof chain built by [`make_proof_chain_v2`] for the specified transaction.
+///
+/// Header fields must match the masterchain block of the proof chain.
+/// Returns the hash of the proven transaction.
+///
+/// [`make_proof_chain_v2`]: crate::block::make_proof_chain_v2
+pub fn verify_proof_chain_v2<M>(
+    proof_chain: &DynCell,
+    vset: TrustedVset<'_>,
+    account: &StdAddr,
+    lt: u64,
+    signature_id: Option<i32>,
+) -> Result<HashBytes, Error>
+where
+    M: BlockchainModels,
+{
+    let chain = ProofChainV2::load_from_proof(proof_chain)?;
+    if chain.kind != ProofKind::Tx {
+        return Err(Error::InvalidData);
+    }
+
+    let mc_block = chain.chain.mc_block.parse::<M::Block>()?;
+    let info = mc_block.load_info()?;
+    if mc_block.global_id() != chain.global_id
+        || info.seqno() != chain.mc_seqno
+        || info.gen_utime() != chain.mc_gen_utime
+    {
+        return Err(Error::InvalidData);
+    }
+
+    verify_tx_in_chain::<M>(&chain.chain, vset, account, lt, signature_id)
+}
+
+fn verify_tx_in_chain<M>(
+    chain: &ProofChain,
+    vset: TrustedVset<'_>,
+    account: &StdAddr,
+    lt: u64,
+    signature_id: Option<i32>,
+) -> Result<HashBytes, Error>
+where
+    M: BlockchainModels,
+{
+    // Check masterchain block signatures.
+    let mc_block = chain.mc_block.parse::<M::Block>()?;
+    let mc_block_id = BlockId {
+        shard: ShardIdent::MASTERCHAIN,
+        seqno: mc_block.load_info()?.seqno(),
+        root_hash: *chain.mc_block.hash(0),
+        file_hash: chain.mc_file_hash,
+    };
+    check_signatures_dict(
+        &mc_block_id,
+        &chain.signatures,
+        chain.vset_utime_since,
+        vset,
+        signature_id,
+    )?;
+
+    if account.is_masterchain() {
+        // Transaction must be in the masterchain block itself.
+        if !chain.shard_blocks.is_empty() {
+            return Err(Error::InvalidData);
+        }
+        return find_tx_hash::<M>(&chain.mc_block, &account.address, lt);
+    }
+
+    verify_shard_chain::<M>(&chain.mc_block, &chain.shard_blocks, account)?;
+
+    find_tx_hash::<M>(chain.target_block(), &account.address, lt)
+}
+
+/// Checks that shard blocks are linked with the masterchain block
+/// on the path to the specified account.
+///
+/// Shard blocks are ordered from the block which is referenced by the
+/// masterchain block to the oldest one.
+pub fn verify_shard_chain<M>(
+    mc_block: &Cell,
+    shard_blocks: &[Cell],
+    account: &StdAddr,
+) -> Result<(), Error>
+where
+    M: BlockchainModels,
+{
+    let Some(latest_shard_block) = shard_blocks.first() else {
+        return Err(Error::InvalidData);
+    };
+
+    // Check that the latest shard block is referenced by the masterchain block.
+    let (shard, descr) = mc_block
+        .parse::<M::Block>()?
+        .load_extra()?
+        .load_custom()?
+        .ok_or(Error::CellUnderflow)?
+        .find_shard_descr(account.workchain as i32, &account.address)?;
+
+    if latest_shard_block.hash(0) != &descr.root_hash {
+        return Err(Error::InvalidData);
+    }
+
+    // Check links between shard blocks (from the latest to the oldest one).
+    let mut expected_shard = shard;
+    let mut expected_seqno = descr.seqno;
+    for (i, shard_block) in shard_blocks.iter().enumerate() {
+        let info = shard_block.parse::<M::Block>()?.load_info()?;
+        if info.shard() != &expected_shard
+            || info.seqno() != expected_seqno
+            || !expected_shard.contains_account(&account.address)
+        {
+            return Err(Error::InvalidData);
+        }
+
+        // The target block has no links to check.
+        let Some(prev_block) = shard_blocks.get(i + 1) else {
+            break;
+        };
+
+        // NOTE: Shard can change here in case of split or merge.
+        let prev = info.load_prev_block_id(&account.address)?;
+        if prev_block.hash(0) != &prev.root_hash {
+            return Err(Error::InvalidData);
+        }
+        expected_shard = prev.shard;
+        expected_seqno = prev.seqno;
+    }
+
+    Ok(())
+}
+
+/// Light client for the chain of masterchain key blocks.
+///
+/// Starts from a trusted validator set and accepts key blocks one by one.
+/// Each key block must be signed by the current validator set and must
+/// reference the last accepted key block. The new current validator set
+/// is taken from the config param 34 of the accepted key block.
+pub struct KeyBlockChainVerifier<M> {
+    vset: ValidatorSet,
+    signature_id: Option<i32>,
+    last_key_block_id: Option<BlockId>,
+    _models: PhantomData<M>,
+}
+
+impl<M> KeyBlockChainVerifier<M>
+where
+    M: BlockchainModels,
+{
+    /// Creates a verifier with only a trusted validator set.
+    ///
+    /// NOTE: Any key block signed by this set will be accepted first.
+    pub fn new(vset: ValidatorSet, signature_id: Option<i32>) -> Self {
+        Self {
+            vset,
+            signature_id,
+            last_key_block_id: None,
+            _models: PhantomData,
+        }
+    }
+
+    /// Creates a verifier with a trusted key block (or zerostate)
+    /// and its current validator set.
+    pub fn with_key_block(
+        key_block_id: BlockId,
+        vset: ValidatorSet,
+        signature_id: Option<i32>,
+    ) -> Self {
+        Self {
+            vset,
+            signature_id,
+            last_key_block_id: Some(key_block_id),
+            _models: PhantomData,
+        }
+    }
+
+    pub fn current_vset(&self) -> &ValidatorSet {
+        &self.vset
+    }
+
+    pub fn last_key_block_id(&self) -> Option<&BlockId> {
+        self.last_key_block_id.as_ref()
+    }
+
+    pub fn signature_id(&self) -> Option<i32> {
+        self.signature_id
+    }
+
+    /// Updates the signature id (e.g. after the capabilities change).
+    pub fn set_signature_id(&mut self, signature_id: Option<i32>) {
+        self.signature_id = signature_id;
+    }
+
+    /// Verifies the next key block and updates the current validator set.
+    ///
+    /// Block root can be a key block proof built by [`make_key_block_proof`].
+    /// Accepting the last key block again is a no-op.
+    ///
+    /// [`make_key_block_proof`]: crate::block::make_key_block_proof
+    pub fn accept<I>(
+        &mut self,
+        block_id: &BlockId,
+        block_root: &Cell,
+        signatures: I,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Result<BlockSignature, Error>>,
+    {
+        if !block_id.is_masterchain() || block_root.hash(0) != &block_id.root_hash {
+            return Err(Error::InvalidData);
+        }
+
+        let block = block_root.parse::<M::Block>()?;
+        let info = block.load_info()?;
+        if !info.is_key_block() || info.seqno() != block_id.seqno {
+            return Err(Error::InvalidData);
+        }
+
+        if let Some(last) = &self.last_key_block_id {
+            match block_id.seqno.cmp(&last.seqno) {
+                // Older key blocks are not allowed.
+                std::cmp::Ordering::Less => return Err(Error::InvalidData),
+                // Same seqno must be the same block.
+                std::cmp::Ordering::Equal if block_id == last => return Ok(()),
+                std::cmp::Ordering::Equal => return Err(Error::InvalidData),
+                // Key blocks must be accepted without gaps.
+                std::cmp::Ordering::Greater if info.prev_key_block_seqno() != last.seqno => {
+                    return Err(Error::InvalidData);
+                }
+                std::cmp::Ordering::Greater => {}
+            }
+        }
+
+        // Key block must be signed by the current validator set.
+        check_signatures(block_id, signatures, &self.vset, self.signature_id)?;
+
+        let vset = block
+            .load_extra()?
+            .load_custom()?
+            .ok_or(Error::CellUnderflow)?
+            .config()
+            .ok_or(Error::CellUnderflow)?
+            .get_current_validator_set()?;
+        if vset.utime_since < self.vset.utime_since {
+            return Err(Error::InvalidData);
+        }
+
+        self.vset = vset;
+        self.last_key_block_id = Some(*block_id);
+        Ok(())
+    }
+}
+
+fn check_signatures_dict(
+    block_id: &BlockId,
+    signatures: &Cell,
+    vset_utime_since: u32,
+    vset: TrustedVset<'_>,
+    signature_id: Option<i32>,
+) -> Result<(), Error> {
+    let signatures = Dict::<u16, PlainSignature>::from_raw(Some(signatures.clone()));
+    let to_sign = build_data_for_sign(block_id, signature_id);
+
+    match vset {
+        TrustedVset::Full(vset) => {
+            let mut weight = 0u64;
+            if vset.utime_since != vset_utime_since {
+                return Err(Error::InvalidData);
+            }
+
+            for item in signatures.iter() {
+                let (idx, PlainSignature(signature)) = item?;
+                let node = vset.list.get(idx as usize).ok_or(Error::InvalidData)?;
+                if !verify_signature(&node.public_key, &to_sign, &signature) {
+                    return Err(Error::InvalidSignature);
+                }
+                weight = weight.checked_add(node.weight).ok_or(Error::IntOverflow)?;
+            }
+
+            check_signatures_weight(weight, vset)
+        }
+        TrustedVset::EpochData(epoch_data) => {
+            let epoch_data = epoch_data.parse::<EpochData>()?;
+            check_epoch_data_signatures(&to_sign, &signatures, vset_utime_since, &epoch_data)
+        }
+    }
+}
+
+/// Checks a signatures dict built by [`prepare_signatures`] against
+/// the epoch data with the same rules as the bridge contract:
+/// - epoch data must be for the same validator set;
+/// - each signature must be from a main validator;
+/// - each signature must be valid;
+/// - total signatures weight must reach the cutoff weight.
+///
+/// [`prepare_signatures`]: crate::block::prepare_signatures
+pub fn check_signatures_with_epoch_data(
+    block_id: &BlockId,
+    signatures: &Cell,
+    vset_utime_since: u32,
+    epoch_data: &EpochData,
+    signature_id: Option<i32>,
+) -> Result<(), Error> {
+    let signatures = Dict::<u16, PlainSignature>::from_raw(Some(signatures.clone()));
+    let to_sign = build_data_for_sign(block_id, signature_id);
+    check_epoch_data_signatures(&to_sign, &signatures, vset_utime_since, epoch_data)
+}
+
+fn check_epoch_data_signatures(
+    to_sign: &[u8],
+    signatures: &Dict<u16, PlainSignature>,
+    vset_utime_since: u32,
+    epoch_data: &EpochData,
+) -> Result<(), Error> {
+    if epoch_data.utime_since != vset_utime_since {
+        return Err(Error::InvalidData);
+    }
+
+    // NOTE: Weights are summed without overflow as in the contract.
+    let mut weight = 0u128;
+    for item in signatures.iter() {
+        let (idx, PlainSignature(signature)) = item?;
+        let (public_key, node_weight) =
+            epoch_data.validators.get(idx)?.ok_or(Error::InvalidData)?;
+        if !verify_signature(&public_key, to_sign, &signature) {
+            return Err(Error::InvalidSignature);
+        }
+        weight += node_weight as u128;
+    }
+
+    // Check that signature weight is enough.
+    if weight >= epoch_data.cutoff_weight.into_inner() {
+        Ok(())
+    } else {
+        Err(Error::InvalidData)
+    }
+}
+
+fn verify_signature(public_key: &HashBytes, data: &[u8], signature: &[u8; 64]) -> bool {
+    match tycho_crypto::ed25519::PublicKey::from_bytes(public_key.0) {
+        Some(public_key) => public_key.verify_raw(data, signature),
+        None => false,
+    }
+}
+
+fn find_tx_hash<M>(block_root: &Cell, account: &HashBytes, lt: u64) -> Result<HashBytes, Error>
+where
+    M: BlockchainModels,
+{
+    let block = block_root.parse::<M::Block>()?;
+    let account_blocks = block.load_extra()?.load_account_blocks()?;
+
+    let (_, account_block) = account_blocks.get(account)?.ok_or(Error::InvalidData)?;
+
+    let (transactions, _) = account_block.transactions.into_parts();
+    let (_, tx) = transactions.get(lt)?.ok_or(Error::InvalidData)?;
+
+    // NOTE: Transaction cell is usually pruned so we use its original hash.
+    Ok(*tx.hash(0))
+}
@@ -3,6 +3,7 @@ use std::borrow::Cow;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
+use tycho_types::cell::HashBytes;
 use tycho_types::models::{StdAddr, StdAddrFormat};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +40,53 @@ impl schemars::JsonSchema for TonAddr {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxHash(pub HashBytes);
+
+#[cfg(feature = "api")]
+impl schemars::JsonSchema for TxHash {
+    fn schema_name() -> Cow<'static, str> {
+        Cow::Borrowed("Transaction hash")
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        hash_json_schema(generator, "Transaction hash as hex")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MsgHash(pub HashBytes);
+
+#[cfg(feature = "api")]
+impl schemars::JsonSchema for MsgHash {
+    fn schema_name() -> Cow<'static, str> {
+        Cow::Borrowed("Message hash")
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        hash_json_schema(generator, "Message hash as hex")
+    }
+}
+
+#[cfg(feature = "api")]
+fn hash_json_schema(
+    generator: &mut schemars::SchemaGenerator,
+    description: &'static str,
+) -> schemars::Schema {
+    let mut schema = generator.subschema_for::<String>();
+    let object = schema.ensure_object();
+    object.insert("description".into(), description.into());
+    object.insert("format".into(), "[0-9a-fA-F]{64}".into());
+    object.insert(
+        "examples".into(),
+        vec![serde_json::json!(
+            "3333333333333333333333333333333333333333333333333333333333333333"
+        )]
+        .into(),
+    );
+    schema
+}
+
 pub mod ton_address {
     use tycho_types::models::{StdAddr, StdAddrBase64Repr};
 